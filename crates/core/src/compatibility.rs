@@ -139,6 +139,7 @@ fn build_ods(object_id: u16, width: u16, height: u16, rle: &[u8]) -> Vec<u8> {
 }
 
 /// Build a minimal valid single-cue PGS (.sup) binary.
+#[allow(clippy::too_many_arguments)]
 fn build_pgs_sup(
     pts: u32,
     screen_w: u16,
@@ -245,6 +246,11 @@ fn build_owned_packet(
         height,
         color_indices,
         alpha_values,
+        forced: false,
+        stream_id: 0x20,
+        color_controls: Vec::new(),
+        control_sequences: Vec::new(),
+        sub_cues: Vec::new(),
         packet_data: SubtitlePacketData::Owned(owned),
         even_field_range: 0..even_end,
         odd_field_range: even_end..odd_end,
@@ -308,7 +314,7 @@ fn build_mks_with_track(
     payload: Option<&[u8]>,
 ) -> Vec<u8> {
     let ebml_header = ebml_element(0x1a45_dfa3, &ebml_element(0x4282, b"matroska"));
-    let info = ebml_element(0x1549_a966, &ebml_element(0x2ad7_b1, &[0x0f, 0x42, 0x40]));
+    let info = ebml_element(0x1549_a966, &ebml_element(0x002a_d7b1, &[0x0f, 0x42, 0x40]));
 
     let mut track_children = Vec::new();
     track_children.extend_from_slice(&ebml_element(0xd7, &[0x01]));
@@ -317,7 +323,7 @@ fn build_mks_with_track(
     if !codec_private.is_empty() {
         track_children.extend_from_slice(&ebml_element(0x63a2, codec_private));
     }
-    track_children.extend_from_slice(&ebml_element(0x22b5_9c, language.as_bytes()));
+    track_children.extend_from_slice(&ebml_element(0x0022_b59c, language.as_bytes()));
     let tracks = ebml_element(0x1654_ae6b, &ebml_element(0xae, &track_children));
 
     let mut segment_payload = Vec::new();
@@ -8,8 +8,9 @@ use super::rle::{ObjectField, decode_object_field};
 use super::segment::{
     CLUT_DEFINITION, DISPLAY_DEFINITION, DisplayDefinition, END_OF_DISPLAY_SET, OBJECT_DATA,
     PAGE_COMPOSITION, PAGE_STATE_ACQUISITION, PAGE_STATE_MODE_CHANGE, PageComposition,
-    REGION_COMPOSITION, RegionComposition,
+    REGION_COMPOSITION, RegionComposition, RegionObjectRef,
 };
+use super::text::{decode_character_codes, draw_text};
 use super::{DEFAULT_SCREEN_HEIGHT, DEFAULT_SCREEN_WIDTH, MAX_DVB_BITMAP_PIXELS};
 
 #[derive(Debug, Clone)]
@@ -21,7 +22,7 @@ struct Region {
     clut_id: u8,
     bgcolor: u8,
     pixels: Vec<u8>,
-    objects: Vec<(u16, u16, u16)>, // object_id, x, y
+    objects: Vec<RegionObjectRef>,
 }
 
 #[derive(Debug, Clone)]
@@ -29,6 +30,8 @@ struct ObjectPlacement {
     region_id: u8,
     x: u16,
     y: u16,
+    foreground: u8,
+    background: u8,
 }
 
 #[derive(Debug, Clone)]
@@ -40,6 +43,18 @@ pub struct DvbComposition {
     pub rgba: Vec<u8>,
 }
 
+/// Text of a character-coded object, positioned on the screen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DvbTextRun {
+    pub x: u16,
+    pub y: u16,
+    pub text: String,
+    /// Packed RGBA of the region's foreground pixel code
+    pub foreground: u32,
+    /// Packed RGBA of the region's background pixel code
+    pub background: u32,
+}
+
 #[derive(Debug, Clone)]
 pub struct DvbFrame {
     pub width: u16,
@@ -57,14 +72,21 @@ pub struct DisplayCue {
     pub screen_height: u16,
     /// Snapshot of composed frame at this cue (None = clear screen).
     pub frame: Option<DvbFrame>,
+    /// Character-coded objects shown at this cue, in page region order.
+    pub text_runs: Vec<DvbTextRun>,
 }
 
 pub struct DvbContext {
     regions: HashMap<u8, Region>,
     cluts: HashMap<u8, Clut>,
     object_placements: HashMap<u16, Vec<ObjectPlacement>>,
+    object_texts: HashMap<u16, String>,
     page: Option<PageComposition>,
     display_definition: Option<DisplayDefinition>,
+    /// Selected (composition, ancillary) page ids; `None` accepts every page.
+    page_ids: Option<(u16, u16)>,
+    /// Draw character-coded objects into region bitmaps with the built-in font.
+    render_text: bool,
 }
 
 impl DvbContext {
@@ -73,8 +95,37 @@ impl DvbContext {
             regions: HashMap::new(),
             cluts: HashMap::new(),
             object_placements: HashMap::new(),
+            object_texts: HashMap::new(),
             page: None,
             display_definition: None,
+            page_ids: None,
+            render_text: false,
+        }
+    }
+
+    /// Restrict decoding to one subtitle service. The selection survives
+    /// `reset`.
+    pub fn set_page_ids(&mut self, page_ids: Option<(u16, u16)>) {
+        self.page_ids = page_ids;
+    }
+
+    /// Draw character-coded objects into the region bitmaps. The setting
+    /// survives `reset`.
+    pub fn set_render_text(&mut self, render_text: bool) {
+        self.render_text = render_text;
+    }
+
+    /// Segments of the composition page are always decoded. The ancillary
+    /// page only contributes CLUTs and objects shared between services
+    /// (EN 300 743).
+    fn accepts_segment(&self, page_id: u16, segment_type: u8) -> bool {
+        match self.page_ids {
+            None => true,
+            Some((composition, ancillary)) => {
+                page_id == composition
+                    || (page_id == ancillary
+                        && matches!(segment_type, CLUT_DEFINITION | OBJECT_DATA))
+            }
         }
     }
 
@@ -82,6 +133,7 @@ impl DvbContext {
         self.regions.clear();
         self.cluts.clear();
         self.object_placements.clear();
+        self.object_texts.clear();
         self.page = None;
         self.display_definition = None;
     }
@@ -99,6 +151,9 @@ impl DvbContext {
         let mut saw_eds = false;
 
         for segment in segments {
+            if !self.accepts_segment(segment.page_id, segment.segment_type) {
+                continue;
+            }
             match segment.segment_type {
                 PAGE_COMPOSITION => {
                     if let Some(page) = PageComposition::parse(segment.data) {
@@ -106,6 +161,7 @@ impl DvbContext {
                             self.regions.clear();
                             self.cluts.clear();
                             self.object_placements.clear();
+                            self.object_texts.clear();
                         }
                         self.page = Some(page);
                     }
@@ -136,6 +192,7 @@ impl DvbContext {
                 }
                 END_OF_DISPLAY_SET => {
                     saw_eds = true;
+                    break;
                 }
                 _ => {}
             }
@@ -170,8 +227,8 @@ impl DvbContext {
         });
 
         // Drop old object placements for this region.
-        for (object_id, _, _) in &region.objects {
-            if let Some(list) = self.object_placements.get_mut(object_id) {
+        for object in &region.objects {
+            if let Some(list) = self.object_placements.get_mut(&object.object_id) {
                 list.retain(|placement| placement.region_id != rcs.region_id);
             }
         }
@@ -193,7 +250,6 @@ impl DvbContext {
         region.objects.clear();
 
         for object in rcs.objects {
-            region.objects.push((object.object_id, object.x, object.y));
             self.object_placements
                 .entry(object.object_id)
                 .or_default()
@@ -201,12 +257,15 @@ impl DvbContext {
                     region_id: rcs.region_id,
                     x: object.x,
                     y: object.y,
+                    foreground: object.foreground,
+                    background: object.background,
                 });
+            region.objects.push(object);
         }
     }
 
     fn apply_object(&mut self, data: &[u8]) {
-        if data.len() < 7 {
+        if data.len() < 4 {
             return;
         }
 
@@ -214,8 +273,10 @@ impl DvbContext {
         let coding_method = (data[2] >> 2) & 0x03;
         let non_mod = ((data[2] >> 1) & 0x01) != 0;
 
-        if coding_method != 0 {
-            return;
+        match coding_method {
+            0 if data.len() >= 7 => {}
+            1 => return self.apply_character_object(object_id, &data[3..]),
+            _ => return,
         }
 
         let top_field_len = u16::from_be_bytes([data[3], data[4]]) as usize;
@@ -277,6 +338,84 @@ impl DvbContext {
         }
     }
 
+    /// Decode a string of character codes (`number_of_codes` followed by
+    /// 16-bit codes) and optionally draw it at each placement.
+    fn apply_character_object(&mut self, object_id: u16, data: &[u8]) {
+        let Some((&number_of_codes, codes)) = data.split_first() else {
+            return;
+        };
+        let codes: Vec<u16> = codes
+            .chunks_exact(2)
+            .take(number_of_codes as usize)
+            .map(|code| u16::from_be_bytes([code[0], code[1]]))
+            .collect();
+        let text = decode_character_codes(&codes);
+
+        if self.render_text {
+            for placement in self.object_placements.get(&object_id).into_iter().flatten() {
+                let Some(region) = self.regions.get_mut(&placement.region_id) else {
+                    continue;
+                };
+                draw_text(
+                    &mut region.pixels,
+                    region.width as usize,
+                    region.height as usize,
+                    (placement.x as usize, placement.y as usize),
+                    &text,
+                    placement.foreground,
+                    placement.background,
+                );
+            }
+        }
+
+        self.object_texts.insert(object_id, text);
+    }
+
+    fn text_runs(&self, page: &PageComposition) -> Vec<DvbTextRun> {
+        let (window_x, window_y) = self
+            .display_definition
+            .as_ref()
+            .map_or((0, 0), |dds| (dds.window_x, dds.window_y));
+        let mut runs = Vec::new();
+
+        for region_ref in &page.regions {
+            let Some(region) = self.regions.get(&region_ref.region_id) else {
+                continue;
+            };
+            let clut = self
+                .cluts
+                .get(&region.clut_id)
+                .cloned()
+                .unwrap_or_else(|| Clut::default_clut(region.clut_id));
+            let palette = clut.entries_for_depth(region.depth);
+
+            for object in &region.objects {
+                let Some(text) = self.object_texts.get(&object.object_id) else {
+                    continue;
+                };
+                runs.push(DvbTextRun {
+                    x: window_x
+                        .saturating_add(region_ref.x)
+                        .saturating_add(object.x),
+                    y: window_y
+                        .saturating_add(region_ref.y)
+                        .saturating_add(object.y),
+                    text: text.clone(),
+                    foreground: palette
+                        .get(object.foreground as usize)
+                        .copied()
+                        .unwrap_or(0),
+                    background: palette
+                        .get(object.background as usize)
+                        .copied()
+                        .unwrap_or(0),
+                });
+            }
+        }
+
+        runs
+    }
+
     fn compose_cue(&self, pts_ms: u32) -> DisplayCue {
         let (screen_width, screen_height) = self.screen_size();
         let Some(page) = self.page.as_ref() else {
@@ -288,6 +427,7 @@ impl DvbContext {
                 screen_width,
                 screen_height,
                 frame: None,
+                text_runs: Vec::new(),
             };
         };
 
@@ -302,6 +442,7 @@ impl DvbContext {
                 screen_width,
                 screen_height,
                 frame: None,
+                text_runs: Vec::new(),
             };
         }
 
@@ -390,6 +531,7 @@ impl DvbContext {
             screen_width,
             screen_height,
             frame,
+            text_runs: self.text_runs(page),
         }
     }
 }
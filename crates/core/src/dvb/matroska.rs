@@ -0,0 +1,86 @@
+//! DVB subtitle extraction from Matroska `S_DVBSUB` tracks.
+//!
+//! Blocks carry the PES data field of one display set (with or without the
+//! data identifier bytes). CodecPrivate holds the service's page ids.
+
+use super::TimedPayload;
+use crate::mkv::{MATROSKA_CODEC_DVBSUB, MatroskaTrack, parse_matroska_segment};
+
+/// A DVB subtitling service: the fields of a subtitling_descriptor entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DvbSubtitleService {
+    /// ISO 639 language code
+    pub language: Option<String>,
+    /// Subtitling type (e.g. 0x10 normal, 0x20 hard of hearing)
+    pub subtitling_type: u8,
+    /// Page carrying the service's compositions
+    pub composition_page_id: u16,
+    /// Page carrying CLUTs and objects shared between services
+    pub ancillary_page_id: u16,
+}
+
+/// Parse `S_DVBSUB` CodecPrivate: composition page id, ancillary page id and
+/// subtitling type.
+pub fn parse_dvbsub_codec_private(codec_private: &[u8]) -> Option<DvbSubtitleService> {
+    let bytes: &[u8; 5] = codec_private.get(..5)?.try_into().ok()?;
+    Some(DvbSubtitleService {
+        language: None,
+        subtitling_type: bytes[4],
+        composition_page_id: u16::from_be_bytes([bytes[0], bytes[1]]),
+        ancillary_page_id: u16::from_be_bytes([bytes[2], bytes[3]]),
+    })
+}
+
+/// Extract a DVB track from Matroska data as timed payloads, with its
+/// service parameters. Uses the first DVB track when `track_number` is `None`.
+pub fn extract_dvb_from_mks(
+    data: &[u8],
+    track_number: Option<u64>,
+) -> Result<(Vec<TimedPayload>, Option<DvbSubtitleService>), String> {
+    let segment = parse_matroska_segment(data)?;
+    let track = segment.select_track(MATROSKA_CODEC_DVBSUB, track_number)?;
+    let service = track_service(track);
+
+    let mut frames = segment.read_frames(data, track)?;
+    frames.sort_by_key(|frame| frame.timestamp_ms);
+    let units = frames
+        .iter()
+        .map(|frame| TimedPayload {
+            pts_ms: frame.timestamp_ms,
+            payload: frame.payload(data).to_vec(),
+        })
+        .collect();
+    Ok((units, service))
+}
+
+/// Service parameters of an `S_DVBSUB` track, from its CodecPrivate and
+/// language.
+pub(super) fn track_service(track: &MatroskaTrack) -> Option<DvbSubtitleService> {
+    track
+        .codec_private
+        .as_deref()
+        .and_then(parse_dvbsub_codec_private)
+        .map(|service| DvbSubtitleService {
+            language: track.preferred_language().map(str::to_string),
+            ..service
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_codec_private_page_ids() {
+        assert_eq!(
+            parse_dvbsub_codec_private(&[0x00, 0x02, 0x00, 0x03, 0x20]),
+            Some(DvbSubtitleService {
+                language: None,
+                subtitling_type: 0x20,
+                composition_page_id: 2,
+                ancillary_page_id: 3,
+            })
+        );
+        assert_eq!(parse_dvbsub_codec_private(&[0x00, 0x02]), None);
+    }
+}
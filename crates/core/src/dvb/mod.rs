@@ -4,10 +4,13 @@
 
 mod clut;
 mod context;
+mod matroska;
 mod parser;
 mod pes;
 mod rle;
 mod segment;
+mod text;
+mod transport;
 
 pub(crate) const MAX_DVB_BITMAP_PIXELS: usize = 16_777_216;
 pub(crate) const DEFAULT_SCREEN_WIDTH: u16 = 720;
@@ -15,7 +18,10 @@ pub(crate) const DEFAULT_SCREEN_HEIGHT: u16 = 576;
 
 pub use clut::*;
 pub use context::*;
+pub use matroska::*;
 pub use parser::*;
 pub use pes::*;
 pub use rle::*;
 pub use segment::*;
+pub use text::*;
+pub use transport::*;
@@ -1,7 +1,12 @@
 //! High-level DVB subtitle parser API (PGS-like surface).
 
-use super::context::{DisplayCue, DvbComposition, DvbContext, DvbFrame};
+use std::io::{Read, Seek};
+
+use super::context::{DisplayCue, DvbComposition, DvbContext, DvbFrame, DvbTextRun};
+use super::matroska::{DvbSubtitleService, extract_dvb_from_mks, track_service};
 use super::pes::{TimedPayload, parse_timed_stream};
+use super::transport::{extract_dvb_from_ts, list_dvb_streams};
+use crate::mkv::{MATROSKA_CODEC_DVBSUB, MatroskaReader};
 use crate::utils::binary_search_timestamp;
 
 const MAX_PENDING_BYTES: usize = 32 * 1024 * 1024;
@@ -15,6 +20,9 @@ pub struct DvbParser {
     last_render_issue: Option<String>,
     screen_width: u16,
     screen_height: u16,
+    service: Option<DvbSubtitleService>,
+    page_ids: Option<(u16, u16)>,
+    continuity_errors: usize,
 }
 
 impl DvbParser {
@@ -27,17 +35,49 @@ impl DvbParser {
             last_render_issue: None,
             screen_width: super::DEFAULT_SCREEN_WIDTH,
             screen_height: super::DEFAULT_SCREEN_HEIGHT,
+            service: None,
+            page_ids: None,
+            continuity_errors: 0,
         }
     }
 
+    /// Decode only the subtitle service on `composition_page_id`, sharing
+    /// CLUTs and objects from `ancillary_page_id`. Segments for other pages
+    /// are dropped. Pass the composition page id twice when the service has
+    /// no ancillary page.
+    pub fn set_page_ids(&mut self, composition_page_id: u16, ancillary_page_id: u16) {
+        self.page_ids = Some((composition_page_id, ancillary_page_id));
+        self.context.set_page_ids(self.page_ids);
+    }
+
+    /// Decode every page again, or follow the loaded service's page ids.
+    pub fn clear_page_ids(&mut self) {
+        self.page_ids = None;
+        self.context.set_page_ids(None);
+    }
+
+    /// Also draw character-coded objects into the cue bitmaps with the
+    /// built-in fixed font. Applies to data parsed afterwards.
+    pub fn set_render_text(&mut self, render_text: bool) {
+        self.context.set_render_text(render_text);
+    }
+
+    /// The page ids set with `set_page_ids`.
+    pub fn page_ids(&self) -> Option<(u16, u16)> {
+        self.page_ids
+    }
+
     pub fn reset(&mut self) {
         self.cues.clear();
         self.timestamps_ms.clear();
         self.pending.clear();
         self.context.reset();
+        self.context.set_page_ids(self.page_ids);
         self.last_render_issue = None;
         self.screen_width = super::DEFAULT_SCREEN_WIDTH;
         self.screen_height = super::DEFAULT_SCREEN_HEIGHT;
+        self.service = None;
+        self.continuity_errors = 0;
     }
 
     /// Parse a complete DVB dump (`"DV"` framed and/or MPEG PES).
@@ -48,6 +88,135 @@ impl DvbParser {
         self.cues.len()
     }
 
+    /// Parse an `S_DVBSUB` track from Matroska/MKS data.
+    /// Uses the first DVB track when `track_number` is `None`.
+    pub fn parse_matroska(
+        &mut self,
+        data: &[u8],
+        track_number: Option<u64>,
+    ) -> Result<usize, String> {
+        let (units, service) = extract_dvb_from_mks(data, track_number)?;
+        self.reset();
+        self.select_service(service);
+        self.ingest_units(units);
+        Ok(self.cues.len())
+    }
+
+    /// Parse an `S_DVBSUB` track from a seekable Matroska source, reading
+    /// only the headers, Cues and the track's blocks. Uses the first DVB
+    /// track when `track_number` is `None`.
+    pub fn parse_matroska_reader<R: Read + Seek>(
+        &mut self,
+        source: R,
+        track_number: Option<u64>,
+    ) -> Result<usize, String> {
+        let mut reader = MatroskaReader::open(source)?;
+        reader.select_track(MATROSKA_CODEC_DVBSUB, track_number)?;
+        self.start_matroska_reader(&reader)?;
+        while self.read_matroska_next(&mut reader)?.is_some() {}
+        Ok(self.cues.len())
+    }
+
+    /// Clear the parser and select the service of the reader's selected DVB
+    /// track, before loading it with `read_matroska_next`.
+    pub fn start_matroska_reader<R: Read + Seek>(
+        &mut self,
+        reader: &MatroskaReader<R>,
+    ) -> Result<(), String> {
+        match reader.selected_track() {
+            Some(track) if track.codec_id == MATROSKA_CODEC_DVBSUB => {
+                let service = track_service(track);
+                self.reset();
+                self.select_service(service);
+                Ok(())
+            }
+            Some(_) => Err("Selected Matroska track is not a DVB track".to_string()),
+            None => Err("No Matroska track selected".to_string()),
+        }
+    }
+
+    /// Read the next cluster of the reader's selected track and decode its
+    /// display sets, so cues are available while the file is still being
+    /// read. Returns the number of new cues, or `None` once the track is
+    /// complete.
+    pub fn read_matroska_next<R: Read + Seek>(
+        &mut self,
+        reader: &mut MatroskaReader<R>,
+    ) -> Result<Option<usize>, String> {
+        let read = reader.frames().len();
+        if reader.read_next()?.is_none() {
+            return Ok(None);
+        }
+
+        let before = self.cues.len();
+        let units = reader.frames()[read..]
+            .iter()
+            .map(|frame| TimedPayload {
+                pts_ms: frame.timestamp_ms,
+                payload: frame.payload(&[]).to_vec(),
+            })
+            .collect();
+        self.ingest_units(units);
+        Ok(Some(self.cues.len() - before))
+    }
+
+    /// Parse a DVB subtitle stream from an MPEG transport stream.
+    /// Uses the first stream with a subtitling descriptor when `pid` is `None`.
+    pub fn parse_transport_stream(
+        &mut self,
+        data: &[u8],
+        pid: Option<u16>,
+    ) -> Result<usize, String> {
+        let streams = list_dvb_streams(data);
+        let pid = pid
+            .or_else(|| streams.first().map(|stream| stream.pid))
+            .ok_or_else(|| "No DVB subtitle stream found in transport stream".to_string())?;
+        let services = streams
+            .into_iter()
+            .find(|stream| stream.pid == pid)
+            .map(|stream| stream.services)
+            .unwrap_or_default();
+        // A PID can multiplex several services; prefer the configured one
+        let service = match self.page_ids {
+            Some((composition_page_id, _)) => services
+                .into_iter()
+                .find(|service| service.composition_page_id == composition_page_id),
+            None => services.into_iter().next(),
+        };
+
+        let extracted = extract_dvb_from_ts(data, pid);
+        self.reset();
+        self.select_service(service);
+        self.ingest_units(extracted.units);
+        self.continuity_errors = extracted.continuity_errors;
+        Ok(self.cues.len())
+    }
+
+    /// TS packets lost or out of order on the subtitle PID in the last
+    /// `parse_transport_stream`.
+    pub fn continuity_errors(&self) -> usize {
+        self.continuity_errors
+    }
+
+    /// Remember the loaded service and, unless page ids were set explicitly,
+    /// decode only its pages.
+    fn select_service(&mut self, service: Option<DvbSubtitleService>) {
+        if self.page_ids.is_none()
+            && let Some(service) = &service
+        {
+            self.context.set_page_ids(Some((
+                service.composition_page_id,
+                service.ancillary_page_id,
+            )));
+        }
+        self.service = service;
+    }
+
+    /// Service parameters of the loaded stream, when the container provides them.
+    pub fn service(&self) -> Option<&DvbSubtitleService> {
+        self.service.as_ref()
+    }
+
     pub fn feed(&mut self, chunk: &[u8]) -> usize {
         if chunk.is_empty() && self.pending.is_empty() {
             return 0;
@@ -177,6 +346,35 @@ impl DvbParser {
         self.cues.get(index).map_or(-1, |cue| cue.page_state as i32)
     }
 
+    /// Get the character-coded text runs of a cue.
+    pub fn get_cue_text_runs(&self, index: usize) -> Option<&[DvbTextRun]> {
+        self.cues.get(index).map(|cue| cue.text_runs.as_slice())
+    }
+
+    /// Get the plain text of a cue's character-coded objects, one run per line.
+    pub fn get_cue_text(&self, index: usize) -> Option<String> {
+        let runs = self.get_cue_text_runs(index)?;
+        Some(
+            runs.iter()
+                .map(|run| run.text.as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )
+    }
+
+    /// Find the cues whose character-coded text contains `query`
+    /// (case-insensitive).
+    pub fn search(&self, query: &str) -> Vec<u32> {
+        let query = query.to_lowercase();
+        (0..self.cues.len())
+            .filter(|&index| {
+                self.get_cue_text(index)
+                    .is_some_and(|text| text.to_lowercase().contains(&query))
+            })
+            .map(|index| index as u32)
+            .collect()
+    }
+
     pub fn last_render_issue(&self) -> String {
         self.last_render_issue.clone().unwrap_or_default()
     }
@@ -246,9 +444,19 @@ mod tests {
     }
 
     fn build_simple_display_set() -> Vec<u8> {
-        // Page: timeout 5s, version 0, mode-change, region 1 at (10, 20)
+        let mut payload = vec![0x20, 0x00];
+        payload.extend(display_set_segments(1, 1, 10));
+        payload.push(0xFF);
+        payload
+    }
+
+    /// Display set on `page_id` whose object is sent on `object_page_id`.
+    fn display_set_segments(page_id: u16, object_page_id: u16, region_x: u16) -> Vec<u8> {
+        // Page: timeout 5s, version 0, mode-change, region 1 at (region_x, 20)
         let mut page = vec![0x05, 0x08]; // state = mode-change (bits 3:2 = 10)
-        page.extend_from_slice(&[0x01, 0x00, 0x00, 0x0A, 0x00, 0x14]);
+        page.extend_from_slice(&[0x01, 0x00]);
+        page.extend_from_slice(&region_x.to_be_bytes());
+        page.extend_from_slice(&[0x00, 0x14]);
 
         // Region 1: 4x2, depth 8 (encoded as 3 << 2), clut 0, fill, no objects initially then object
         // depth field: bits 5-3 = region_depth; FFmpeg uses (buf[6] >> 2) & 7
@@ -280,13 +488,11 @@ mod tests {
         object.extend_from_slice(&0u16.to_be_bytes()); // bottom field len 0 => duplicate
         object.extend_from_slice(&top_field);
 
-        let mut payload = vec![0x20, 0x00];
-        payload.extend(segment(PAGE_COMPOSITION, 1, &page));
-        payload.extend(segment(REGION_COMPOSITION, 1, &region));
-        payload.extend(segment(OBJECT_DATA, 1, &object));
-        payload.extend(segment(END_OF_DISPLAY_SET, 1, &[]));
-        payload.push(0xFF);
-        payload
+        let mut segments = segment(PAGE_COMPOSITION, page_id, &page);
+        segments.extend(segment(REGION_COMPOSITION, page_id, &region));
+        segments.extend(segment(OBJECT_DATA, object_page_id, &object));
+        segments.extend(segment(END_OF_DISPLAY_SET, page_id, &[]));
+        segments
     }
 
     fn build_page_only(state: u8, with_region: bool) -> Vec<u8> {
@@ -322,6 +528,190 @@ mod tests {
         assert_eq!(frame.compositions[0].height, 2);
     }
 
+    #[test]
+    fn page_ids_select_one_multiplexed_service() {
+        let mut payload = vec![0x20, 0x00];
+        payload.extend(display_set_segments(1, 3, 10));
+        payload.extend(display_set_segments(2, 2, 100));
+        payload.push(0xFF);
+        let data = encode_dv_frame(1000, &payload);
+
+        let mut reference = DvbParser::new();
+        reference.parse(&encode_dv_frame(1000, &build_simple_display_set()));
+        let expected = reference.render_at_index(0).expect("reference frame");
+
+        let mut parser = DvbParser::new();
+        parser.set_page_ids(1, 3);
+        assert_eq!(parser.parse(&data), 1);
+        let frame = parser.render_at_index(0).expect("page 1 frame");
+        assert_eq!(frame.compositions.len(), 1);
+        assert_eq!(frame.compositions[0].x, 10);
+        // The object arrives on the ancillary page
+        assert_eq!(frame.compositions[0].rgba, expected.compositions[0].rgba);
+
+        parser.set_page_ids(1, 1);
+        parser.parse(&data);
+        let frame = parser.render_at_index(0).expect("page 1 frame");
+        assert_ne!(frame.compositions[0].rgba, expected.compositions[0].rgba);
+
+        parser.set_page_ids(2, 2);
+        parser.parse(&data);
+        let frame = parser.render_at_index(0).expect("page 2 frame");
+        assert_eq!(frame.compositions.len(), 1);
+        assert_eq!(frame.compositions[0].x, 100);
+
+        parser.clear_page_ids();
+        assert_eq!(parser.page_ids(), None);
+    }
+
+    #[test]
+    fn character_coded_objects_become_text_runs() {
+        use crate::dvb::{Clut, TEXT_CELL_HEIGHT, TEXT_CELL_WIDTH};
+
+        let mut page = vec![0x05, 0x08];
+        page.extend_from_slice(&[0x01, 0x00, 0x00, 0x0A, 0x00, 0x14]);
+        let mut region = vec![0x01, 0x08];
+        region.extend_from_slice(&((2 * TEXT_CELL_WIDTH) as u16).to_be_bytes());
+        region.extend_from_slice(&(TEXT_CELL_HEIGHT as u16).to_be_bytes());
+        region.extend_from_slice(&[0x0C, 0x00, 0x00, 0x00]);
+        // Object 7: composite string at (0, 0), foreground 1, background 0
+        region.extend_from_slice(&[0x00, 0x07, 0x80, 0x00, 0x00, 0x00, 0x01, 0x00]);
+        // Coding method 1 with two character codes
+        let object = [0x00, 0x07, 0x04, 0x02, 0x00, b'H', 0x00, b'i'];
+
+        let mut payload = vec![0x20, 0x00];
+        payload.extend(segment(PAGE_COMPOSITION, 1, &page));
+        payload.extend(segment(REGION_COMPOSITION, 1, &region));
+        payload.extend(segment(OBJECT_DATA, 1, &object));
+        payload.extend(segment(END_OF_DISPLAY_SET, 1, &[]));
+        let data = encode_dv_frame(0, &payload);
+        let foreground = Clut::default_clut(0).entries_for_depth(8)[1];
+
+        let mut parser = DvbParser::new();
+        assert_eq!(parser.parse(&data), 1);
+        assert_eq!(parser.get_cue_text(0).as_deref(), Some("Hi"));
+        assert_eq!(parser.search("HI"), vec![0]);
+        let runs = parser.get_cue_text_runs(0).expect("runs");
+        assert_eq!((runs[0].x, runs[0].y), (10, 20));
+        assert_eq!(runs[0].foreground, foreground);
+        let frame = parser.render_at_index(0).expect("frame");
+        let has_foreground = |rgba: &[u8]| {
+            rgba.chunks_exact(4)
+                .any(|pixel| pixel == foreground.to_le_bytes())
+        };
+        assert!(!has_foreground(&frame.compositions[0].rgba));
+
+        parser.set_render_text(true);
+        parser.parse(&data);
+        let frame = parser.render_at_index(0).expect("frame");
+        assert!(has_foreground(&frame.compositions[0].rgba));
+    }
+
+    #[test]
+    fn parse_matroska_track() {
+        use crate::mkv::MATROSKA_CODEC_DVBSUB;
+        use crate::mkv::test_util::{build_mks, cluster, simple_block, track_entry};
+
+        let mks = build_mks(
+            &[track_entry(
+                3,
+                MATROSKA_CODEC_DVBSUB,
+                Some(&[0x00, 0x01, 0x00, 0x01, 0x10]),
+                "fin",
+                &[],
+            )],
+            &[cluster(
+                1500,
+                &[simple_block(3, 0, &build_simple_display_set())],
+            )],
+        );
+
+        let mut parser = DvbParser::new();
+        assert_eq!(parser.parse_matroska(&mks, None), Ok(1));
+        assert_eq!(parser.get_cue_start_time(0), 1500.0);
+        let service = parser.service().expect("service");
+        assert_eq!(service.language.as_deref(), Some("fin"));
+        assert_eq!(service.composition_page_id, 1);
+        assert_eq!(service.subtitling_type, 0x10);
+        assert!(parser.render_at_index(0).is_some());
+        assert!(parser.parse_matroska(&mks, Some(4)).is_err());
+
+        let mut parser = DvbParser::new();
+        let source = std::io::Cursor::new(mks);
+        assert_eq!(parser.parse_matroska_reader(source, None), Ok(1));
+        assert_eq!(parser.get_cue_start_time(0), 1500.0);
+        assert_eq!(
+            parser.service().map(|service| service.ancillary_page_id),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn parse_transport_stream_service() {
+        use crate::dvb::{DESCRIPTOR_DVB_SUBTITLING, PRIVATE_PES_STREAM_TYPE};
+        use crate::ts::{TS_PACKET_SIZE, TS_SYNC_BYTE};
+
+        let packetize = |pid: u16, payload: &[u8], counter: &mut u8| {
+            let mut out = Vec::new();
+            for (index, chunk) in payload.chunks(TS_PACKET_SIZE - 4).enumerate() {
+                let start = if index == 0 { 0x40 } else { 0x00 };
+                let mut packet = vec![
+                    TS_SYNC_BYTE,
+                    start | (pid >> 8) as u8,
+                    pid as u8,
+                    0x10 | (*counter & 0x0F),
+                ];
+                *counter = counter.wrapping_add(1);
+                packet.extend_from_slice(chunk);
+                packet.resize(TS_PACKET_SIZE, 0xFF);
+                out.extend(packet);
+            }
+            out
+        };
+        let psi_section = |table_id: u8, body: &[u8]| {
+            let mut section = vec![0x00, table_id, 0xB0, 0x00, 0x00, 0x01, 0xC1, 0x00, 0x00];
+            section.extend_from_slice(body);
+            section.extend_from_slice(&[0, 0, 0, 0]);
+            section[3] = (section.len() - 4) as u8;
+            section
+        };
+
+        let mut pmt = vec![0xE1, 0x00, 0xF0, 0x00];
+        pmt.extend_from_slice(&[PRIVATE_PES_STREAM_TYPE, 0xE0, 0x40, 0xF0, 0x0A]);
+        pmt.extend_from_slice(&[DESCRIPTOR_DVB_SUBTITLING, 0x08, b's', b'w', b'e', 0x10]);
+        pmt.extend_from_slice(&[0x00, 0x01, 0x00, 0x01]);
+
+        // PES with PTS 2s carrying the display set
+        let mut pes = vec![0x00, 0x00, 0x01, 0xBD, 0x00, 0x00, 0x80, 0x80, 0x05];
+        pes.extend_from_slice(&[0x21, 0x00, 0x0B, 0x7E, 0x41]);
+        pes.extend(build_simple_display_set());
+
+        let mut ts = packetize(0, &psi_section(0x00, &[0x00, 0x01, 0xE1, 0x00]), &mut 0);
+        ts.extend(packetize(0x100, &psi_section(0x02, &pmt), &mut 0));
+        ts.extend(packetize(0x40, &pes, &mut 0));
+
+        let streams = list_dvb_streams(&ts);
+        assert_eq!(streams.len(), 1);
+        assert_eq!(streams[0].pid, 0x40);
+
+        let mut parser = DvbParser::new();
+        assert_eq!(parser.parse_transport_stream(&ts, None), Ok(1));
+        assert_eq!(parser.get_cue_start_time(0), 2000.0);
+        let service = parser.service().expect("service");
+        assert_eq!(service.language.as_deref(), Some("swe"));
+        assert_eq!(service.subtitling_type, 0x10);
+        assert!(parser.render_at_index(0).is_some());
+        assert_eq!(parser.continuity_errors(), 0);
+        assert_eq!(parser.parse_transport_stream(&ts, Some(0x41)), Ok(0));
+        assert!(parser.parse_transport_stream(&[], None).is_err());
+
+        // Skip continuity counters 1 to 4 on the subtitle PID
+        ts.extend(packetize(0x40, &pes, &mut 5));
+        parser.parse_transport_stream(&ts, None).expect("parse");
+        assert_eq!(parser.continuity_errors(), 1);
+        assert_eq!(parser.last_render_issue(), "");
+    }
+
     #[test]
     fn feed_progressive() {
         let payload = build_simple_display_set();
@@ -294,7 +294,8 @@ pub fn normalize_es_payload(payload: &[u8]) -> &[u8] {
     data
 }
 
-/// Iterate segments inside a normalized ES payload until EDS or end.
+/// Iterate segments inside a normalized ES payload until stuffing or end.
+/// A PES can carry several pages, each closed by its own EDS.
 pub fn iter_segments(payload: &[u8]) -> Vec<Segment<'_>> {
     let data = normalize_es_payload(payload);
     let mut offset = 0usize;
@@ -307,12 +308,8 @@ pub fn iter_segments(payload: &[u8]) -> Vec<Segment<'_>> {
 
         match Segment::parse(&data[offset..]) {
             Some((segment, consumed)) => {
-                let is_end = segment.segment_type == super::segment::END_OF_DISPLAY_SET;
                 segments.push(segment);
                 offset += consumed;
-                if is_end {
-                    break;
-                }
             }
             None => break,
         }
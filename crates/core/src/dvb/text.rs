@@ -0,0 +1,342 @@
+//! Character-coded objects (coding method 01): character table 00 decoding
+//! and a small built-in fixed font for rendering them.
+
+/// Width of a glyph in font pixels.
+const GLYPH_WIDTH: usize = 5;
+/// Height of a glyph in font pixels.
+const GLYPH_HEIGHT: usize = 7;
+/// Each font pixel is drawn as a square of this many region pixels.
+const GLYPH_SCALE: usize = 2;
+/// Character cell width in region pixels (glyph plus one column of spacing).
+pub const TEXT_CELL_WIDTH: usize = (GLYPH_WIDTH + 1) * GLYPH_SCALE;
+/// Character cell height in region pixels (glyph plus one row of spacing).
+pub const TEXT_CELL_HEIGHT: usize = (GLYPH_HEIGHT + 1) * GLYPH_SCALE;
+
+/// Line break control code of the DVB character tables.
+const CODE_CR_LF: u16 = 0x8A;
+
+/// Non-spacing diacritics (0xC1-0xCF) with the letters they precompose with.
+const DIACRITICS: [(u16, char, &str, &str); 13] = [
+    (0xC1, '\u{0300}', "AEIOUaeiou", "ÀÈÌÒÙàèìòù"),
+    (
+        0xC2,
+        '\u{0301}',
+        "AEIOUYaeiouyCcNnSsZz",
+        "ÁÉÍÓÚÝáéíóúýĆćŃńŚśŹź",
+    ),
+    (0xC3, '\u{0302}', "AEIOUaeiou", "ÂÊÎÔÛâêîôû"),
+    (0xC4, '\u{0303}', "ANOano", "ÃÑÕãñõ"),
+    (0xC5, '\u{0304}', "AEIOUaeiou", "ĀĒĪŌŪāēīōū"),
+    (0xC6, '\u{0306}', "AGUagu", "ĂĞŬăğŭ"),
+    (0xC7, '\u{0307}', "CEGIZcegz", "ĊĖĠİŻċėġż"),
+    (0xC8, '\u{0308}', "AEIOUaeiouy", "ÄËÏÖÜäëïöüÿ"),
+    (0xCA, '\u{030A}', "AUau", "ÅŮåů"),
+    (0xCB, '\u{0327}', "CSTcst", "ÇŞŢçşţ"),
+    (0xCD, '\u{030B}', "OUou", "ŐŰőű"),
+    (0xCE, '\u{0328}', "AEae", "ĄĘąę"),
+    (0xCF, '\u{030C}', "CDENRSTZcdenrstz", "ČĎĚŇŘŠŤŽčďěňřšťž"),
+];
+
+/// Decode character codes of an object data segment into text.
+///
+/// Codes up to 0xFF use character table 00 (EN 300 468, figure A.1); larger
+/// codes are taken as UCS-2. Unknown control codes are dropped.
+pub fn decode_character_codes(codes: &[u16]) -> String {
+    let mut text = String::with_capacity(codes.len());
+    let mut pending_diacritic = None;
+
+    for &code in codes {
+        if let Some(entry) = DIACRITICS.iter().find(|entry| entry.0 == code) {
+            pending_diacritic = Some(entry);
+            continue;
+        }
+        let Some(ch) = table_00_char(code) else {
+            continue;
+        };
+        match pending_diacritic.take() {
+            Some(&(_, mark, bases, composed)) => match bases.chars().position(|base| base == ch) {
+                Some(index) => text.extend(composed.chars().nth(index)),
+                None => {
+                    text.push(ch);
+                    text.push(mark);
+                }
+            },
+            None => text.push(ch),
+        }
+    }
+
+    text
+}
+
+fn table_00_char(code: u16) -> Option<char> {
+    let ch = match code {
+        CODE_CR_LF => '\n',
+        0x20..=0x7E => code as u8 as char,
+        0xA0 => '\u{00A0}',
+        0xA1 => '¡',
+        0xA2 => '¢',
+        0xA3 => '£',
+        0xA4 => '$',
+        0xA5 => '¥',
+        0xA6 => '#',
+        0xA7 => '§',
+        0xA8 => '¤',
+        0xA9 => '‘',
+        0xAA => '“',
+        0xAB => '«',
+        0xAC => '←',
+        0xAD => '↑',
+        0xAE => '→',
+        0xAF => '↓',
+        0xB0 => '°',
+        0xB1 => '±',
+        0xB2 => '²',
+        0xB3 => '³',
+        0xB4 => '×',
+        0xB5 => 'µ',
+        0xB6 => '¶',
+        0xB7 => '·',
+        0xB8 => '÷',
+        0xB9 => '’',
+        0xBA => '”',
+        0xBB => '»',
+        0xBC => '¼',
+        0xBD => '½',
+        0xBE => '¾',
+        0xBF => '¿',
+        0xD0 => '―',
+        0xD1 => '¹',
+        0xD2 => '®',
+        0xD3 => '©',
+        0xD4 => '™',
+        0xD5 => '♪',
+        0xD6 => '¬',
+        0xD7 => '¦',
+        0xDC => '⅛',
+        0xDD => '⅜',
+        0xDE => '⅝',
+        0xDF => '⅞',
+        0xE0 => 'Ω',
+        0xE1 => 'Æ',
+        0xE2 => 'Đ',
+        0xE3 => 'ª',
+        0xE4 => 'Ħ',
+        0xE6 => 'Ĳ',
+        0xE7 => 'Ŀ',
+        0xE8 => 'Ł',
+        0xE9 => 'Ø',
+        0xEA => 'Œ',
+        0xEB => 'º',
+        0xEC => 'Þ',
+        0xED => 'Ŧ',
+        0xEE => 'Ŋ',
+        0xEF => 'ŉ',
+        0xF0 => 'ĸ',
+        0xF1 => 'æ',
+        0xF2 => 'đ',
+        0xF3 => 'ð',
+        0xF4 => 'ħ',
+        0xF5 => 'ı',
+        0xF6 => 'ĳ',
+        0xF7 => 'ŀ',
+        0xF8 => 'ł',
+        0xF9 => 'ø',
+        0xFA => 'œ',
+        0xFB => 'ß',
+        0xFC => 'þ',
+        0xFD => 'ŧ',
+        0xFE => 'ŋ',
+        0xFF => '\u{00AD}',
+        0x0100.. => return char::from_u32(code as u32),
+        _ => return None,
+    };
+    Some(ch)
+}
+
+/// 5x7 glyphs for ASCII 0x20-0x7E, one byte per row (bit 4 = leftmost).
+const FONT_5X7: [[u8; GLYPH_HEIGHT]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04], // '!'
+    [0x0A, 0x0A, 0x0A, 0x00, 0x00, 0x00, 0x00], // '"'
+    [0x0A, 0x0A, 0x1F, 0x0A, 0x1F, 0x0A, 0x0A], // '#'
+    [0x04, 0x0F, 0x14, 0x0E, 0x05, 0x1E, 0x04], // '$'
+    [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03], // '%'
+    [0x0C, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0D], // '&'
+    [0x04, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00], // "'"
+    [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02], // '('
+    [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08], // ')'
+    [0x00, 0x04, 0x15, 0x0E, 0x15, 0x04, 0x00], // '*'
+    [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00], // '+'
+    [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08], // ','
+    [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00], // '-'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C], // '.'
+    [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00], // '/'
+    [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E], // '0'
+    [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E], // '1'
+    [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F], // '2'
+    [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E], // '3'
+    [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02], // '4'
+    [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E], // '5'
+    [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E], // '6'
+    [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08], // '7'
+    [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E], // '8'
+    [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C], // '9'
+    [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00], // ':'
+    [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x04, 0x08], // ';'
+    [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02], // '<'
+    [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00], // '='
+    [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08], // '>'
+    [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04], // '?'
+    [0x0E, 0x11, 0x01, 0x0D, 0x15, 0x15, 0x0E], // '@'
+    [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11], // 'A'
+    [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E], // 'B'
+    [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E], // 'C'
+    [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C], // 'D'
+    [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F], // 'E'
+    [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10], // 'F'
+    [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F], // 'G'
+    [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11], // 'H'
+    [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E], // 'I'
+    [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C], // 'J'
+    [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11], // 'K'
+    [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F], // 'L'
+    [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11], // 'M'
+    [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11], // 'N'
+    [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E], // 'O'
+    [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10], // 'P'
+    [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D], // 'Q'
+    [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11], // 'R'
+    [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E], // 'S'
+    [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04], // 'T'
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E], // 'U'
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04], // 'V'
+    [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A], // 'W'
+    [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11], // 'X'
+    [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04], // 'Y'
+    [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F], // 'Z'
+    [0x0E, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0E], // '['
+    [0x00, 0x10, 0x08, 0x04, 0x02, 0x01, 0x00], // '\\'
+    [0x0E, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0E], // ']'
+    [0x04, 0x0A, 0x11, 0x00, 0x00, 0x00, 0x00], // '^'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F], // '_'
+    [0x08, 0x04, 0x02, 0x00, 0x00, 0x00, 0x00], // '`'
+    [0x00, 0x00, 0x0E, 0x01, 0x0F, 0x11, 0x0F], // 'a'
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x1E], // 'b'
+    [0x00, 0x00, 0x0E, 0x10, 0x10, 0x11, 0x0E], // 'c'
+    [0x01, 0x01, 0x0D, 0x13, 0x11, 0x11, 0x0F], // 'd'
+    [0x00, 0x00, 0x0E, 0x11, 0x1F, 0x10, 0x0E], // 'e'
+    [0x06, 0x09, 0x08, 0x1C, 0x08, 0x08, 0x08], // 'f'
+    [0x00, 0x0F, 0x11, 0x11, 0x0F, 0x01, 0x0E], // 'g'
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x11], // 'h'
+    [0x04, 0x00, 0x0C, 0x04, 0x04, 0x04, 0x0E], // 'i'
+    [0x02, 0x00, 0x06, 0x02, 0x02, 0x12, 0x0C], // 'j'
+    [0x10, 0x10, 0x12, 0x14, 0x18, 0x14, 0x12], // 'k'
+    [0x0C, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E], // 'l'
+    [0x00, 0x00, 0x1A, 0x15, 0x15, 0x11, 0x11], // 'm'
+    [0x00, 0x00, 0x16, 0x19, 0x11, 0x11, 0x11], // 'n'
+    [0x00, 0x00, 0x0E, 0x11, 0x11, 0x11, 0x0E], // 'o'
+    [0x00, 0x00, 0x1E, 0x11, 0x1E, 0x10, 0x10], // 'p'
+    [0x00, 0x00, 0x0D, 0x13, 0x0F, 0x01, 0x01], // 'q'
+    [0x00, 0x00, 0x16, 0x19, 0x10, 0x10, 0x10], // 'r'
+    [0x00, 0x00, 0x0E, 0x10, 0x0E, 0x01, 0x1E], // 's'
+    [0x08, 0x08, 0x1C, 0x08, 0x08, 0x09, 0x06], // 't'
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x13, 0x0D], // 'u'
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x0A, 0x04], // 'v'
+    [0x00, 0x00, 0x11, 0x11, 0x15, 0x15, 0x0A], // 'w'
+    [0x00, 0x00, 0x11, 0x0A, 0x04, 0x0A, 0x11], // 'x'
+    [0x00, 0x00, 0x11, 0x11, 0x0F, 0x01, 0x0E], // 'y'
+    [0x00, 0x00, 0x1F, 0x02, 0x04, 0x08, 0x1F], // 'z'
+    [0x02, 0x04, 0x04, 0x08, 0x04, 0x04, 0x02], // '{'
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04], // '|'
+    [0x08, 0x04, 0x04, 0x02, 0x04, 0x04, 0x08], // '}'
+    [0x00, 0x00, 0x08, 0x15, 0x02, 0x00, 0x00], // '~'
+];
+
+/// Glyph rows for a character. Accented letters fall back to their base
+/// letter and anything else outside ASCII to `?`.
+fn glyph(ch: char) -> &'static [u8; GLYPH_HEIGHT] {
+    let ch = DIACRITICS
+        .iter()
+        .find_map(|&(_, _, bases, composed)| {
+            let index = composed.chars().position(|letter| letter == ch)?;
+            bases.chars().nth(index)
+        })
+        .unwrap_or(ch);
+    let index = match ch {
+        ' '..='~' => ch as usize - 0x20,
+        _ => '?' as usize - 0x20,
+    };
+    &FONT_5X7[index]
+}
+
+/// Draw text into an indexed bitmap with the built-in font, starting at
+/// `(x, y)`. Cells are filled with `background` and glyph pixels with
+/// `foreground`; anything outside the bitmap is clipped.
+pub fn draw_text(
+    pixels: &mut [u8],
+    width: usize,
+    height: usize,
+    (x, y): (usize, usize),
+    text: &str,
+    foreground: u8,
+    background: u8,
+) {
+    let mut cell_y = y;
+    for line in text.split('\n') {
+        let mut cell_x = x;
+        // Combining marks have no cell of their own
+        for ch in line
+            .chars()
+            .filter(|ch| !('\u{0300}'..='\u{036F}').contains(ch))
+        {
+            let rows = glyph(ch);
+            for row in 0..TEXT_CELL_HEIGHT {
+                let py = cell_y + row;
+                if py >= height {
+                    break;
+                }
+                let bits = rows.get(row / GLYPH_SCALE).copied().unwrap_or(0);
+                for column in 0..TEXT_CELL_WIDTH {
+                    let px = cell_x + column;
+                    if px >= width {
+                        break;
+                    }
+                    let font_column = column / GLYPH_SCALE;
+                    let set = font_column < GLYPH_WIDTH && bits & (0x10 >> font_column) != 0;
+                    pixels[py * width + px] = if set { foreground } else { background };
+                }
+            }
+            cell_x += TEXT_CELL_WIDTH;
+        }
+        cell_y += TEXT_CELL_HEIGHT;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_table_00_with_diacritics() {
+        let mut codes: Vec<u16> = "Caf".bytes().map(u16::from).collect();
+        codes.extend_from_slice(&[0xC2, b'e' as u16, CODE_CR_LF, 0xC8, b'x' as u16, 0x01]);
+        codes.extend_from_slice(&[0xD5, 0x20AC]);
+        assert_eq!(decode_character_codes(&codes), "Café\nx\u{0308}♪€");
+    }
+
+    #[test]
+    fn draws_glyphs_into_cells() {
+        let width = TEXT_CELL_WIDTH * 2;
+        let mut pixels = vec![0u8; width * TEXT_CELL_HEIGHT];
+        draw_text(&mut pixels, width, TEXT_CELL_HEIGHT, (0, 0), "é|", 2, 1);
+
+        // 'é' is drawn as 'e', whose first row is blank
+        assert!(pixels[..TEXT_CELL_WIDTH].iter().all(|&code| code == 1));
+        // '|' is a centre column of foreground on every glyph row
+        let centre = TEXT_CELL_WIDTH + 2 * GLYPH_SCALE;
+        for row in 0..GLYPH_HEIGHT * GLYPH_SCALE {
+            assert_eq!(pixels[row * width + centre], 2);
+        }
+        assert_eq!(pixels[(TEXT_CELL_HEIGHT - 1) * width + centre], 1);
+    }
+}
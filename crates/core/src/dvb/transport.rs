@@ -0,0 +1,128 @@
+//! DVB subtitle extraction from MPEG transport streams (DVR recordings).
+//!
+//! Subtitle streams are private PES streams (stream_type 0x06) whose PMT
+//! entry carries a subtitling_descriptor (tag 0x59) listing the services
+//! multiplexed on the PID.
+
+use super::{DvbSubtitleService, TimedPayload, pts_90k_to_ms};
+use crate::ts::{PesAssembler, iter_ts_packets, parse_ts_programs, read_language_code};
+
+/// PMT stream type of PES packets carrying private data.
+pub const PRIVATE_PES_STREAM_TYPE: u8 = 0x06;
+/// Subtitling descriptor tag (EN 300 468).
+pub const DESCRIPTOR_DVB_SUBTITLING: u8 = 0x59;
+/// PES stream id of private stream 1.
+const PRIVATE_STREAM_1: u8 = 0xBD;
+
+/// A DVB subtitle stream found in a transport stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DvbTransportStream {
+    /// Packet identifier
+    pub pid: u16,
+    /// Services from the subtitling descriptor, in descriptor order
+    pub services: Vec<DvbSubtitleService>,
+}
+
+/// DVB subtitles demuxed from one PID.
+#[derive(Debug, Clone)]
+pub struct DvbTransportPayloads {
+    /// PES data fields with their presentation times
+    pub units: Vec<TimedPayload>,
+    /// TS packets lost or out of order (continuity counter gaps)
+    pub continuity_errors: usize,
+}
+
+/// Parse the 8-byte entries of a subtitling descriptor.
+pub fn parse_subtitling_descriptor(data: &[u8]) -> Vec<DvbSubtitleService> {
+    data.chunks_exact(8)
+        .map(|entry| DvbSubtitleService {
+            language: read_language_code(entry),
+            subtitling_type: entry[3],
+            composition_page_id: u16::from_be_bytes([entry[4], entry[5]]),
+            ancillary_page_id: u16::from_be_bytes([entry[6], entry[7]]),
+        })
+        .collect()
+}
+
+/// List the DVB subtitle streams declared in the PAT/PMT of a transport
+/// stream.
+pub fn list_dvb_streams(data: &[u8]) -> Vec<DvbTransportStream> {
+    parse_ts_programs(data)
+        .into_iter()
+        .filter(|stream| stream.stream_type == PRIVATE_PES_STREAM_TYPE)
+        .filter_map(|stream| {
+            let descriptor = stream.find_descriptor(DESCRIPTOR_DVB_SUBTITLING)?;
+            Some(DvbTransportStream {
+                pid: stream.pid,
+                services: parse_subtitling_descriptor(&descriptor.data),
+            })
+        })
+        .collect()
+}
+
+/// Demux the DVB subtitle PES packets carried on `pid`.
+pub fn extract_dvb_from_ts(data: &[u8], pid: u16) -> DvbTransportPayloads {
+    let mut assembler = PesAssembler::new(pid);
+    let mut units = Vec::new();
+
+    let mut append = |pes: crate::ts::PesPacket| {
+        if pes.stream_id != PRIVATE_STREAM_1 {
+            return;
+        }
+        // Segments without a PTS cannot be placed on the timeline
+        if let Some(pts) = pes.pts {
+            units.push(TimedPayload {
+                pts_ms: pts_90k_to_ms(pts),
+                payload: pes.payload,
+            });
+        }
+    };
+
+    for packet in iter_ts_packets(data) {
+        if packet.pid != pid || packet.transport_error {
+            continue;
+        }
+        if let Some(pes) = assembler.push_packet(&packet) {
+            append(pes);
+        }
+    }
+    if let Some(pes) = assembler.flush() {
+        append(pes);
+    }
+
+    DvbTransportPayloads {
+        units,
+        continuity_errors: assembler.continuity_errors(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_subtitling_descriptor_entries() {
+        let services = parse_subtitling_descriptor(&[
+            b'e', b'n', b'g', 0x10, 0x00, 0x01, 0x00, 0x02, //
+            b'd', b'e', b'u', 0x20, 0x00, 0x03, 0x00, 0x03, //
+            0xFF, 0xFF, // truncated entry
+        ]);
+        assert_eq!(
+            services,
+            vec![
+                DvbSubtitleService {
+                    language: Some("eng".to_string()),
+                    subtitling_type: 0x10,
+                    composition_page_id: 1,
+                    ancillary_page_id: 2,
+                },
+                DvbSubtitleService {
+                    language: Some("deu".to_string()),
+                    subtitling_type: 0x20,
+                    composition_page_id: 3,
+                    ancillary_page_id: 3,
+                },
+            ]
+        );
+    }
+}
@@ -1,7 +1,10 @@
 //! Pure Rust parser and renderer core for graphical subtitles.
 
 pub mod dvb;
+pub mod mkv;
 pub mod pgs;
+pub mod textst;
+pub mod ts;
 pub mod utils;
 pub mod vobsub;
 
@@ -10,4 +13,5 @@ mod compatibility;
 
 pub use dvb::*;
 pub use pgs::*;
+pub use textst::*;
 pub use vobsub::*;
@@ -0,0 +1,153 @@
+//! EBML element IDs and variable-size integer readers.
+
+pub(crate) const EBML_ID_SEGMENT: u32 = 0x1853_8067;
+pub(crate) const EBML_ID_SEEK_HEAD: u32 = 0x114D_9B74;
+pub(crate) const EBML_ID_SEEK: u32 = 0x4DBB;
+pub(crate) const EBML_ID_SEEK_ID: u32 = 0x53AB;
+pub(crate) const EBML_ID_SEEK_POSITION: u32 = 0x53AC;
+pub(crate) const EBML_ID_SEGMENT_INFO: u32 = 0x1549_A966;
+pub(crate) const EBML_ID_TRACKS: u32 = 0x1654_AE6B;
+pub(crate) const EBML_ID_TRACK_ENTRY: u32 = 0xAE;
+pub(crate) const EBML_ID_TRACK_NUMBER: u32 = 0xD7;
+pub(crate) const EBML_ID_TRACK_TYPE: u32 = 0x83;
+pub(crate) const EBML_ID_CODEC_ID: u32 = 0x86;
+pub(crate) const EBML_ID_CODEC_PRIVATE: u32 = 0x63A2;
+pub(crate) const EBML_ID_LANGUAGE: u32 = 0x0022_B59C;
+pub(crate) const EBML_ID_LANGUAGE_IETF: u32 = 0x0022_B59D;
+pub(crate) const EBML_ID_NAME: u32 = 0x536E;
+pub(crate) const EBML_ID_FLAG_DEFAULT: u32 = 0x88;
+pub(crate) const EBML_ID_FLAG_FORCED: u32 = 0x55AA;
+pub(crate) const EBML_ID_FLAG_HEARING_IMPAIRED: u32 = 0x55AB;
+pub(crate) const EBML_ID_FLAG_ORIGINAL: u32 = 0x55AE;
+pub(crate) const EBML_ID_DEFAULT_DURATION: u32 = 0x0023_E383;
+pub(crate) const EBML_ID_CONTENT_ENCODINGS: u32 = 0x6D80;
+pub(crate) const EBML_ID_CONTENT_ENCODING: u32 = 0x6240;
+pub(crate) const EBML_ID_CONTENT_COMPRESSION: u32 = 0x5034;
+pub(crate) const EBML_ID_CONTENT_COMP_ALGO: u32 = 0x4254;
+pub(crate) const EBML_ID_CONTENT_COMP_SETTINGS: u32 = 0x4255;
+pub(crate) const EBML_ID_TIMECODE_SCALE: u32 = 0x002A_D7B1;
+pub(crate) const EBML_ID_CLUSTER: u32 = 0x1F43_B675;
+pub(crate) const EBML_ID_CLUSTER_TIMESTAMP: u32 = 0xE7;
+pub(crate) const EBML_ID_BLOCK_GROUP: u32 = 0xA0;
+pub(crate) const EBML_ID_BLOCK: u32 = 0xA1;
+pub(crate) const EBML_ID_BLOCK_DURATION: u32 = 0x9B;
+pub(crate) const EBML_ID_SIMPLE_BLOCK: u32 = 0xA3;
+pub(crate) const EBML_ID_CUES: u32 = 0x1C53_BB6B;
+pub(crate) const EBML_ID_CUE_POINT: u32 = 0xBB;
+pub(crate) const EBML_ID_CUE_TRACK_POSITIONS: u32 = 0xB7;
+pub(crate) const EBML_ID_CUE_TRACK: u32 = 0xF7;
+pub(crate) const EBML_ID_CUE_CLUSTER_POSITION: u32 = 0xF1;
+pub(crate) const EBML_ID_CUE_RELATIVE_POSITION: u32 = 0xF0;
+
+pub(crate) fn next_element(
+    data: &[u8],
+    pos: usize,
+    limit: usize,
+) -> Result<(u32, usize, usize), String> {
+    let (id, id_len) =
+        read_element_id(data, pos).ok_or_else(|| "Invalid EBML element ID".to_string())?;
+    let size_pos = pos + id_len;
+    let (size, size_len) =
+        read_size_vint(data, size_pos).ok_or_else(|| "Invalid EBML element size".to_string())?;
+    let data_start = size_pos + size_len;
+    let data_end = match size {
+        Some(size) => data_start
+            .checked_add(size as usize)
+            .filter(|end| *end <= limit)
+            .ok_or_else(|| "Truncated Matroska element payload".to_string())?,
+        None => limit,
+    };
+    Ok((id, data_start, data_end))
+}
+
+pub(crate) fn read_uint(data: &[u8], start: usize, end: usize) -> Result<u64, String> {
+    let size = end.saturating_sub(start);
+    if size == 0 || size > 8 {
+        return Err("Unsupported EBML integer size".to_string());
+    }
+
+    let mut value = 0u64;
+    for &byte in &data[start..end] {
+        value = (value << 8) | byte as u64;
+    }
+    Ok(value)
+}
+
+pub(crate) fn read_string(data: &[u8], start: usize, end: usize) -> String {
+    String::from_utf8_lossy(&data[start..end])
+        .trim_matches(char::from(0))
+        .trim()
+        .to_string()
+}
+
+pub(crate) fn read_element_id(data: &[u8], pos: usize) -> Option<(u32, usize)> {
+    let first = *data.get(pos)?;
+    let width = vint_width(first)?;
+    if width > 4 || pos + width > data.len() {
+        return None;
+    }
+
+    let mut value = first as u32;
+    for &byte in &data[pos + 1..pos + width] {
+        value = (value << 8) | byte as u32;
+    }
+
+    Some((value, width))
+}
+
+pub(crate) fn read_size_vint(data: &[u8], pos: usize) -> Option<(Option<u64>, usize)> {
+    let (value, width) = read_vint(data, pos)?;
+    let unknown_value = if width == 8 {
+        u64::MAX >> 8
+    } else {
+        (1u64 << (width * 7)) - 1
+    };
+
+    if value == unknown_value {
+        Some((None, width))
+    } else {
+        Some((Some(value), width))
+    }
+}
+
+pub(crate) fn read_vint(data: &[u8], pos: usize) -> Option<(u64, usize)> {
+    let first = *data.get(pos)?;
+    let width = vint_width(first)?;
+    if pos + width > data.len() {
+        return None;
+    }
+
+    let mask = if width == 8 {
+        0
+    } else {
+        (1u8 << (8 - width)) - 1
+    };
+    let mut value = (first & mask) as u64;
+    for &byte in &data[pos + 1..pos + width] {
+        value = (value << 8) | byte as u64;
+    }
+
+    Some((value, width))
+}
+
+pub(crate) fn vint_width(first: u8) -> Option<usize> {
+    if first & 0x80 != 0 {
+        Some(1)
+    } else if first & 0x40 != 0 {
+        Some(2)
+    } else if first & 0x20 != 0 {
+        Some(3)
+    } else if first & 0x10 != 0 {
+        Some(4)
+    } else if first & 0x08 != 0 {
+        Some(5)
+    } else if first & 0x04 != 0 {
+        Some(6)
+    } else if first & 0x02 != 0 {
+        Some(7)
+    } else if first & 0x01 != 0 {
+        Some(8)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,17 @@
+//! Matroska (MKV / MKS) container parsing.
+//!
+//! Walks the EBML segment to list subtitle tracks and collect their blocks,
+//! splitting laced blocks and undoing zlib and header-stripping content
+//! compression. Shared by the VobSub, PGS and DVB loaders. `MatroskaReader`
+//! does the same from a seekable source, reading only what one track needs.
+
+mod ebml;
+mod reader;
+mod segment;
+#[cfg(test)]
+pub(crate) mod test_util;
+mod tracks;
+
+pub use reader::*;
+pub use segment::*;
+pub use tracks::*;
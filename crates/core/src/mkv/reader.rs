@@ -0,0 +1,881 @@
+//! Range-driven Matroska reading for files too large to hold in memory.
+//!
+//! Only the segment headers, the Cues and the selected track's blocks are
+//! read from the source; other blocks are skipped by seeking past them.
+//! Frames are collected progressively, one cue (or cluster) at a time.
+
+use std::io::{Read, Seek, SeekFrom};
+
+use super::ebml::*;
+use super::segment::{
+    BlockTiming, MAX_BLOCK_PAYLOAD_SIZE, MatroskaFrame, MatroskaTrack, parse_block,
+    parse_block_group, parse_segment_info, parse_tracks, push_frame, select_track,
+};
+
+/// Largest SeekHead, Info or Tracks element read into memory.
+const MAX_HEADER_ELEMENT_SIZE: u64 = 1 << 20;
+/// Largest Cues element read into memory.
+const MAX_CUES_SIZE: u64 = 16 << 20;
+/// Largest SimpleBlock/BlockGroup read into memory (payload plus headers).
+const MAX_BLOCK_ELEMENT_SIZE: u64 = MAX_BLOCK_PAYLOAD_SIZE as u64 + 4096;
+/// Longest element header: 4-byte ID and 8-byte size.
+const MAX_ELEMENT_HEADER_LEN: u64 = 12;
+/// Bytes read to find the track number at the start of a block.
+const BLOCK_TRACK_PEEK_LEN: u64 = 8;
+
+#[derive(Debug, Clone, Copy)]
+struct ElementHeader {
+    id: u32,
+    data_start: u64,
+    /// End of the payload, or of the parent when the size is unknown
+    data_end: u64,
+    unknown_size: bool,
+}
+
+/// Where the selected track's next frames are, as absolute file offsets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReadTarget {
+    /// One block, from a cue with `CueRelativePosition`
+    Block { cluster: u64, relative: u64 },
+    /// A whole cluster, scanned for the track's blocks
+    Cluster(u64),
+}
+
+impl ReadTarget {
+    fn sort_key(&self) -> (u64, u64) {
+        match *self {
+            ReadTarget::Block { cluster, relative } => (cluster, relative),
+            ReadTarget::Cluster(cluster) => (cluster, 0),
+        }
+    }
+}
+
+/// Lazily reads one subtitle track from a seekable Matroska source.
+///
+/// Uses the Cues to jump straight to the track's blocks when they index it,
+/// and otherwise walks the clusters, reading only block headers of other
+/// tracks. The Cues are trusted to list every block of the track; call
+/// `set_full_scan` for files where they do not. Frames own their payloads, so
+/// `MatroskaFrame::payload` ignores its `data` argument.
+pub struct MatroskaReader<R> {
+    source: R,
+    segment_start: u64,
+    segment_end: u64,
+    timescale_ns: u64,
+    tracks: Vec<MatroskaTrack>,
+    cues_position: Option<u64>,
+    first_cluster: Option<u64>,
+    selected: Option<usize>,
+    targets: Vec<ReadTarget>,
+    next_target: usize,
+    /// Next top-level position when walking clusters without Cues
+    scan_position: Option<u64>,
+    /// Last cluster whose timestamp was read: (position, timestamp)
+    cluster_timestamp: Option<(u64, i64)>,
+    frames: Vec<MatroskaFrame>,
+    bytes_read: u64,
+    full_scan: bool,
+}
+
+impl<R: Read + Seek> MatroskaReader<R> {
+    /// Read the segment headers (SeekHead, Info and Tracks) of a source.
+    pub fn open(mut source: R) -> Result<Self, String> {
+        let source_len = source.seek(SeekFrom::End(0)).map_err(io_error)?;
+        let mut reader = Self {
+            source,
+            segment_start: 0,
+            segment_end: source_len,
+            timescale_ns: 1_000_000,
+            tracks: Vec::new(),
+            cues_position: None,
+            first_cluster: None,
+            selected: None,
+            targets: Vec::new(),
+            next_target: 0,
+            scan_position: None,
+            cluster_timestamp: None,
+            frames: Vec::new(),
+            bytes_read: 0,
+            full_scan: false,
+        };
+
+        let segment = reader.find_segment(source_len)?;
+        reader.segment_start = segment.data_start;
+        reader.segment_end = segment.data_end;
+        reader.read_segment_headers()?;
+        Ok(reader)
+    }
+
+    /// Subtitle tracks, in header order.
+    pub fn tracks(&self) -> &[MatroskaTrack] {
+        &self.tracks
+    }
+
+    /// Nanoseconds per timestamp tick (`TimestampScale`).
+    pub fn timescale_ns(&self) -> u64 {
+        self.timescale_ns
+    }
+
+    /// Total bytes read from the source so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    pub fn selected_track(&self) -> Option<&MatroskaTrack> {
+        self.selected.map(|index| &self.tracks[index])
+    }
+
+    /// Walk every cluster instead of following the Cues, for files whose Cues
+    /// index only some of the track's blocks. Costs a read per block of
+    /// every track, so it is off by default. Applies from the next
+    /// `select_track`.
+    pub fn set_full_scan(&mut self, full_scan: bool) {
+        self.full_scan = full_scan;
+    }
+
+    /// Select the track to read, by number or the first one of `codec_id`
+    /// when `track_number` is `None`. Discards frames read for a previous
+    /// track.
+    pub fn select_track(
+        &mut self,
+        codec_id: &str,
+        track_number: Option<u64>,
+    ) -> Result<&MatroskaTrack, String> {
+        let number = select_track(&self.tracks, codec_id, track_number)?.track_number;
+        let index = self
+            .tracks
+            .iter()
+            .position(|track| track.track_number == number)
+            .expect("selected track is listed");
+
+        self.frames.clear();
+        self.cluster_timestamp = None;
+        self.next_target = 0;
+        self.targets = match self.cues_position {
+            Some(position) if !self.full_scan => self.read_cue_targets(position, number)?,
+            _ => Vec::new(),
+        };
+        self.scan_position = if self.targets.is_empty() {
+            self.first_cluster
+        } else {
+            None
+        };
+        self.selected = Some(index);
+        Ok(&self.tracks[index])
+    }
+
+    /// Frames read so far, in file order.
+    pub fn frames(&self) -> &[MatroskaFrame] {
+        &self.frames
+    }
+
+    /// Whether every frame of the selected track has been read.
+    pub fn is_complete(&self) -> bool {
+        self.next_target >= self.targets.len() && self.scan_position.is_none()
+    }
+
+    /// Read the next cue (or, without Cues, the next cluster). Returns the
+    /// number of new frames, or `None` once the track is complete.
+    pub fn read_next(&mut self) -> Result<Option<usize>, String> {
+        let index = self
+            .selected
+            .ok_or_else(|| "No Matroska track selected".to_string())?;
+        if self.is_complete() {
+            return Ok(None);
+        }
+
+        // Borrow the track out so blocks can be read into `self`
+        let track = std::mem::take(&mut self.tracks[index]);
+        let before = self.frames.len();
+        let result = self.read_next_target(&track);
+        self.tracks[index] = track;
+        result?;
+        Ok(Some(self.frames.len() - before))
+    }
+
+    /// Read every remaining frame of the selected track.
+    pub fn read_to_end(&mut self) -> Result<&[MatroskaFrame], String> {
+        while self.read_next()?.is_some() {}
+        Ok(&self.frames)
+    }
+
+    fn read_next_target(&mut self, track: &MatroskaTrack) -> Result<(), String> {
+        if let Some(&target) = self.targets.get(self.next_target) {
+            self.next_target += 1;
+            match target {
+                ReadTarget::Block { cluster, relative } => {
+                    self.read_cued_block(track, cluster, relative)
+                }
+                ReadTarget::Cluster(position) => {
+                    let header = self.read_cluster_header(position)?;
+                    self.read_cluster(track, position, &header).map(|_| ())
+                }
+            }
+        } else if let Some(position) = self.scan_position {
+            self.scan_position = self.scan_next_cluster(track, position)?;
+            Ok(())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn find_segment(&mut self, source_len: u64) -> Result<ElementHeader, String> {
+        let mut position = 0;
+
+        while position < source_len {
+            let header = self.read_header(position, source_len)?;
+            if header.id == EBML_ID_SEGMENT {
+                return Ok(header);
+            }
+            position = header.data_end;
+        }
+
+        Err("Matroska Segment element not found".to_string())
+    }
+
+    /// Walk the top-level elements up to the first cluster, then follow the
+    /// SeekHead to elements stored after the clusters.
+    fn read_segment_headers(&mut self) -> Result<(), String> {
+        let mut seeks = Vec::new();
+        let mut visited = Vec::new();
+        let mut found_tracks = false;
+        let mut position = self.segment_start;
+
+        while position < self.segment_end {
+            let header = self.read_header(position, self.segment_end)?;
+            if header.id == EBML_ID_CLUSTER {
+                self.first_cluster = Some(position);
+                break;
+            }
+            self.read_top_level(position, &header, &mut seeks)?;
+            found_tracks |= header.id == EBML_ID_TRACKS;
+            visited.push(position);
+            if header.unknown_size {
+                break;
+            }
+            position = header.data_end;
+        }
+
+        let mut next_seek = 0;
+        while let Some(&(id, position)) = seeks.get(next_seek) {
+            next_seek += 1;
+            if visited.contains(&position) || position >= self.segment_end {
+                continue;
+            }
+            visited.push(position);
+            match id {
+                EBML_ID_CLUSTER => {
+                    self.first_cluster.get_or_insert(position);
+                }
+                EBML_ID_SEEK_HEAD | EBML_ID_SEGMENT_INFO | EBML_ID_TRACKS | EBML_ID_CUES => {
+                    let header = self.read_header(position, self.segment_end)?;
+                    if header.id != id {
+                        return Err(
+                            "Matroska SeekHead entry points to the wrong element".to_string()
+                        );
+                    }
+                    self.read_top_level(position, &header, &mut seeks)?;
+                    found_tracks |= id == EBML_ID_TRACKS;
+                }
+                _ => {}
+            }
+        }
+
+        if !found_tracks {
+            return Err("Matroska Tracks element not found".to_string());
+        }
+        Ok(())
+    }
+
+    fn read_top_level(
+        &mut self,
+        position: u64,
+        header: &ElementHeader,
+        seeks: &mut Vec<(u32, u64)>,
+    ) -> Result<(), String> {
+        match header.id {
+            EBML_ID_SEEK_HEAD => {
+                let body = self.read_body(header, MAX_HEADER_ELEMENT_SIZE)?;
+                parse_seek_head(&body, self.segment_start, seeks)?;
+            }
+            EBML_ID_SEGMENT_INFO => {
+                let body = self.read_body(header, MAX_HEADER_ELEMENT_SIZE)?;
+                parse_segment_info(&body, 0, body.len(), &mut self.timescale_ns)?;
+            }
+            EBML_ID_TRACKS => {
+                let body = self.read_body(header, MAX_HEADER_ELEMENT_SIZE)?;
+                parse_tracks(&body, 0, body.len(), &mut self.tracks)?;
+            }
+            EBML_ID_CUES => self.cues_position = Some(position),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn read_cue_targets(
+        &mut self,
+        position: u64,
+        track_number: u64,
+    ) -> Result<Vec<ReadTarget>, String> {
+        let header = self.read_header(position, self.segment_end)?;
+        let body = self.read_body(&header, MAX_CUES_SIZE)?;
+        let mut targets = parse_cues(&body, self.segment_start, track_number)?;
+
+        // A cluster that is scanned whole needs no separate block reads
+        let scanned: Vec<u64> = targets
+            .iter()
+            .filter_map(|target| match target {
+                ReadTarget::Cluster(cluster) => Some(*cluster),
+                ReadTarget::Block { .. } => None,
+            })
+            .collect();
+        targets.retain(|target| match target {
+            ReadTarget::Block { cluster, .. } => !scanned.contains(cluster),
+            ReadTarget::Cluster(_) => true,
+        });
+        targets.sort_by_key(ReadTarget::sort_key);
+        targets.dedup();
+        Ok(targets)
+    }
+
+    fn read_cued_block(
+        &mut self,
+        track: &MatroskaTrack,
+        cluster: u64,
+        relative: u64,
+    ) -> Result<(), String> {
+        let header = self.read_cluster_header(cluster)?;
+        let cluster_timestamp = match self.cluster_timestamp {
+            Some((position, timestamp)) if position == cluster => timestamp,
+            _ => {
+                let timestamp = self.read_cluster_timestamp(&header)?;
+                self.cluster_timestamp = Some((cluster, timestamp));
+                timestamp
+            }
+        };
+
+        let block_position = header
+            .data_start
+            .checked_add(relative)
+            .filter(|position| *position < header.data_end)
+            .ok_or_else(|| "Matroska cue points outside its cluster".to_string())?;
+        let block = self.read_header(block_position, header.data_end)?;
+        self.read_block_element(track, &block, cluster_timestamp)
+    }
+
+    fn read_cluster_header(&mut self, position: u64) -> Result<ElementHeader, String> {
+        let header = self.read_header(position, self.segment_end)?;
+        if header.id != EBML_ID_CLUSTER {
+            return Err("Matroska cue does not point to a cluster".to_string());
+        }
+        Ok(header)
+    }
+
+    /// Read the `Timestamp` that precedes a cluster's blocks.
+    fn read_cluster_timestamp(&mut self, cluster: &ElementHeader) -> Result<i64, String> {
+        let mut position = cluster.data_start;
+
+        while position < cluster.data_end {
+            let child = self.read_header(position, cluster.data_end)?;
+            match child.id {
+                EBML_ID_CLUSTER_TIMESTAMP => {
+                    let body = self.read_body(&child, 8)?;
+                    return Ok(read_uint(&body, 0, body.len())? as i64);
+                }
+                EBML_ID_SIMPLE_BLOCK | EBML_ID_BLOCK_GROUP => break,
+                _ => {}
+            }
+            position = child.data_end;
+        }
+
+        Ok(0)
+    }
+
+    /// Scan a cluster for the track's blocks. Returns the cluster's end.
+    fn read_cluster(
+        &mut self,
+        track: &MatroskaTrack,
+        position: u64,
+        header: &ElementHeader,
+    ) -> Result<u64, String> {
+        let mut cluster_timestamp = 0i64;
+        let mut child_position = header.data_start;
+
+        while child_position < header.data_end {
+            let child = self.read_header(child_position, header.data_end)?;
+            match child.id {
+                EBML_ID_CLUSTER_TIMESTAMP => {
+                    let body = self.read_body(&child, 8)?;
+                    cluster_timestamp = read_uint(&body, 0, body.len())? as i64;
+                    self.cluster_timestamp = Some((position, cluster_timestamp));
+                }
+                EBML_ID_SIMPLE_BLOCK | EBML_ID_BLOCK_GROUP => {
+                    self.read_block_element(track, &child, cluster_timestamp)?;
+                }
+                // The next top-level element ends a cluster of unknown size
+                EBML_ID_CLUSTER | EBML_ID_CUES if header.unknown_size => {
+                    return Ok(child_position);
+                }
+                _ => {}
+            }
+            child_position = child.data_end;
+        }
+
+        Ok(header.data_end)
+    }
+
+    /// Scan the next cluster at or after `position`. Returns where to continue.
+    fn scan_next_cluster(
+        &mut self,
+        track: &MatroskaTrack,
+        mut position: u64,
+    ) -> Result<Option<u64>, String> {
+        while position < self.segment_end {
+            let header = self.read_header(position, self.segment_end)?;
+            if header.id == EBML_ID_CLUSTER {
+                return self.read_cluster(track, position, &header).map(Some);
+            }
+            if header.unknown_size {
+                break;
+            }
+            position = header.data_end;
+        }
+
+        Ok(None)
+    }
+
+    /// Read a SimpleBlock or BlockGroup of `track`; blocks of other tracks
+    /// are skipped after reading their track number.
+    fn read_block_element(
+        &mut self,
+        track: &MatroskaTrack,
+        header: &ElementHeader,
+        cluster_timestamp: i64,
+    ) -> Result<(), String> {
+        let block_start = match header.id {
+            EBML_ID_SIMPLE_BLOCK => header.data_start,
+            EBML_ID_BLOCK_GROUP => match self.find_group_block(header)? {
+                Some(block_start) => block_start,
+                None => return Ok(()),
+            },
+            _ => return Ok(()),
+        };
+
+        let peek_len = BLOCK_TRACK_PEEK_LEN.min(header.data_end.saturating_sub(block_start));
+        let peek = self.read_bytes(block_start, peek_len)?;
+        let (track_num, _) =
+            read_vint(&peek, 0).ok_or_else(|| "Invalid Matroska block track number".to_string())?;
+        if track_num != track.track_number {
+            return Ok(());
+        }
+
+        let body = self.read_body(header, MAX_BLOCK_ELEMENT_SIZE)?;
+        let timing = BlockTiming {
+            cluster_timestamp,
+            timescale_ns: self.timescale_ns,
+            duration_ticks: None,
+        };
+        let mut frames = Vec::new();
+        if header.id == EBML_ID_SIMPLE_BLOCK {
+            parse_block(&body, 0, body.len(), track, &timing, &mut frames)?;
+        } else {
+            parse_block_group(&body, 0, body.len(), track, timing, &mut frames)?;
+        }
+        for frame in frames {
+            push_frame(&mut self.frames, frame.into_owned(&body))?;
+        }
+        Ok(())
+    }
+
+    fn find_group_block(&mut self, group: &ElementHeader) -> Result<Option<u64>, String> {
+        let mut position = group.data_start;
+
+        while position < group.data_end {
+            let child = self.read_header(position, group.data_end)?;
+            if child.id == EBML_ID_BLOCK {
+                return Ok(Some(child.data_start));
+            }
+            position = child.data_end;
+        }
+
+        Ok(None)
+    }
+
+    /// Read an element header; an unknown size extends to `limit`.
+    fn read_header(&mut self, position: u64, limit: u64) -> Result<ElementHeader, String> {
+        let len = limit.saturating_sub(position).min(MAX_ELEMENT_HEADER_LEN);
+        let bytes = self.read_bytes(position, len)?;
+        let (id, id_len) =
+            read_element_id(&bytes, 0).ok_or_else(|| "Invalid EBML element ID".to_string())?;
+        let (size, size_len) = read_size_vint(&bytes, id_len)
+            .ok_or_else(|| "Invalid EBML element size".to_string())?;
+        let data_start = position + (id_len + size_len) as u64;
+        let data_end = match size {
+            Some(size) => data_start
+                .checked_add(size)
+                .filter(|end| *end <= limit)
+                .ok_or_else(|| "Truncated Matroska element payload".to_string())?,
+            None => limit,
+        };
+
+        Ok(ElementHeader {
+            id,
+            data_start,
+            data_end,
+            unknown_size: size.is_none(),
+        })
+    }
+
+    fn read_body(&mut self, header: &ElementHeader, max_size: u64) -> Result<Vec<u8>, String> {
+        let size = header.data_end - header.data_start;
+        if size > max_size {
+            return Err("Matroska element exceeds supported size limit".to_string());
+        }
+        self.read_bytes(header.data_start, size)
+    }
+
+    fn read_bytes(&mut self, position: u64, len: u64) -> Result<Vec<u8>, String> {
+        self.source
+            .seek(SeekFrom::Start(position))
+            .map_err(io_error)?;
+        let mut bytes = vec![0; len as usize];
+        self.source.read_exact(&mut bytes).map_err(io_error)?;
+        self.bytes_read += len;
+        Ok(bytes)
+    }
+}
+
+fn parse_seek_head(
+    data: &[u8],
+    segment_start: u64,
+    seeks: &mut Vec<(u32, u64)>,
+) -> Result<(), String> {
+    let mut pos = 0;
+
+    while pos < data.len() {
+        let (id, data_start, data_end) = next_element(data, pos, data.len())?;
+        if id == EBML_ID_SEEK {
+            let mut seek_id = None;
+            let mut seek_position = None;
+            let mut child = data_start;
+            while child < data_end {
+                let (child_id, child_start, child_end) = next_element(data, child, data_end)?;
+                match child_id {
+                    EBML_ID_SEEK_ID => {
+                        seek_id = read_element_id(&data[child_start..child_end], 0)
+                            .map(|(seek_id, _)| seek_id);
+                    }
+                    EBML_ID_SEEK_POSITION => {
+                        seek_position = Some(read_uint(data, child_start, child_end)?);
+                    }
+                    _ => {}
+                }
+                child = child_end;
+            }
+            if let (Some(seek_id), Some(seek_position)) = (seek_id, seek_position) {
+                seeks.push((seek_id, segment_start.saturating_add(seek_position)));
+            }
+        }
+        pos = data_end;
+    }
+
+    Ok(())
+}
+
+/// Collect the read targets of one track from a `Cues` payload.
+fn parse_cues(
+    data: &[u8],
+    segment_start: u64,
+    track_number: u64,
+) -> Result<Vec<ReadTarget>, String> {
+    let mut targets = Vec::new();
+    let mut pos = 0;
+
+    while pos < data.len() {
+        let (id, point_start, point_end) = next_element(data, pos, data.len())?;
+        if id == EBML_ID_CUE_POINT {
+            let mut child = point_start;
+            while child < point_end {
+                let (child_id, child_start, child_end) = next_element(data, child, point_end)?;
+                if child_id == EBML_ID_CUE_TRACK_POSITIONS
+                    && let Some(target) =
+                        parse_cue_track_positions(data, child_start, child_end, track_number)?
+                {
+                    targets.push(match target {
+                        ReadTarget::Block { cluster, relative } => ReadTarget::Block {
+                            cluster: segment_start.saturating_add(cluster),
+                            relative,
+                        },
+                        ReadTarget::Cluster(cluster) => {
+                            ReadTarget::Cluster(segment_start.saturating_add(cluster))
+                        }
+                    });
+                }
+                child = child_end;
+            }
+        }
+        pos = point_end;
+    }
+
+    Ok(targets)
+}
+
+/// Parse `CueTrackPositions`, with the cluster position segment-relative.
+fn parse_cue_track_positions(
+    data: &[u8],
+    start: usize,
+    end: usize,
+    track_number: u64,
+) -> Result<Option<ReadTarget>, String> {
+    let mut track = None;
+    let mut cluster = None;
+    let mut relative = None;
+    let mut pos = start;
+
+    while pos < end {
+        let (id, data_start, data_end) = next_element(data, pos, end)?;
+        match id {
+            EBML_ID_CUE_TRACK => track = Some(read_uint(data, data_start, data_end)?),
+            EBML_ID_CUE_CLUSTER_POSITION => cluster = Some(read_uint(data, data_start, data_end)?),
+            EBML_ID_CUE_RELATIVE_POSITION => {
+                relative = Some(read_uint(data, data_start, data_end)?);
+            }
+            _ => {}
+        }
+        pos = data_end;
+    }
+
+    if track != Some(track_number) {
+        return Ok(None);
+    }
+    Ok(cluster.map(|cluster| match relative {
+        Some(relative) => ReadTarget::Block { cluster, relative },
+        None => ReadTarget::Cluster(cluster),
+    }))
+}
+
+fn io_error(error: std::io::Error) -> String {
+    format!("Failed to read Matroska data: {error}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mkv::MATROSKA_CODEC_PGS;
+    use crate::mkv::test_util::{
+        build_segment, cluster, element, encode_uint, segment_info, simple_block, track_entry,
+    };
+    use std::io::Cursor;
+
+    const VIDEO_BLOCK_SIZE: usize = 200_000;
+
+    /// Counts the reads issued to a source, like range requests.
+    struct CountingSource {
+        inner: Cursor<Vec<u8>>,
+        reads: usize,
+    }
+
+    impl Read for CountingSource {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.reads += 1;
+            self.inner.read(buf)
+        }
+    }
+
+    impl Seek for CountingSource {
+        fn seek(&mut self, position: SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(position)
+        }
+    }
+
+    /// A video track with large blocks and a PGS track with one block per
+    /// cluster (at 100, 1100 and 2100 ms).
+    fn build_muxed_file(with_cues: bool) -> Vec<u8> {
+        let cued: &[usize] = if with_cues { &[0, 1, 2] } else { &[] };
+        build_file(3, &[0, 1, 2], cued, VIDEO_BLOCK_SIZE)
+    }
+
+    /// `cluster_count` clusters one second apart, each with a video block, and
+    /// a PGS block 100 ms into each `subtitled` cluster. Only the PGS blocks
+    /// of the `cued` clusters get cue points; without any there are no Cues.
+    fn build_file(
+        cluster_count: usize,
+        subtitled: &[usize],
+        cued: &[usize],
+        video_block_size: usize,
+    ) -> Vec<u8> {
+        let video = element(
+            EBML_ID_TRACK_ENTRY,
+            &[
+                element(EBML_ID_TRACK_NUMBER, &[1]),
+                element(EBML_ID_TRACK_TYPE, &[1]),
+                element(EBML_ID_CODEC_ID, b"V_TEST"),
+            ]
+            .concat(),
+        );
+        let subtitle = track_entry(2, MATROSKA_CODEC_PGS, None, "eng", &[]);
+        let tracks = element(EBML_ID_TRACKS, &[video, subtitle].concat());
+
+        let video_block = simple_block(1, 0, &vec![0u8; video_block_size]);
+        let clusters: Vec<Vec<u8>> = (0..cluster_count)
+            .map(|index| {
+                let mut blocks = vec![video_block.clone()];
+                if subtitled.contains(&index) {
+                    blocks.push(simple_block(2, 100, &[0x16, index as u8]));
+                }
+                cluster(index as u64 * 1000, &blocks)
+            })
+            .collect();
+
+        if cued.is_empty() {
+            return build_segment(&[segment_info(), tracks, clusters.concat()]);
+        }
+
+        // SeekPosition is 8 bytes wide so the SeekHead size is known up front
+        let seek_head = |cues_position: u64| {
+            element(
+                EBML_ID_SEEK_HEAD,
+                &element(
+                    EBML_ID_SEEK,
+                    &[
+                        element(EBML_ID_SEEK_ID, &EBML_ID_CUES.to_be_bytes()),
+                        element(EBML_ID_SEEK_POSITION, &cues_position.to_be_bytes()),
+                    ]
+                    .concat(),
+                ),
+            )
+        };
+        let info = segment_info();
+        let mut cluster_positions = Vec::new();
+        let mut position = (seek_head(0).len() + info.len() + tracks.len()) as u64;
+        for cluster in &clusters {
+            cluster_positions.push(position);
+            position += cluster.len() as u64;
+        }
+
+        // The subtitle block follows the cluster Timestamp and the video block
+        let relative = |index: u64| {
+            (element(EBML_ID_CLUSTER_TIMESTAMP, &encode_uint(index * 1000)).len()
+                + video_block.len()) as u64
+        };
+        let cue_points: Vec<Vec<u8>> = cluster_positions
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| subtitled.contains(index) && cued.contains(index))
+            .map(|(index, &cluster_position)| {
+                let mut positions = vec![
+                    element(EBML_ID_CUE_TRACK, &[2]),
+                    element(EBML_ID_CUE_CLUSTER_POSITION, &encode_uint(cluster_position)),
+                ];
+                // Leave the middle cue without a relative position
+                if index != 1 {
+                    positions.push(element(
+                        EBML_ID_CUE_RELATIVE_POSITION,
+                        &encode_uint(relative(index as u64)),
+                    ));
+                }
+                element(
+                    EBML_ID_CUE_POINT,
+                    &element(EBML_ID_CUE_TRACK_POSITIONS, &positions.concat()),
+                )
+            })
+            .collect();
+        let cues = element(EBML_ID_CUES, &cue_points.concat());
+
+        build_segment(&[seek_head(position), info, tracks, clusters.concat(), cues])
+    }
+
+    fn frame_summary(reader: &MatroskaReader<Cursor<Vec<u8>>>) -> Vec<(u32, Vec<u8>)> {
+        reader
+            .frames()
+            .iter()
+            .map(|frame| (frame.timestamp_ms, frame.payload(&[]).to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn reads_cued_blocks_without_touching_video() {
+        let file = build_muxed_file(true);
+        let file_len = file.len() as u64;
+        let mut reader = MatroskaReader::open(Cursor::new(file)).expect("open");
+        assert_eq!(reader.tracks().len(), 1);
+
+        reader
+            .select_track(MATROSKA_CODEC_PGS, None)
+            .expect("track");
+        assert_eq!(reader.read_next(), Ok(Some(1)));
+        assert_eq!(frame_summary(&reader), vec![(100, vec![0x16, 0])]);
+
+        reader.read_to_end().expect("frames");
+        assert!(reader.is_complete());
+        assert_eq!(
+            frame_summary(&reader),
+            vec![
+                (100, vec![0x16, 0]),
+                (1100, vec![0x16, 1]),
+                (2100, vec![0x16, 2]),
+            ]
+        );
+        assert!(
+            reader.bytes_read() < 1024,
+            "read {} of {file_len}",
+            reader.bytes_read()
+        );
+    }
+
+    #[test]
+    fn scans_clusters_without_cues() {
+        let file = build_muxed_file(false);
+        let mut reader = MatroskaReader::open(Cursor::new(file)).expect("open");
+        reader
+            .select_track(MATROSKA_CODEC_PGS, Some(2))
+            .expect("track");
+
+        reader.read_to_end().expect("frames");
+        assert_eq!(
+            frame_summary(&reader)
+                .iter()
+                .map(|(timestamp, _)| *timestamp)
+                .collect::<Vec<_>>(),
+            vec![100, 1100, 2100]
+        );
+        assert!(reader.bytes_read() < 1024);
+        assert!(reader.select_track(MATROSKA_CODEC_PGS, Some(1)).is_err());
+    }
+
+    #[test]
+    fn follows_cues_past_subtitle_free_clusters() {
+        let subtitled = [0, 250, 499];
+        let file = build_file(500, &subtitled, &subtitled, 1000);
+        let source = CountingSource {
+            inner: Cursor::new(file),
+            reads: 0,
+        };
+        let mut reader = MatroskaReader::open(source).expect("open");
+        reader
+            .select_track(MATROSKA_CODEC_PGS, None)
+            .expect("track");
+
+        reader.read_to_end().expect("frames");
+        let timestamps: Vec<u32> = reader
+            .frames()
+            .iter()
+            .map(|frame| frame.timestamp_ms)
+            .collect();
+        assert_eq!(timestamps, vec![100, 250_100, 499_100]);
+        assert!(reader.source.reads < 32, "{} reads", reader.source.reads);
+
+        // Cues that leave a block out are trusted unless a full scan is asked for
+        let file = build_file(500, &subtitled, &[0, 499], 1000);
+        let mut reader = MatroskaReader::open(Cursor::new(file)).expect("open");
+        reader
+            .select_track(MATROSKA_CODEC_PGS, None)
+            .expect("track");
+        assert_eq!(reader.read_to_end().expect("frames").len(), 2);
+        reader.set_full_scan(true);
+        reader
+            .select_track(MATROSKA_CODEC_PGS, None)
+            .expect("track");
+        assert_eq!(reader.read_to_end().expect("frames").len(), 3);
+    }
+}
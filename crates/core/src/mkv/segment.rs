@@ -0,0 +1,850 @@
+//! Matroska segment walking: track headers and subtitle block extraction.
+
+use miniz_oxide::inflate::{TINFLStatus, decompress_to_vec_zlib_with_limit};
+use std::collections::HashMap;
+use std::ops::Range;
+
+use super::ebml::*;
+
+/// Codec ID of DVD (VobSub) subtitle tracks.
+pub const MATROSKA_CODEC_VOBSUB: &str = "S_VOBSUB";
+/// Codec ID of Blu-ray PGS subtitle tracks.
+pub const MATROSKA_CODEC_PGS: &str = "S_HDMV/PGS";
+/// Codec ID of DVB subtitle tracks.
+pub const MATROSKA_CODEC_DVBSUB: &str = "S_DVBSUB";
+
+pub(crate) const MATROSKA_SUBTITLE_TRACK_TYPE: u64 = 0x11;
+const MAX_CODEC_PRIVATE_SIZE: usize = 1 << 16;
+pub(crate) const MAX_BLOCK_PAYLOAD_SIZE: usize = 1 << 20;
+const MAX_TRACK_FRAMES: usize = 65_536;
+const MAX_CONTENT_COMP_SETTINGS_SIZE: usize = 1 << 12;
+const LACING_NONE: u8 = 0;
+const LACING_XIPH: u8 = 1;
+const LACING_FIXED: u8 = 2;
+const LACING_EBML: u8 = 3;
+
+/// A subtitle track declared in the Matroska `Tracks` element.
+#[derive(Debug, Clone, Default)]
+pub struct MatroskaTrack {
+    /// Track number used by blocks
+    pub track_number: u64,
+    /// Codec ID (e.g. `S_VOBSUB`, `S_HDMV/PGS`, `S_DVBSUB`)
+    pub codec_id: String,
+    /// `Language` (ISO 639-2)
+    pub language: Option<String>,
+    /// `LanguageIETF` (BCP 47)
+    pub language_ietf: Option<String>,
+    /// Track name
+    pub name: Option<String>,
+    /// `FlagDefault`: eligible for automatic selection
+    pub flag_default: bool,
+    /// `FlagForced`: shown even when subtitles are off
+    pub flag_forced: bool,
+    /// `FlagHearingImpaired`: intended for hearing-impaired viewers
+    pub flag_hearing_impaired: bool,
+    /// `FlagOriginal`: in the content's original language
+    pub flag_original: bool,
+    /// Codec-specific initialization data
+    pub codec_private: Option<Vec<u8>>,
+    /// `DefaultDuration` in nanoseconds, which spaces the frames of a laced block
+    pub default_duration_ns: Option<u64>,
+    pub(crate) compression: TrackCompression,
+}
+
+impl MatroskaTrack {
+    /// `LanguageIETF` when present, otherwise `Language`.
+    pub fn preferred_language(&self) -> Option<&str> {
+        self.language_ietf
+            .as_deref()
+            .or(self.language.as_deref())
+            .filter(|value| !value.is_empty())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) enum TrackCompression {
+    #[default]
+    None,
+    Zlib,
+    HeaderStrip(Vec<u8>),
+}
+
+#[derive(Debug, Clone)]
+enum TrackPayload {
+    BorrowedRange(Range<usize>),
+    Owned(Vec<u8>),
+}
+
+/// A decoded subtitle block of one track.
+#[derive(Debug, Clone)]
+pub struct MatroskaFrame {
+    /// Presentation time in milliseconds
+    pub timestamp_ms: u32,
+    /// Display duration from `BlockDuration`, in milliseconds
+    pub duration_ms: Option<u32>,
+    payload: TrackPayload,
+}
+
+impl MatroskaFrame {
+    /// Get the block payload, decompressed, borrowing from the file data when
+    /// it was stored uncompressed.
+    pub fn payload<'a>(&'a self, data: &'a [u8]) -> &'a [u8] {
+        match &self.payload {
+            TrackPayload::BorrowedRange(range) => &data[range.clone()],
+            TrackPayload::Owned(payload) => payload,
+        }
+    }
+
+    /// Copy a borrowed payload out of `data`, so the frame no longer refers
+    /// to it.
+    pub(crate) fn into_owned(self, data: &[u8]) -> MatroskaFrame {
+        match self.payload {
+            TrackPayload::BorrowedRange(range) => MatroskaFrame {
+                payload: TrackPayload::Owned(data[range].to_vec()),
+                ..self
+            },
+            TrackPayload::Owned(_) => self,
+        }
+    }
+}
+
+/// Segment layout and subtitle tracks of a Matroska file.
+#[derive(Debug, Clone)]
+pub struct MatroskaSegment {
+    data_start: usize,
+    data_end: usize,
+    /// Nanoseconds per timestamp tick (`TimestampScale`)
+    pub timescale_ns: u64,
+    /// Subtitle tracks, in header order
+    pub tracks: Vec<MatroskaTrack>,
+}
+
+impl MatroskaSegment {
+    /// Find the first track with the given codec ID.
+    pub fn find_track(&self, codec_id: &str) -> Option<&MatroskaTrack> {
+        self.tracks.iter().find(|track| track.codec_id == codec_id)
+    }
+
+    /// Find a track of the given codec by number, or the first one when
+    /// `track_number` is `None`.
+    pub fn select_track(
+        &self,
+        codec_id: &str,
+        track_number: Option<u64>,
+    ) -> Result<&MatroskaTrack, String> {
+        select_track(&self.tracks, codec_id, track_number)
+    }
+
+    /// Collect the blocks of one track from every cluster, in file order.
+    pub fn read_frames(
+        &self,
+        data: &[u8],
+        track: &MatroskaTrack,
+    ) -> Result<Vec<MatroskaFrame>, String> {
+        let mut frames = Vec::new();
+        let mut pos = self.data_start;
+
+        while pos < self.data_end {
+            let (id, data_start, data_end) = next_element(data, pos, self.data_end)?;
+            if id == EBML_ID_CLUSTER {
+                parse_cluster(
+                    data,
+                    data_start,
+                    data_end,
+                    track,
+                    self.timescale_ns,
+                    &mut frames,
+                )?;
+            }
+            pos = data_end;
+        }
+
+        Ok(frames)
+    }
+
+    /// Count the blocks of every track number, without decoding payloads.
+    pub fn block_counts(&self, data: &[u8]) -> Result<HashMap<u64, usize>, String> {
+        let mut counts = HashMap::new();
+        let mut pos = self.data_start;
+
+        while pos < self.data_end {
+            let (id, data_start, data_end) = next_element(data, pos, self.data_end)?;
+            if id == EBML_ID_CLUSTER {
+                count_cluster_blocks(data, data_start, data_end, &mut counts)?;
+            }
+            pos = data_end;
+        }
+
+        Ok(counts)
+    }
+}
+
+/// Parse the segment headers (info and tracks) of a Matroska file.
+pub fn parse_matroska_segment(data: &[u8]) -> Result<MatroskaSegment, String> {
+    let (data_start, data_end) = find_segment(data)?;
+    let mut segment = MatroskaSegment {
+        data_start,
+        data_end,
+        timescale_ns: 1_000_000,
+        tracks: Vec::new(),
+    };
+    let mut pos = data_start;
+
+    while pos < data_end {
+        let (id, element_start, element_end) = next_element(data, pos, data_end)?;
+
+        match id {
+            EBML_ID_SEGMENT_INFO => {
+                parse_segment_info(data, element_start, element_end, &mut segment.timescale_ns)?
+            }
+            EBML_ID_TRACKS => parse_tracks(data, element_start, element_end, &mut segment.tracks)?,
+            _ => {}
+        }
+
+        pos = element_end;
+    }
+
+    Ok(segment)
+}
+
+pub(crate) fn select_track<'a>(
+    tracks: &'a [MatroskaTrack],
+    codec_id: &str,
+    track_number: Option<u64>,
+) -> Result<&'a MatroskaTrack, String> {
+    match track_number {
+        None => tracks
+            .iter()
+            .find(|track| track.codec_id == codec_id)
+            .ok_or_else(|| format!("No {codec_id} track found in Matroska container")),
+        Some(number) => {
+            let track = tracks
+                .iter()
+                .find(|track| track.track_number == number)
+                .ok_or_else(|| format!("Matroska subtitle track {number} not found"))?;
+            if track.codec_id != codec_id {
+                return Err(format!(
+                    "Matroska track {number} is {}, not {codec_id}",
+                    track.codec_id
+                ));
+            }
+            Ok(track)
+        }
+    }
+}
+
+fn find_segment(data: &[u8]) -> Result<(usize, usize), String> {
+    let mut pos = 0usize;
+
+    while pos < data.len() {
+        let (id, id_len) =
+            read_element_id(data, pos).ok_or_else(|| "Invalid EBML element ID".to_string())?;
+        let size_pos = pos + id_len;
+        let (size, size_len) = read_size_vint(data, size_pos)
+            .ok_or_else(|| "Invalid EBML element size".to_string())?;
+        let data_start = size_pos + size_len;
+        let data_end = match size {
+            Some(size) => data_start
+                .checked_add(size as usize)
+                .filter(|end| *end <= data.len())
+                .ok_or_else(|| "Truncated Matroska element payload".to_string())?,
+            None => data.len(),
+        };
+
+        if id == EBML_ID_SEGMENT {
+            return Ok((data_start, data_end));
+        }
+
+        pos = data_end;
+    }
+
+    Err("Matroska Segment element not found".to_string())
+}
+
+pub(crate) fn parse_segment_info(
+    data: &[u8],
+    start: usize,
+    end: usize,
+    timescale_ns: &mut u64,
+) -> Result<(), String> {
+    let mut pos = start;
+
+    while pos < end {
+        let (id, data_start, data_end) = next_element(data, pos, end)?;
+        if id == EBML_ID_TIMECODE_SCALE {
+            *timescale_ns = read_uint(data, data_start, data_end)?;
+        }
+        pos = data_end;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn parse_tracks(
+    data: &[u8],
+    start: usize,
+    end: usize,
+    tracks: &mut Vec<MatroskaTrack>,
+) -> Result<(), String> {
+    let mut pos = start;
+
+    while pos < end {
+        let (id, data_start, data_end) = next_element(data, pos, end)?;
+        if id == EBML_ID_TRACK_ENTRY
+            && let Some(track) = parse_track_entry(data, data_start, data_end)?
+        {
+            tracks.push(track);
+        }
+        pos = data_end;
+    }
+
+    Ok(())
+}
+
+/// Parse a track entry, returning `None` for non-subtitle tracks.
+fn parse_track_entry(
+    data: &[u8],
+    start: usize,
+    end: usize,
+) -> Result<Option<MatroskaTrack>, String> {
+    let mut track = MatroskaTrack {
+        flag_default: true,
+        ..MatroskaTrack::default()
+    };
+    let mut track_type = 0u64;
+    let mut pos = start;
+
+    while pos < end {
+        let (id, data_start, data_end) = next_element(data, pos, end)?;
+
+        match id {
+            EBML_ID_TRACK_NUMBER => track.track_number = read_uint(data, data_start, data_end)?,
+            EBML_ID_TRACK_TYPE => track_type = read_uint(data, data_start, data_end)?,
+            EBML_ID_CODEC_ID => track.codec_id = read_string(data, data_start, data_end),
+            EBML_ID_CODEC_PRIVATE => {
+                let size = data_end - data_start;
+                if size > MAX_CODEC_PRIVATE_SIZE {
+                    return Err("Matroska CodecPrivate exceeds supported size limit".to_string());
+                }
+                track.codec_private = Some(data[data_start..data_end].to_vec());
+            }
+            EBML_ID_LANGUAGE => track.language = Some(read_string(data, data_start, data_end)),
+            EBML_ID_LANGUAGE_IETF => {
+                track.language_ietf = Some(read_string(data, data_start, data_end));
+            }
+            EBML_ID_NAME => track.name = Some(read_string(data, data_start, data_end)),
+            EBML_ID_FLAG_DEFAULT => {
+                track.flag_default = read_uint(data, data_start, data_end)? != 0
+            }
+            EBML_ID_FLAG_FORCED => track.flag_forced = read_uint(data, data_start, data_end)? != 0,
+            EBML_ID_FLAG_HEARING_IMPAIRED => {
+                track.flag_hearing_impaired = read_uint(data, data_start, data_end)? != 0;
+            }
+            EBML_ID_FLAG_ORIGINAL => {
+                track.flag_original = read_uint(data, data_start, data_end)? != 0;
+            }
+            EBML_ID_DEFAULT_DURATION => {
+                track.default_duration_ns = Some(read_uint(data, data_start, data_end)?);
+            }
+            EBML_ID_CONTENT_ENCODINGS => {
+                track.compression = parse_content_encodings(data, data_start, data_end)?;
+            }
+            _ => {}
+        }
+
+        pos = data_end;
+    }
+
+    if track_type != MATROSKA_SUBTITLE_TRACK_TYPE || track.track_number == 0 {
+        return Ok(None);
+    }
+    Ok(Some(track))
+}
+
+fn parse_cluster(
+    data: &[u8],
+    start: usize,
+    end: usize,
+    track: &MatroskaTrack,
+    timescale_ns: u64,
+    frames: &mut Vec<MatroskaFrame>,
+) -> Result<(), String> {
+    let mut cluster_timestamp = 0i64;
+    let mut pos = start;
+
+    while pos < end {
+        let (id, data_start, data_end) = next_element(data, pos, end)?;
+        let block = BlockTiming {
+            cluster_timestamp,
+            timescale_ns,
+            duration_ticks: None,
+        };
+
+        match id {
+            EBML_ID_CLUSTER_TIMESTAMP => {
+                cluster_timestamp = read_uint(data, data_start, data_end)? as i64;
+            }
+            EBML_ID_SIMPLE_BLOCK => {
+                parse_block(data, data_start, data_end, track, &block, frames)?;
+            }
+            EBML_ID_BLOCK_GROUP => {
+                parse_block_group(data, data_start, data_end, track, block, frames)?;
+            }
+            _ => {}
+        }
+
+        pos = data_end;
+    }
+
+    Ok(())
+}
+
+fn count_cluster_blocks(
+    data: &[u8],
+    start: usize,
+    end: usize,
+    counts: &mut HashMap<u64, usize>,
+) -> Result<(), String> {
+    let mut pos = start;
+
+    while pos < end {
+        let (id, data_start, data_end) = next_element(data, pos, end)?;
+        let block = match id {
+            EBML_ID_SIMPLE_BLOCK => Some(data_start),
+            EBML_ID_BLOCK_GROUP => {
+                let mut child = data_start;
+                let mut block = None;
+                while child < data_end {
+                    let (child_id, child_start, child_end) = next_element(data, child, data_end)?;
+                    if child_id == EBML_ID_BLOCK {
+                        block = Some(child_start);
+                        break;
+                    }
+                    child = child_end;
+                }
+                block
+            }
+            _ => None,
+        };
+        if let Some(block_start) = block {
+            let (track_num, _) = read_vint(&data[..data_end], block_start)
+                .ok_or_else(|| "Invalid Matroska block track number".to_string())?;
+            *counts.entry(track_num).or_default() += 1;
+        }
+        pos = data_end;
+    }
+
+    Ok(())
+}
+
+/// Timing context shared by the frames of one block.
+pub(crate) struct BlockTiming {
+    pub(crate) cluster_timestamp: i64,
+    pub(crate) timescale_ns: u64,
+    /// `BlockDuration`, in timestamp ticks
+    pub(crate) duration_ticks: Option<u64>,
+}
+
+pub(crate) fn parse_block_group(
+    data: &[u8],
+    start: usize,
+    end: usize,
+    track: &MatroskaTrack,
+    mut timing: BlockTiming,
+    frames: &mut Vec<MatroskaFrame>,
+) -> Result<(), String> {
+    let mut block = None;
+    let mut pos = start;
+
+    while pos < end {
+        let (id, data_start, data_end) = next_element(data, pos, end)?;
+        match id {
+            EBML_ID_BLOCK => block = Some((data_start, data_end)),
+            EBML_ID_BLOCK_DURATION => {
+                timing.duration_ticks = Some(read_uint(data, data_start, data_end)?);
+            }
+            _ => {}
+        }
+        pos = data_end;
+    }
+
+    match block {
+        Some((block_start, block_end)) => {
+            parse_block(data, block_start, block_end, track, &timing, frames)
+        }
+        None => Ok(()),
+    }
+}
+
+pub(crate) fn parse_block(
+    source_data: &[u8],
+    start: usize,
+    end: usize,
+    track: &MatroskaTrack,
+    timing: &BlockTiming,
+    frames: &mut Vec<MatroskaFrame>,
+) -> Result<(), String> {
+    let data = &source_data[start..end];
+    let (track_num, track_num_len) =
+        read_vint(data, 0).ok_or_else(|| "Invalid Matroska block track number".to_string())?;
+
+    if track_num != track.track_number {
+        return Ok(());
+    }
+
+    if data.len() < track_num_len + 3 {
+        return Err("Truncated Matroska block header".to_string());
+    }
+
+    let relative_timestamp =
+        i16::from_be_bytes([data[track_num_len], data[track_num_len + 1]]) as i64;
+    let flags = data[track_num_len + 2];
+
+    let payload_offset = track_num_len + 3;
+    let payload = &data[payload_offset..];
+    if payload.is_empty() {
+        return Ok(());
+    }
+
+    let absolute_ticks = timing.cluster_timestamp.saturating_add(relative_timestamp);
+    if absolute_ticks < 0 {
+        return Err("Matroska subtitle block timestamp underflowed before zero".to_string());
+    }
+
+    let timestamp_ns = absolute_ticks as u128 * timing.timescale_ns as u128;
+    let block_duration_ns = timing
+        .duration_ticks
+        .map(|ticks| ticks as u128 * timing.timescale_ns as u128);
+
+    // Laced frames follow each other DefaultDuration apart; without one, the
+    // BlockDuration (which covers the whole lace) is shared out evenly
+    let ranges = split_laced_frames(payload, (flags >> 1) & 0x03)?;
+    let frame_count = ranges.len();
+    let frame_step_ns = match (frame_count, track.default_duration_ns, block_duration_ns) {
+        (1, _, _) => 0,
+        (_, Some(default_duration), _) => default_duration as u128,
+        (_, None, Some(block_duration)) => block_duration / frame_count as u128,
+        (_, None, None) => {
+            return Err(
+                "Laced Matroska subtitle block has no DefaultDuration or BlockDuration".to_string(),
+            );
+        }
+    };
+
+    for (index, range) in ranges.into_iter().enumerate() {
+        let offset_ns = index as u128 * frame_step_ns;
+        let duration_ns = if index + 1 < frame_count {
+            Some(frame_step_ns)
+        } else {
+            block_duration_ns
+                .map(|block_duration| block_duration.saturating_sub(offset_ns))
+                .or((frame_count > 1).then_some(frame_step_ns))
+        };
+        let frame = &payload[range.clone()];
+        if frame.is_empty() {
+            continue;
+        }
+        if frame.len() > MAX_BLOCK_PAYLOAD_SIZE {
+            return Err("Matroska subtitle block exceeds supported size limit".to_string());
+        }
+        let frame_start = start + payload_offset + range.start;
+        let frame_end = start + payload_offset + range.end;
+        push_frame(
+            frames,
+            MatroskaFrame {
+                timestamp_ms: ns_to_ms(timestamp_ns + offset_ns),
+                duration_ms: duration_ns.map(ns_to_ms),
+                payload: decode_track_payload(frame, frame_start..frame_end, &track.compression)?,
+            },
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Split a block payload into its frames according to the lacing mode
+/// (0 none, 1 Xiph, 2 fixed-size, 3 EBML). Ranges are relative to `payload`.
+fn split_laced_frames(payload: &[u8], lacing: u8) -> Result<Vec<Range<usize>>, String> {
+    let truncated = || "Truncated Matroska lacing header".to_string();
+    let (frame_count, mut pos) = match lacing {
+        LACING_NONE => (1, 0usize),
+        _ => (*payload.first().ok_or_else(truncated)? as usize + 1, 1usize),
+    };
+    let mut sizes = Vec::with_capacity(frame_count);
+
+    match lacing {
+        LACING_NONE => {}
+        LACING_XIPH => {
+            for _ in 0..frame_count - 1 {
+                let mut size = 0usize;
+                loop {
+                    let byte = *payload.get(pos).ok_or_else(truncated)?;
+                    pos += 1;
+                    size += byte as usize;
+                    if byte != 0xFF {
+                        break;
+                    }
+                }
+                sizes.push(size);
+            }
+        }
+        LACING_EBML => {
+            if frame_count > 1 {
+                let (first, width) = read_vint(payload, pos).ok_or_else(truncated)?;
+                pos += width;
+                let mut size = first as i64;
+                sizes.push(first as usize);
+                for _ in 0..frame_count - 2 {
+                    let (raw, width) = read_vint(payload, pos).ok_or_else(truncated)?;
+                    pos += width;
+                    let bias = (1i64 << (7 * width - 1)) - 1;
+                    size = size.saturating_add(raw as i64 - bias);
+                    if size < 0 {
+                        return Err("Invalid Matroska EBML lace size".to_string());
+                    }
+                    sizes.push(size as usize);
+                }
+            }
+        }
+        LACING_FIXED => {
+            let data_len = payload.len() - pos;
+            if data_len % frame_count != 0 {
+                return Err("Matroska fixed-size lace does not divide the block".to_string());
+            }
+            sizes.resize(frame_count - 1, data_len / frame_count);
+        }
+        _ => unreachable!("lacing is a 2-bit field"),
+    }
+
+    let laced_len = sizes
+        .iter()
+        .try_fold(0usize, |total, &size| total.checked_add(size))
+        .filter(|&total| total <= payload.len() - pos)
+        .ok_or_else(|| "Matroska lace sizes exceed the block size".to_string())?;
+    sizes.push(payload.len() - pos - laced_len);
+
+    let mut ranges = Vec::with_capacity(frame_count);
+    for size in sizes {
+        ranges.push(pos..pos + size);
+        pos += size;
+    }
+    Ok(ranges)
+}
+
+fn parse_content_encodings(
+    data: &[u8],
+    start: usize,
+    end: usize,
+) -> Result<TrackCompression, String> {
+    let mut pos = start;
+
+    while pos < end {
+        let (id, data_start, data_end) = next_element(data, pos, end)?;
+        if id == EBML_ID_CONTENT_ENCODING {
+            let compression = parse_content_encoding(data, data_start, data_end)?;
+            if !matches!(compression, TrackCompression::None) {
+                return Ok(compression);
+            }
+        }
+        pos = data_end;
+    }
+
+    Ok(TrackCompression::None)
+}
+
+fn parse_content_encoding(
+    data: &[u8],
+    start: usize,
+    end: usize,
+) -> Result<TrackCompression, String> {
+    let mut pos = start;
+
+    while pos < end {
+        let (id, data_start, data_end) = next_element(data, pos, end)?;
+        if id == EBML_ID_CONTENT_COMPRESSION {
+            return parse_content_compression(data, data_start, data_end);
+        }
+        pos = data_end;
+    }
+
+    Ok(TrackCompression::None)
+}
+
+fn parse_content_compression(
+    data: &[u8],
+    start: usize,
+    end: usize,
+) -> Result<TrackCompression, String> {
+    if start == end {
+        return Ok(TrackCompression::Zlib);
+    }
+
+    let mut algo = 0u64;
+    let mut settings = None;
+    let mut pos = start;
+
+    while pos < end {
+        let (id, data_start, data_end) = next_element(data, pos, end)?;
+        match id {
+            EBML_ID_CONTENT_COMP_ALGO => algo = read_uint(data, data_start, data_end)?,
+            EBML_ID_CONTENT_COMP_SETTINGS => {
+                let settings_len = data_end - data_start;
+                if settings_len > MAX_CONTENT_COMP_SETTINGS_SIZE {
+                    return Err(
+                        "Matroska content compression settings exceed supported size limit"
+                            .to_string(),
+                    );
+                }
+                settings = Some(data[data_start..data_end].to_vec());
+            }
+            _ => {}
+        }
+        pos = data_end;
+    }
+
+    match algo {
+        0 => Ok(TrackCompression::Zlib),
+        3 => Ok(TrackCompression::HeaderStrip(settings.unwrap_or_default())),
+        other => Err(format!(
+            "Unsupported Matroska content compression algorithm: {other}"
+        )),
+    }
+}
+
+fn decode_track_payload(
+    payload: &[u8],
+    payload_range: Range<usize>,
+    compression: &TrackCompression,
+) -> Result<TrackPayload, String> {
+    let decoded = match compression {
+        TrackCompression::None => TrackPayload::BorrowedRange(payload_range),
+        TrackCompression::Zlib => {
+            decompress_to_vec_zlib_with_limit(payload, MAX_BLOCK_PAYLOAD_SIZE)
+                .map_err(|error| match error.status {
+                    TINFLStatus::HasMoreOutput => {
+                        "Inflated Matroska subtitle block exceeds supported size limit".to_string()
+                    }
+                    TINFLStatus::Adler32Mismatch => {
+                        "Matroska subtitle block failed checksum verification".to_string()
+                    }
+                    _ => "Failed to inflate zlib-compressed Matroska subtitle block".to_string(),
+                })
+                .map(TrackPayload::Owned)?
+        }
+        TrackCompression::HeaderStrip(prefix) => {
+            if prefix.len().saturating_add(payload.len()) > MAX_BLOCK_PAYLOAD_SIZE {
+                return Err(
+                    "Header-stripped Matroska subtitle block exceeds supported size limit"
+                        .to_string(),
+                );
+            }
+            let mut out = Vec::with_capacity(prefix.len() + payload.len());
+            out.extend_from_slice(prefix);
+            out.extend_from_slice(payload);
+            TrackPayload::Owned(out)
+        }
+    };
+
+    Ok(decoded)
+}
+
+pub(crate) fn push_frame(
+    frames: &mut Vec<MatroskaFrame>,
+    frame: MatroskaFrame,
+) -> Result<(), String> {
+    if frames.len() >= MAX_TRACK_FRAMES {
+        return Err("Matroska subtitle track exceeds supported frame count".to_string());
+    }
+    frames.push(frame);
+    Ok(())
+}
+
+fn ns_to_ms(value_ns: u128) -> u32 {
+    (value_ns / 1_000_000).min(u32::MAX as u128) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mkv::test_util::{block_group, build_mks, cluster, laced_block_group, track_entry};
+
+    #[test]
+    fn splits_all_lacing_modes() {
+        // Xiph: 3 frames, sizes 2 and 256 (255 + 1), last takes the rest
+        let mut xiph = vec![2, 2, 0xFF, 0x01];
+        xiph.extend(std::iter::repeat_n(0xAA, 2 + 256 + 3));
+        assert_eq!(
+            split_laced_frames(&xiph, LACING_XIPH),
+            Ok(vec![4..6, 6..262, 262..265])
+        );
+
+        // EBML: 3 frames, first size 4, then a -1 difference (0xBE = 62 - 63)
+        let mut ebml = vec![2, 0x84, 0xBE];
+        ebml.extend([0xBB; 4 + 3 + 5]);
+        assert_eq!(
+            split_laced_frames(&ebml, LACING_EBML),
+            Ok(vec![3..7, 7..10, 10..15])
+        );
+
+        // Fixed-size: 2 frames of 3 bytes
+        let fixed = [1, 1, 2, 3, 4, 5, 6];
+        assert_eq!(
+            split_laced_frames(&fixed, LACING_FIXED),
+            Ok(vec![1..4, 4..7])
+        );
+        assert!(split_laced_frames(&fixed[..6], LACING_FIXED).is_err());
+
+        assert!(split_laced_frames(&[2, 9, 1], LACING_XIPH).is_err());
+    }
+
+    #[test]
+    fn reads_block_durations() {
+        let mks = build_mks(
+            &[track_entry(1, MATROSKA_CODEC_PGS, None, "eng", &[])],
+            &[cluster(
+                1000,
+                &[
+                    block_group(1, 0, &[0x80], Some(1500)),
+                    block_group(1, 2000, &[0x80], None),
+                ],
+            )],
+        );
+        let segment = parse_matroska_segment(&mks).expect("segment");
+        let frames = segment
+            .read_frames(&mks, &segment.tracks[0])
+            .expect("frames");
+        let timing: Vec<_> = frames
+            .iter()
+            .map(|frame| (frame.timestamp_ms, frame.duration_ms))
+            .collect();
+        assert_eq!(timing, vec![(1000, Some(1500)), (3000, None)]);
+    }
+
+    #[test]
+    fn shares_block_duration_across_laced_frames() {
+        let read = |blocks: Vec<Vec<u8>>| {
+            let mks = build_mks(
+                &[track_entry(1, MATROSKA_CODEC_PGS, None, "eng", &[])],
+                &[cluster(1000, &blocks)],
+            );
+            let segment = parse_matroska_segment(&mks).expect("segment");
+            segment.read_frames(&mks, &segment.tracks[0]).map(|frames| {
+                frames
+                    .iter()
+                    .map(|frame| (frame.timestamp_ms, frame.duration_ms))
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        let frames: [&[u8]; 3] = [&[0x80], &[0x81], &[0x82]];
+        assert_eq!(
+            read(vec![laced_block_group(1, 0, &frames, Some(3000))]),
+            Ok(vec![
+                (1000, Some(1000)),
+                (2000, Some(1000)),
+                (3000, Some(1000))
+            ])
+        );
+        assert!(read(vec![laced_block_group(1, 0, &frames, None)]).is_err());
+    }
+}
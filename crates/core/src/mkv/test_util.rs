@@ -0,0 +1,177 @@
+//! EBML builders for Matroska test files.
+
+use super::ebml::*;
+use super::segment::MATROSKA_SUBTITLE_TRACK_TYPE;
+
+/// Build a Matroska file (1 ms timestamp scale) from track entries and clusters.
+pub(crate) fn build_mks(track_entries: &[Vec<u8>], clusters: &[Vec<u8>]) -> Vec<u8> {
+    let tracks = element(EBML_ID_TRACKS, &track_entries.concat());
+    build_segment(&[segment_info(), tracks, clusters.concat()])
+}
+
+/// Build a Matroska file from the top-level elements of its segment.
+pub(crate) fn build_segment(children: &[Vec<u8>]) -> Vec<u8> {
+    let ebml_header = element(0x1A45_DFA3, &element(0x4286, &[0x01]));
+    [ebml_header, element(EBML_ID_SEGMENT, &children.concat())].concat()
+}
+
+/// `Info` with a 1 ms timestamp scale.
+pub(crate) fn segment_info() -> Vec<u8> {
+    element(
+        EBML_ID_SEGMENT_INFO,
+        &element(EBML_ID_TIMECODE_SCALE, &[0x0F, 0x42, 0x40]),
+    )
+}
+
+/// Build a subtitle `TrackEntry`; `extra` holds additional child elements.
+pub(crate) fn track_entry(
+    track_num: u64,
+    codec_id: &str,
+    codec_private: Option<&[u8]>,
+    language: &str,
+    extra: &[Vec<u8>],
+) -> Vec<u8> {
+    let mut children = vec![
+        element(EBML_ID_TRACK_NUMBER, &encode_uint(track_num)),
+        element(EBML_ID_TRACK_TYPE, &[MATROSKA_SUBTITLE_TRACK_TYPE as u8]),
+        element(EBML_ID_CODEC_ID, codec_id.as_bytes()),
+        element(EBML_ID_LANGUAGE, language.as_bytes()),
+    ];
+    if let Some(codec_private) = codec_private {
+        children.push(element(EBML_ID_CODEC_PRIVATE, codec_private));
+    }
+    children.extend_from_slice(extra);
+    element(EBML_ID_TRACK_ENTRY, &children.concat())
+}
+
+/// `ContentEncodings` declaring zlib compression.
+pub(crate) fn zlib_encoding() -> Vec<u8> {
+    element(
+        EBML_ID_CONTENT_ENCODINGS,
+        &element(
+            EBML_ID_CONTENT_ENCODING,
+            &element(EBML_ID_CONTENT_COMPRESSION, &[]),
+        ),
+    )
+}
+
+/// `DefaultDuration` of `duration_ns` nanoseconds.
+pub(crate) fn default_duration(duration_ns: u64) -> Vec<u8> {
+    element(EBML_ID_DEFAULT_DURATION, &encode_uint(duration_ns))
+}
+
+/// Build a `Cluster` from its timestamp and block elements.
+pub(crate) fn cluster(timestamp: u64, blocks: &[Vec<u8>]) -> Vec<u8> {
+    let mut children = vec![element(EBML_ID_CLUSTER_TIMESTAMP, &encode_uint(timestamp))];
+    children.extend_from_slice(blocks);
+    element(EBML_ID_CLUSTER, &children.concat())
+}
+
+/// Build a `SimpleBlock` element without lacing.
+pub(crate) fn simple_block(track_num: u64, relative_timestamp: i16, payload: &[u8]) -> Vec<u8> {
+    element(
+        EBML_ID_SIMPLE_BLOCK,
+        &block_body(track_num, relative_timestamp, 0x80, payload),
+    )
+}
+
+/// Build a `BlockGroup` holding one unlaced `Block` and an optional
+/// `BlockDuration` (in timestamp ticks).
+pub(crate) fn block_group(
+    track_num: u64,
+    relative_timestamp: i16,
+    payload: &[u8],
+    duration: Option<u64>,
+) -> Vec<u8> {
+    let mut children = element(
+        EBML_ID_BLOCK,
+        &block_body(track_num, relative_timestamp, 0x00, payload),
+    );
+    if let Some(duration) = duration {
+        children.extend(element(EBML_ID_BLOCK_DURATION, &encode_uint(duration)));
+    }
+    element(EBML_ID_BLOCK_GROUP, &children)
+}
+
+/// Build a `BlockGroup` holding a Xiph-laced `Block` of `frames` and an
+/// optional `BlockDuration` covering all of them.
+pub(crate) fn laced_block_group(
+    track_num: u64,
+    relative_timestamp: i16,
+    frames: &[&[u8]],
+    duration: Option<u64>,
+) -> Vec<u8> {
+    let mut payload = vec![(frames.len() - 1) as u8];
+    for frame in &frames[..frames.len() - 1] {
+        payload.extend(std::iter::repeat_n(0xFF, frame.len() / 255));
+        payload.push((frame.len() % 255) as u8);
+    }
+    payload.extend(frames.concat());
+    let mut children = element(
+        EBML_ID_BLOCK,
+        &block_body(track_num, relative_timestamp, 0x02, &payload),
+    );
+    if let Some(duration) = duration {
+        children.extend(element(EBML_ID_BLOCK_DURATION, &encode_uint(duration)));
+    }
+    element(EBML_ID_BLOCK_GROUP, &children)
+}
+
+fn block_body(track_num: u64, relative_timestamp: i16, flags: u8, payload: &[u8]) -> Vec<u8> {
+    let mut block = encode_track_number(track_num);
+    block.extend_from_slice(&relative_timestamp.to_be_bytes());
+    block.push(flags);
+    block.extend_from_slice(payload);
+    block
+}
+
+pub(crate) fn element(id: u32, payload: &[u8]) -> Vec<u8> {
+    let mut out = encode_element_id(id);
+    out.extend_from_slice(&encode_size(payload.len() as u64));
+    out.extend_from_slice(payload);
+    out
+}
+
+fn encode_element_id(id: u32) -> Vec<u8> {
+    let bytes = id.to_be_bytes();
+    let skip = bytes.iter().position(|&byte| byte != 0).unwrap_or(3);
+    bytes[skip..].to_vec()
+}
+
+fn encode_size(value: u64) -> Vec<u8> {
+    for width in 1..=8 {
+        let max_value = if width == 8 {
+            u64::MAX >> 8
+        } else {
+            (1u64 << (width * 7)) - 2
+        };
+        if value <= max_value {
+            let mut bytes = vec![0u8; width];
+            let mut temp = value;
+            for index in (0..width).rev() {
+                bytes[index] = (temp & 0xFF) as u8;
+                temp >>= 8;
+            }
+            bytes[0] |= 1 << (8 - width);
+            return bytes;
+        }
+    }
+
+    panic!("size too large for EBML vint encoding")
+}
+
+fn encode_track_number(track_num: u64) -> Vec<u8> {
+    if track_num == 0 || track_num >= 0x7F {
+        panic!("test track number must fit in a one-byte block vint")
+    }
+    vec![0x80 | track_num as u8]
+}
+
+pub(crate) fn encode_uint(value: u64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let first_non_zero = bytes
+        .iter()
+        .position(|byte| *byte != 0)
+        .unwrap_or(bytes.len() - 1);
+    bytes[first_non_zero..].to_vec()
+}
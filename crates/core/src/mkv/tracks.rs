@@ -0,0 +1,129 @@
+//! Subtitle track listing for track pickers.
+
+use super::segment::{
+    MATROSKA_CODEC_DVBSUB, MATROSKA_CODEC_PGS, MATROSKA_CODEC_VOBSUB, MatroskaTrack,
+    parse_matroska_segment,
+};
+
+/// Subtitle codecs libbitsub can decode from Matroska.
+const SUPPORTED_CODECS: [&str; 3] = [
+    MATROSKA_CODEC_VOBSUB,
+    MATROSKA_CODEC_PGS,
+    MATROSKA_CODEC_DVBSUB,
+];
+
+/// A subtitle track with the number of blocks it has in the file.
+#[derive(Debug, Clone)]
+pub struct MatroskaTrackInfo {
+    pub track: MatroskaTrack,
+    /// Number of `SimpleBlock`/`Block` elements carrying this track
+    pub block_count: usize,
+}
+
+impl MatroskaTrackInfo {
+    /// Whether the track's codec can be decoded.
+    pub fn is_supported(&self) -> bool {
+        SUPPORTED_CODECS.contains(&self.track.codec_id.as_str())
+    }
+}
+
+/// List every subtitle track of a Matroska file, in header order.
+pub fn list_matroska_tracks(data: &[u8]) -> Result<Vec<MatroskaTrackInfo>, String> {
+    let segment = parse_matroska_segment(data)?;
+    let counts = segment.block_counts(data)?;
+    Ok(segment
+        .tracks
+        .into_iter()
+        .map(|track| MatroskaTrackInfo {
+            block_count: counts.get(&track.track_number).copied().unwrap_or(0),
+            track,
+        })
+        .collect())
+}
+
+/// Pick the track to load when the user has not chosen one: the first
+/// supported, non-empty track flagged default, else the first supported,
+/// non-empty track.
+pub fn default_matroska_track(tracks: &[MatroskaTrackInfo]) -> Option<&MatroskaTrackInfo> {
+    let mut candidates = tracks
+        .iter()
+        .filter(|info| info.is_supported() && info.block_count > 0);
+    let first = candidates.clone().next();
+    candidates.find(|info| info.track.flag_default).or(first)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mkv::ebml::{
+        EBML_ID_FLAG_DEFAULT, EBML_ID_FLAG_FORCED, EBML_ID_FLAG_HEARING_IMPAIRED,
+        EBML_ID_LANGUAGE_IETF, EBML_ID_NAME,
+    };
+    use crate::mkv::test_util::{
+        block_group, build_mks, cluster, element, simple_block, track_entry,
+    };
+
+    #[test]
+    fn lists_tracks_with_flags_and_block_counts() {
+        let mks = build_mks(
+            &[
+                track_entry(
+                    2,
+                    MATROSKA_CODEC_PGS,
+                    None,
+                    "eng",
+                    &[
+                        element(EBML_ID_FLAG_DEFAULT, &[0]),
+                        element(EBML_ID_FLAG_HEARING_IMPAIRED, &[1]),
+                        element(EBML_ID_NAME, b"SDH"),
+                    ],
+                ),
+                track_entry(
+                    3,
+                    MATROSKA_CODEC_PGS,
+                    None,
+                    "por",
+                    &[
+                        element(EBML_ID_LANGUAGE_IETF, b"pt-BR"),
+                        element(EBML_ID_FLAG_FORCED, &[1]),
+                    ],
+                ),
+                track_entry(4, "S_TEXT/UTF8", None, "eng", &[]),
+            ],
+            &[
+                cluster(0, &[simple_block(2, 0, &[0x80]), simple_block(4, 0, b"hi")]),
+                cluster(
+                    1000,
+                    &[
+                        simple_block(2, 0, &[0x80]),
+                        block_group(3, 0, &[0x80], None),
+                    ],
+                ),
+            ],
+        );
+
+        let tracks = list_matroska_tracks(&mks).expect("tracks");
+        assert_eq!(tracks.len(), 3);
+
+        let sdh = &tracks[0];
+        assert_eq!(sdh.block_count, 2);
+        assert_eq!(sdh.track.name.as_deref(), Some("SDH"));
+        assert!(!sdh.track.flag_default);
+        assert!(sdh.track.flag_hearing_impaired);
+        assert!(!sdh.track.flag_forced);
+
+        let forced = &tracks[1];
+        assert_eq!(forced.block_count, 1);
+        assert_eq!(forced.track.language.as_deref(), Some("por"));
+        assert_eq!(forced.track.language_ietf.as_deref(), Some("pt-BR"));
+        assert_eq!(forced.track.preferred_language(), Some("pt-BR"));
+        assert!(forced.track.flag_default);
+        assert!(forced.track.flag_forced);
+
+        assert!(!tracks[2].is_supported());
+        assert_eq!(
+            default_matroska_track(&tracks).map(|info| info.track.track_number),
+            Some(3)
+        );
+    }
+}
@@ -10,11 +10,11 @@ pub struct CompositionObject {
     pub object_id: u16,
     /// Window ID reference
     pub window_id: u8,
-    /// Cropping flag (bit 7 = has cropping)
+    /// Object flags (bit 7 = has cropping, bit 6 = forced)
     pub cropped_flag: u8,
-    /// Horizontal position within window
+    /// Horizontal screen position of the (cropped) object
     pub x: u16,
-    /// Vertical position within window
+    /// Vertical screen position of the (cropped) object
     pub y: u16,
     /// Cropping horizontal position (if cropped_flag set)
     pub crop_x: u16,
@@ -32,6 +32,12 @@ impl CompositionObject {
     pub fn has_cropping(&self) -> bool {
         (self.cropped_flag & 0x80) != 0
     }
+
+    /// Check if this object is forced (shown even when subtitles are off).
+    #[inline]
+    pub fn is_forced(&self) -> bool {
+        (self.cropped_flag & 0x40) != 0
+    }
 }
 
 /// Presentation Composition Segment contains display parameters.
@@ -129,6 +135,11 @@ impl PresentationCompositionSegment {
         self.composition_state == CompositionState::AcquisitionPoint as u8
     }
 
+    /// Check if any composition object is flagged as forced.
+    pub fn has_forced_objects(&self) -> bool {
+        self.composition_objects.iter().any(|obj| obj.is_forced())
+    }
+
     /// Check if this is a palette-only update.
     #[inline]
     pub fn is_palette_update_only(&self) -> bool {
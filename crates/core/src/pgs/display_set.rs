@@ -142,6 +142,10 @@ impl DisplaySet {
                     // End of display set
                     break;
                 }
+                Ok(SegmentType::DialogStyle | SegmentType::DialogPresentation) => {
+                    // TextST segments are handled by TextStParser
+                    reader.skip(segment_size);
+                }
                 Err(_) => {
                     // Unknown segment type - skip
                     reader.skip(segment_size);
@@ -0,0 +1,652 @@
+//! PGS (.sup) encoder building display sets from RGBA bitmaps.
+//!
+//! Each cue is written as an epoch-start display set (PCS, WDS, PDS, ODS...,
+//! END) followed by a clearing display set at its end time. Bitmaps share a
+//! single quantized palette per cue and each bitmap gets its own window, or
+//! one window covering both when they overlap. DTS values follow the decoder
+//! model checked by `validate_display_sets`.
+
+use std::collections::HashMap;
+
+use super::validate::transfer_ticks;
+use super::{MAX_PGS_OBJECT_DATA_LEN, SegmentType, encode_rle_from_indexed};
+use crate::utils::rgb_to_ycbcr;
+
+/// Maximum payload size of a single PGS segment.
+const MAX_SEGMENT_PAYLOAD: usize = 0xFFFF;
+/// Fixed header bytes of the first ODS fragment (id, version, flags, length, size).
+const FIRST_ODS_HEADER_LEN: usize = 11;
+/// Fixed header bytes of a continuation ODS fragment (id, version, flags).
+const NEXT_ODS_HEADER_LEN: usize = 4;
+/// A composition may reference at most two objects, each in its own window.
+const MAX_COMPOSITION_OBJECTS: usize = 2;
+/// Palette index reserved for fully transparent pixels.
+const TRANSPARENT_INDEX: u8 = 0;
+/// Number of palette entries available for visible colors.
+const MAX_VISIBLE_COLORS: usize = 255;
+/// Default frame rate code (23.976 fps).
+const DEFAULT_FRAME_RATE: u8 = 0x10;
+
+/// An RGBA bitmap to place on screen.
+#[derive(Debug, Clone)]
+pub struct PgsBitmap {
+    /// Horizontal screen position
+    pub x: u16,
+    /// Vertical screen position
+    pub y: u16,
+    /// Bitmap width in pixels
+    pub width: u16,
+    /// Bitmap height in pixels
+    pub height: u16,
+    /// RGBA pixel data, row-major (`width * height * 4` bytes)
+    pub rgba: Vec<u8>,
+    /// Mark the object as forced
+    pub forced: bool,
+}
+
+/// A quantized palette entry in PGS YCbCr+alpha form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PgsPaletteEntry {
+    pub id: u8,
+    pub y: u8,
+    pub cb: u8,
+    pub cr: u8,
+    pub alpha: u8,
+}
+
+/// Result of quantizing one or more RGBA bitmaps to a shared palette.
+#[derive(Debug, Clone)]
+pub struct QuantizedBitmaps {
+    /// Palette entries, at most 256 (index 0 is fully transparent)
+    pub palette: Vec<PgsPaletteEntry>,
+    /// Indexed pixels for each input bitmap
+    pub indexed: Vec<Vec<u8>>,
+}
+
+/// Quantize RGBA bitmaps to a shared palette of at most 256 YCbCr+alpha entries.
+///
+/// Fully transparent pixels map to index 0. Remaining colors are used exactly
+/// when they fit; otherwise channel precision is reduced until they do and
+/// each palette entry becomes the average of the colors it replaces.
+pub fn quantize_rgba_bitmaps(bitmaps: &[&[u8]]) -> QuantizedBitmaps {
+    let mut shift = 0u32;
+    let buckets = loop {
+        let mut buckets: HashMap<u32, usize> = HashMap::new();
+        let mut overflow = false;
+        for pixel in bitmaps.iter().flat_map(|rgba| rgba.chunks_exact(4)) {
+            if pixel[3] == 0 {
+                continue;
+            }
+            let next_index = buckets.len() + 1;
+            buckets
+                .entry(bucket_key(pixel, shift))
+                .or_insert(next_index);
+            if buckets.len() > MAX_VISIBLE_COLORS {
+                overflow = true;
+                break;
+            }
+        }
+        if !overflow || shift >= 7 {
+            break buckets;
+        }
+        shift += 1;
+    };
+
+    let mut sums = vec![[0u64; 5]; buckets.len() + 1];
+    let mut indexed = Vec::with_capacity(bitmaps.len());
+    for rgba in bitmaps {
+        let mut pixels = Vec::with_capacity(rgba.len() / 4);
+        for pixel in rgba.chunks_exact(4) {
+            if pixel[3] == 0 {
+                pixels.push(TRANSPARENT_INDEX);
+                continue;
+            }
+            let index = buckets[&bucket_key(pixel, shift)];
+            let sum = &mut sums[index];
+            for (channel, &value) in sum.iter_mut().zip(pixel) {
+                *channel += value as u64;
+            }
+            sum[4] += 1;
+            pixels.push(index as u8);
+        }
+        indexed.push(pixels);
+    }
+
+    let mut palette = vec![PgsPaletteEntry {
+        id: TRANSPARENT_INDEX,
+        y: 16,
+        cb: 128,
+        cr: 128,
+        alpha: 0,
+    }];
+    for (index, sum) in sums.iter().enumerate().skip(1) {
+        let count = sum[4].max(1);
+        let average = |channel: usize| ((sum[channel] + count / 2) / count) as u8;
+        let (y, cb, cr) = rgb_to_ycbcr(average(0), average(1), average(2));
+        palette.push(PgsPaletteEntry {
+            id: index as u8,
+            y,
+            cb,
+            cr,
+            alpha: average(3),
+        });
+    }
+
+    QuantizedBitmaps { palette, indexed }
+}
+
+#[inline]
+fn bucket_key(pixel: &[u8], shift: u32) -> u32 {
+    u32::from_le_bytes([
+        pixel[0] >> shift,
+        pixel[1] >> shift,
+        pixel[2] >> shift,
+        pixel[3] >> shift,
+    ])
+}
+
+/// Streaming PGS encoder producing a `.sup` byte stream.
+pub struct PgsEncoder {
+    width: u16,
+    height: u16,
+    frame_rate: u8,
+    composition_number: u16,
+    /// End time (90 kHz) and windows of the cue whose clearing display set is pending
+    pending_clear: Option<(u32, Vec<WindowRect>)>,
+    last_start: Option<u32>,
+    /// PTS of the last written display set; decoding may not start earlier
+    last_pts: u32,
+    output: Vec<u8>,
+}
+
+impl PgsEncoder {
+    /// Create an encoder for the given presentation size.
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            frame_rate: DEFAULT_FRAME_RATE,
+            composition_number: 0,
+            pending_clear: None,
+            last_start: None,
+            last_pts: 0,
+            output: Vec::new(),
+        }
+    }
+
+    /// Set the PCS frame rate code (default 0x10, 23.976 fps).
+    pub fn with_frame_rate(mut self, frame_rate: u8) -> Self {
+        self.frame_rate = frame_rate;
+        self
+    }
+
+    /// Append a cue shown from `start_ms` until `end_ms`.
+    ///
+    /// Cues must be added in start-time order. A cue that starts before the
+    /// previous one ends replaces it instead of emitting a clearing display set.
+    pub fn add_cue(
+        &mut self,
+        start_ms: u32,
+        end_ms: u32,
+        bitmaps: &[PgsBitmap],
+    ) -> Result<(), String> {
+        if bitmaps.is_empty() {
+            return Err("PGS cue must contain at least one bitmap".to_string());
+        }
+        if bitmaps.len() > MAX_COMPOSITION_OBJECTS {
+            return Err(format!(
+                "PGS cue has {} bitmaps; at most {MAX_COMPOSITION_OBJECTS} are supported",
+                bitmaps.len()
+            ));
+        }
+        if end_ms <= start_ms {
+            return Err("PGS cue end time must be after its start time".to_string());
+        }
+
+        let start = ms_to_pts(start_ms)?;
+        let end = ms_to_pts(end_ms)?;
+        if self.last_start.is_some_and(|last| start < last) {
+            return Err("PGS cues must be added in start-time order".to_string());
+        }
+
+        for bitmap in bitmaps {
+            self.validate_bitmap(bitmap)?;
+        }
+
+        let rgba: Vec<&[u8]> = bitmaps
+            .iter()
+            .map(|bitmap| bitmap.rgba.as_slice())
+            .collect();
+        let quantized = quantize_rgba_bitmaps(&rgba);
+        let objects = bitmaps
+            .iter()
+            .zip(&quantized.indexed)
+            .map(|(bitmap, indexed)| {
+                encode_rle_from_indexed(indexed, bitmap.width as usize, bitmap.height as usize)
+            })
+            .collect::<Vec<_>>();
+        if objects
+            .iter()
+            .any(|rle| rle.len() + 4 > MAX_PGS_OBJECT_DATA_LEN)
+        {
+            return Err("PGS object data exceeds the 24-bit length limit".to_string());
+        }
+
+        if let Some((clear, windows)) = self.pending_clear.take()
+            && clear <= start
+        {
+            self.write_clear(clear, &windows);
+        }
+
+        let (windows, window_ids) = composition_windows(bitmaps);
+        self.write_epoch_start(
+            start,
+            bitmaps,
+            &windows,
+            &window_ids,
+            &quantized.palette,
+            &objects,
+        );
+        self.pending_clear = Some((end, windows));
+        self.last_start = Some(start);
+        Ok(())
+    }
+
+    /// Finish the stream and return the encoded `.sup` bytes.
+    pub fn finish(mut self) -> Vec<u8> {
+        if let Some((clear, windows)) = self.pending_clear.take() {
+            self.write_clear(clear, &windows);
+        }
+        self.output
+    }
+
+    fn validate_bitmap(&self, bitmap: &PgsBitmap) -> Result<(), String> {
+        if bitmap.width == 0 || bitmap.height == 0 {
+            return Err("PGS bitmap must not be empty".to_string());
+        }
+        if bitmap.rgba.len() != bitmap.width as usize * bitmap.height as usize * 4 {
+            return Err("PGS bitmap RGBA length does not match its size".to_string());
+        }
+        if bitmap.x as u32 + bitmap.width as u32 > self.width as u32
+            || bitmap.y as u32 + bitmap.height as u32 > self.height as u32
+        {
+            return Err("PGS bitmap extends beyond the presentation size".to_string());
+        }
+        Ok(())
+    }
+
+    fn write_epoch_start(
+        &mut self,
+        pts: u32,
+        bitmaps: &[PgsBitmap],
+        windows: &[WindowRect],
+        window_ids: &[u8],
+        palette: &[PgsPaletteEntry],
+        objects: &[Vec<u8>],
+    ) {
+        // Clear the plane, decode the objects, then draw the windows
+        let decode_ticks = transfer_ticks(self.width, self.height, 3200)
+            + bitmaps
+                .iter()
+                .map(|bitmap| transfer_ticks(bitmap.width, bitmap.height, 1600))
+                .sum::<u64>()
+            + window_draw_ticks(windows);
+        let dts = self.decode_start(pts, decode_ticks);
+
+        let mut pcs = self.pcs_header(0x80);
+        pcs.push(bitmaps.len() as u8);
+        for (index, (bitmap, &window_id)) in bitmaps.iter().zip(window_ids).enumerate() {
+            pcs.extend_from_slice(&(index as u16).to_be_bytes());
+            pcs.push(window_id);
+            pcs.push(if bitmap.forced { 0x40 } else { 0x00 });
+            pcs.extend_from_slice(&bitmap.x.to_be_bytes());
+            pcs.extend_from_slice(&bitmap.y.to_be_bytes());
+        }
+        self.write_segment(pts, dts, SegmentType::PresentationComposition, &pcs);
+        self.write_windows(pts, dts, windows);
+
+        let mut pds = vec![0x00, 0x00];
+        for entry in palette {
+            pds.extend_from_slice(&[entry.id, entry.y, entry.cr, entry.cb, entry.alpha]);
+        }
+        self.write_segment(pts, dts, SegmentType::PaletteDefinition, &pds);
+
+        for (index, (bitmap, rle)) in bitmaps.iter().zip(objects).enumerate() {
+            self.write_object(pts, dts, index as u16, bitmap, rle);
+        }
+
+        self.write_segment(pts, dts, SegmentType::End, &[]);
+    }
+
+    fn write_clear(&mut self, pts: u32, windows: &[WindowRect]) {
+        let dts = self.decode_start(pts, window_draw_ticks(windows));
+        let mut pcs = self.pcs_header(0x00);
+        pcs.push(0);
+        self.write_segment(pts, dts, SegmentType::PresentationComposition, &pcs);
+        self.write_windows(pts, dts, windows);
+        self.write_segment(pts, dts, SegmentType::End, &[]);
+    }
+
+    /// Get the DTS leaving `decode_ticks` before `pts`, without starting
+    /// before the previous display set is presented. A display set too close
+    /// to the previous one (or to zero) gets less time than it needs.
+    fn decode_start(&mut self, pts: u32, decode_ticks: u64) -> u32 {
+        let decode_ticks = decode_ticks.min(u32::MAX as u64) as u32;
+        let dts = pts.saturating_sub(decode_ticks).max(self.last_pts);
+        self.last_pts = pts;
+        dts
+    }
+
+    fn write_windows(&mut self, pts: u32, dts: u32, windows: &[WindowRect]) {
+        let mut wds = vec![windows.len() as u8];
+        for (index, window) in windows.iter().enumerate() {
+            wds.push(index as u8);
+            wds.extend_from_slice(&window.x.to_be_bytes());
+            wds.extend_from_slice(&window.y.to_be_bytes());
+            wds.extend_from_slice(&window.width.to_be_bytes());
+            wds.extend_from_slice(&window.height.to_be_bytes());
+        }
+        self.write_segment(pts, dts, SegmentType::WindowDefinition, &wds);
+    }
+
+    /// Write an object, fragmenting it across ODS segments as needed.
+    fn write_object(&mut self, pts: u32, dts: u32, object_id: u16, bitmap: &PgsBitmap, rle: &[u8]) {
+        let first_len = rle.len().min(MAX_SEGMENT_PAYLOAD - FIRST_ODS_HEADER_LEN);
+        let mut chunks = vec![&rle[..first_len]];
+        chunks.extend(rle[first_len..].chunks(MAX_SEGMENT_PAYLOAD - NEXT_ODS_HEADER_LEN));
+        let last = chunks.len() - 1;
+
+        for (index, chunk) in chunks.into_iter().enumerate() {
+            let mut flags = 0u8;
+            if index == 0 {
+                flags |= 0x80;
+            }
+            if index == last {
+                flags |= 0x40;
+            }
+
+            let mut ods = Vec::with_capacity(chunk.len() + FIRST_ODS_HEADER_LEN);
+            ods.extend_from_slice(&object_id.to_be_bytes());
+            ods.push(0x00);
+            ods.push(flags);
+            if index == 0 {
+                let data_length = rle.len() as u32 + 4;
+                ods.extend_from_slice(&data_length.to_be_bytes()[1..]);
+                ods.extend_from_slice(&bitmap.width.to_be_bytes());
+                ods.extend_from_slice(&bitmap.height.to_be_bytes());
+            }
+            ods.extend_from_slice(chunk);
+            self.write_segment(pts, dts, SegmentType::ObjectDefinition, &ods);
+        }
+    }
+
+    fn pcs_header(&mut self, composition_state: u8) -> Vec<u8> {
+        let mut pcs = Vec::with_capacity(11 + MAX_COMPOSITION_OBJECTS * 8);
+        pcs.extend_from_slice(&self.width.to_be_bytes());
+        pcs.extend_from_slice(&self.height.to_be_bytes());
+        pcs.push(self.frame_rate);
+        pcs.extend_from_slice(&self.composition_number.to_be_bytes());
+        pcs.push(composition_state);
+        pcs.push(0x00);
+        pcs.push(0x00);
+        self.composition_number = self.composition_number.wrapping_add(1);
+        pcs
+    }
+
+    /// Write a segment with the "PG" header.
+    fn write_segment(&mut self, pts: u32, dts: u32, segment_type: SegmentType, payload: &[u8]) {
+        self.output.extend_from_slice(&0x5047u16.to_be_bytes());
+        self.output.extend_from_slice(&pts.to_be_bytes());
+        self.output.extend_from_slice(&dts.to_be_bytes());
+        self.output.push(segment_type as u8);
+        self.output
+            .extend_from_slice(&(payload.len() as u16).to_be_bytes());
+        self.output.extend_from_slice(payload);
+    }
+}
+
+/// Window rectangle covering one or more bitmaps.
+#[derive(Debug, Clone, Copy)]
+struct WindowRect {
+    x: u16,
+    y: u16,
+    width: u16,
+    height: u16,
+}
+
+impl WindowRect {
+    fn right(&self) -> u32 {
+        self.x as u32 + self.width as u32
+    }
+
+    fn bottom(&self) -> u32 {
+        self.y as u32 + self.height as u32
+    }
+
+    fn overlaps(&self, other: &Self) -> bool {
+        (self.x as u32) < other.right()
+            && (other.x as u32) < self.right()
+            && (self.y as u32) < other.bottom()
+            && (other.y as u32) < self.bottom()
+    }
+
+    fn union(&self, other: &Self) -> Self {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Self {
+            x,
+            y,
+            width: (self.right().max(other.right()) - x as u32) as u16,
+            height: (self.bottom().max(other.bottom()) - y as u32) as u16,
+        }
+    }
+}
+
+/// Get the windows of a composition and the window of each bitmap. Windows
+/// of one composition must not overlap, so overlapping bitmaps share one.
+fn composition_windows(bitmaps: &[PgsBitmap]) -> (Vec<WindowRect>, Vec<u8>) {
+    let windows: Vec<WindowRect> = bitmaps.iter().map(WindowRect::from).collect();
+    if let [first, second] = windows.as_slice()
+        && first.overlaps(second)
+    {
+        return (vec![first.union(second)], vec![0, 0]);
+    }
+    let window_ids = (0..windows.len() as u8).collect();
+    (windows, window_ids)
+}
+
+/// 90kHz ticks to draw `windows` to the graphics plane.
+fn window_draw_ticks(windows: &[WindowRect]) -> u64 {
+    windows
+        .iter()
+        .map(|window| transfer_ticks(window.width, window.height, 3200))
+        .sum()
+}
+
+impl From<&PgsBitmap> for WindowRect {
+    fn from(bitmap: &PgsBitmap) -> Self {
+        Self {
+            x: bitmap.x,
+            y: bitmap.y,
+            width: bitmap.width,
+            height: bitmap.height,
+        }
+    }
+}
+
+fn ms_to_pts(time_ms: u32) -> Result<u32, String> {
+    time_ms
+        .checked_mul(90)
+        .ok_or_else(|| "PGS cue timestamp exceeds the 32-bit PTS range".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pgs::{DisplaySet, PgsParser};
+
+    fn solid_bitmap(x: u16, y: u16, width: u16, height: u16, rgba: [u8; 4]) -> PgsBitmap {
+        PgsBitmap {
+            x,
+            y,
+            width,
+            height,
+            rgba: rgba.repeat(width as usize * height as usize),
+            forced: false,
+        }
+    }
+
+    #[test]
+    fn encoded_stream_roundtrips_through_parser() {
+        let mut encoder = PgsEncoder::new(1920, 1080);
+        let mut top = solid_bitmap(100, 50, 8, 4, [255, 255, 255, 255]);
+        top.rgba[..4].copy_from_slice(&[0, 0, 0, 0]);
+        top.forced = true;
+        encoder
+            .add_cue(
+                1000,
+                2500,
+                &[top, solid_bitmap(100, 900, 6, 2, [250, 10, 10, 128])],
+            )
+            .unwrap();
+        encoder
+            .add_cue(2000, 4000, &[solid_bitmap(0, 0, 3, 3, [0, 0, 255, 255])])
+            .unwrap();
+        let sup = encoder.finish();
+
+        let mut parser = PgsParser::new();
+        assert_eq!(parser.parse(&sup), 2);
+        assert_eq!(parser.display_set_count(), 3);
+        assert_eq!(parser.get_timestamps(), vec![1000.0, 2000.0]);
+        assert_eq!(parser.get_end_timestamps(), vec![2000.0, 4000.0]);
+        assert!(parser.is_cue_forced(0));
+        assert!(!parser.is_cue_forced(1));
+        assert_eq!(parser.validate(), Vec::new());
+
+        let frame = parser.render_at_index(0).expect("frame");
+        assert_eq!(frame.composition_count(), 2);
+        let top = frame.get_composition(0).unwrap();
+        assert_eq!((top.x, top.y, top.width, top.height), (100, 50, 8, 4));
+        assert_eq!(top.rgba[3], 0);
+        assert_eq!(&top.rgba[4..8], &[255, 255, 255, 255]);
+        let bottom = frame.get_composition(1).unwrap();
+        let pixel = &bottom.rgba[..4];
+        assert!(pixel[0] >= 248 && pixel[1] <= 12 && pixel[2] <= 14 && pixel[3] == 128);
+    }
+
+    #[test]
+    fn large_objects_are_fragmented_across_ods_segments() {
+        let (width, height) = (1000u16, 200u16);
+        let rgba = (0..width as usize * height as usize)
+            .flat_map(|index| [(index % 7) as u8 * 30, 255, 0, 255])
+            .collect();
+        let bitmap = PgsBitmap {
+            x: 0,
+            y: 0,
+            width,
+            height,
+            rgba,
+            forced: false,
+        };
+
+        let mut encoder = PgsEncoder::new(1920, 1080);
+        encoder
+            .add_cue(0, 1000, std::slice::from_ref(&bitmap))
+            .unwrap();
+        let sup = encoder.finish();
+
+        let mut parser = PgsParser::new();
+        assert_eq!(parser.parse(&sup), 1);
+        assert_eq!(parser.validate(), Vec::new());
+        let (display_set, _) = DisplaySet::parse(&sup, true).expect("display set");
+        assert!(display_set.objects.len() > 1);
+        let frame = parser.render_at_index(0).expect("frame");
+        let comp = frame.get_composition(0).unwrap();
+        assert_eq!(comp.rgba.len(), bitmap.rgba.len());
+        assert!(
+            comp.rgba
+                .iter()
+                .zip(&bitmap.rgba)
+                .all(|(actual, expected)| actual.abs_diff(*expected) <= 2)
+        );
+    }
+
+    #[test]
+    fn overlapping_bitmaps_share_a_window() {
+        let mut encoder = PgsEncoder::new(1920, 1080);
+        encoder
+            .add_cue(
+                1000,
+                2000,
+                &[
+                    solid_bitmap(100, 100, 50, 20, [255, 255, 255, 255]),
+                    solid_bitmap(120, 110, 50, 20, [255, 0, 0, 255]),
+                ],
+            )
+            .unwrap();
+        let sup = encoder.finish();
+
+        let mut parser = PgsParser::new();
+        assert_eq!(parser.parse(&sup), 1);
+        assert_eq!(parser.validate(), Vec::new());
+        let display_set = &parser.display_sets()[0];
+        let windows = &display_set.windows[0].windows;
+        assert_eq!(windows.len(), 1);
+        let window = windows[0];
+        assert_eq!(
+            (window.x, window.y, window.width, window.height),
+            (100, 100, 70, 30)
+        );
+        let composition = display_set.composition.as_ref().unwrap();
+        assert!(
+            composition
+                .composition_objects
+                .iter()
+                .all(|object| object.window_id == window.id)
+        );
+
+        // Plane clear, two object decodes and one window draw before the PTS
+        let decode_ticks = (1920 * 1080 * 9u32).div_ceil(3200)
+            + 2 * (50 * 20 * 9u32).div_ceil(1600)
+            + (70 * 30 * 9u32).div_ceil(3200);
+        assert_eq!(display_set.dts, 90_000 - decode_ticks);
+        let clear = &parser.display_sets()[1];
+        assert_eq!(clear.dts, 180_000 - (70 * 30 * 9u32).div_ceil(3200));
+    }
+
+    #[test]
+    fn quantization_limits_palette_to_256_entries() {
+        let rgba: Vec<u8> = (0..4096u32)
+            .flat_map(|value| [(value & 0xFF) as u8, (value >> 4) as u8, 77, 255])
+            .collect();
+        let quantized = quantize_rgba_bitmaps(&[&rgba]);
+
+        assert!(quantized.palette.len() <= 256);
+        assert_eq!(quantized.palette[0].alpha, 0);
+        assert!(
+            quantized.indexed[0]
+                .iter()
+                .all(|&index| (index as usize) < quantized.palette.len() && index != 0)
+        );
+    }
+
+    #[test]
+    fn rejects_out_of_order_and_oversized_cues() {
+        let mut encoder = PgsEncoder::new(720, 480);
+        encoder
+            .add_cue(5000, 6000, &[solid_bitmap(0, 0, 2, 2, [1, 2, 3, 255])])
+            .unwrap();
+        assert!(
+            encoder
+                .add_cue(4000, 4500, &[solid_bitmap(0, 0, 2, 2, [1, 2, 3, 255])])
+                .is_err()
+        );
+        assert!(
+            encoder
+                .add_cue(7000, 8000, &[solid_bitmap(719, 0, 2, 2, [1, 2, 3, 255])])
+                .is_err()
+        );
+        let three = vec![solid_bitmap(0, 0, 1, 1, [1, 2, 3, 255]); 3];
+        assert!(encoder.add_cue(7000, 8000, &three).is_err());
+    }
+}
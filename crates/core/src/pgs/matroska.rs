@@ -0,0 +1,181 @@
+//! PGS extraction from Matroska `S_HDMV/PGS` tracks.
+//!
+//! Each block carries the segments of one display set without the "PG"
+//! header. Segments are re-framed with the block timestamp as PTS and DTS.
+
+use super::{DisplaySet, append_pes_segments, parse_sup_display_sets};
+use crate::mkv::{MATROSKA_CODEC_PGS, MatroskaFrame, parse_matroska_segment};
+
+/// Extract a PGS track from Matroska data and re-frame it as `.sup` data.
+/// Uses the first PGS track when `track_number` is `None`.
+pub fn extract_pgs_sup_from_mks(data: &[u8], track_number: Option<u64>) -> Result<Vec<u8>, String> {
+    let mut sup = Vec::new();
+    for frame in &read_pgs_frames(data, track_number)? {
+        let pts = frame_pts(frame);
+        append_pes_segments(&mut sup, pts, pts, frame.payload(data));
+    }
+    Ok(sup)
+}
+
+/// Extract a PGS track from Matroska data into display sets.
+///
+/// Each block is parsed on its own, so a malformed block only loses itself.
+pub fn extract_pgs_display_sets_from_mks(
+    data: &[u8],
+    track_number: Option<u64>,
+) -> Result<Vec<DisplaySet>, String> {
+    let mut display_sets = Vec::new();
+    for frame in &read_pgs_frames(data, track_number)? {
+        display_sets.extend(frame_display_sets(frame, data));
+    }
+    Ok(display_sets)
+}
+
+/// Parse one block of a PGS track into display sets.
+pub(super) fn frame_display_sets(frame: &MatroskaFrame, data: &[u8]) -> Vec<DisplaySet> {
+    let pts = frame_pts(frame);
+    let mut sup = Vec::new();
+    append_pes_segments(&mut sup, pts, pts, frame.payload(data));
+    parse_sup_display_sets(&sup)
+}
+
+/// Read the blocks of a PGS track in timestamp order.
+fn read_pgs_frames(data: &[u8], track_number: Option<u64>) -> Result<Vec<MatroskaFrame>, String> {
+    let segment = parse_matroska_segment(data)?;
+    let track = segment.select_track(MATROSKA_CODEC_PGS, track_number)?;
+    let mut frames = segment.read_frames(data, track)?;
+    frames.sort_by_key(|frame| frame.timestamp_ms);
+    Ok(frames)
+}
+
+/// The 90 kHz PTS of a block. The 32-bit "PG" timestamps wrap like the
+/// 33-bit PES ones.
+fn frame_pts(frame: &MatroskaFrame) -> u32 {
+    (frame.timestamp_ms as u64 * 90) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mkv::MatroskaReader;
+    use crate::mkv::test_util::{build_mks, cluster, simple_block, track_entry};
+    use crate::pgs::{PgsBitmap, PgsEncoder, PgsParser};
+    use std::io::Cursor;
+
+    /// Split a `.sup` stream into per-display-set block payloads without "PG" headers.
+    fn sup_blocks(sup: &[u8]) -> Vec<(u32, Vec<u8>)> {
+        let mut blocks: Vec<(u32, Vec<u8>)> = Vec::new();
+        let mut current = Vec::new();
+        let mut offset = 0;
+        while offset + 13 <= sup.len() {
+            let pts = u32::from_be_bytes(sup[offset + 2..offset + 6].try_into().unwrap());
+            let segment_len = u16::from_be_bytes([sup[offset + 11], sup[offset + 12]]) as usize;
+            current.extend_from_slice(&sup[offset + 10..offset + 13 + segment_len]);
+            if sup[offset + 10] == 0x80 {
+                blocks.push((pts / 90, std::mem::take(&mut current)));
+            }
+            offset += 13 + segment_len;
+        }
+        blocks
+    }
+
+    fn encoded_sup() -> Vec<u8> {
+        let mut encoder = PgsEncoder::new(1920, 1080);
+        for start in [1000u32, 3000] {
+            let bitmap = PgsBitmap {
+                x: 10,
+                y: 20,
+                width: 64,
+                height: 32,
+                rgba: [255, 255, 255, 255].repeat(64 * 32),
+                forced: false,
+            };
+            encoder.add_cue(start, start + 1500, &[bitmap]).unwrap();
+        }
+        encoder.finish()
+    }
+
+    #[test]
+    fn parses_pgs_track_from_mks() {
+        let sup = encoded_sup();
+
+        let blocks: Vec<Vec<u8>> = sup_blocks(&sup)
+            .iter()
+            .map(|(timestamp_ms, payload)| simple_block(2, *timestamp_ms as i16, payload))
+            .collect();
+        let mks = build_mks(
+            &[
+                track_entry(1, "S_VOBSUB", Some(b"size: 720x480\n"), "eng", &[]),
+                track_entry(2, MATROSKA_CODEC_PGS, None, "jpn", &[]),
+            ],
+            &[cluster(0, &blocks)],
+        );
+
+        let mut expected = PgsParser::new();
+        expected.parse(&sup);
+        let mut parser = PgsParser::new();
+        assert_eq!(parser.parse_matroska(&mks, None), Ok(2));
+        assert_eq!(parser.get_timestamps(), expected.get_timestamps());
+        assert_eq!(parser.get_end_timestamps(), expected.get_end_timestamps());
+        assert!(parser.render_at_index(1).is_some());
+
+        assert!(parser.parse_matroska(&mks, Some(1)).is_err());
+    }
+
+    #[test]
+    fn reads_pgs_track_cluster_by_cluster() {
+        let blocks: Vec<Vec<u8>> = sup_blocks(&encoded_sup())
+            .iter()
+            .map(|(timestamp_ms, payload)| simple_block(1, *timestamp_ms as i16, payload))
+            .collect();
+        let mks = build_mks(
+            &[track_entry(1, MATROSKA_CODEC_PGS, None, "jpn", &[])],
+            &[cluster(0, &blocks[..2]), cluster(0, &blocks[2..])],
+        );
+
+        let mut reader = MatroskaReader::open(Cursor::new(mks.clone())).expect("open");
+        reader
+            .select_track(MATROSKA_CODEC_PGS, None)
+            .expect("track");
+        let mut parser = PgsParser::new();
+        parser.start_matroska_reader(&reader).expect("start");
+        assert_eq!(parser.read_matroska_next(&mut reader), Ok(Some(1)));
+        assert_eq!(parser.get_timestamps(), vec![1000.0]);
+        assert_eq!(parser.read_matroska_next(&mut reader), Ok(Some(1)));
+        while parser
+            .read_matroska_next(&mut reader)
+            .expect("read")
+            .is_some()
+        {}
+        assert_eq!(parser.count(), 2);
+
+        let mut expected = PgsParser::new();
+        expected.parse_matroska(&mks, None).expect("parse");
+        assert_eq!(parser.get_end_timestamps(), expected.get_end_timestamps());
+        assert_eq!(parser.parse_matroska_reader(Cursor::new(mks), None), Ok(2));
+        assert_eq!(parser.get_timestamps(), expected.get_timestamps());
+    }
+
+    #[test]
+    fn malformed_block_only_loses_itself() {
+        let mut blocks = sup_blocks(&encoded_sup());
+        // Give the second PCS 255 composition objects so parsing overruns it
+        blocks[1].1[3 + 10] = 0xFF;
+        let blocks: Vec<Vec<u8>> = blocks
+            .iter()
+            .map(|(timestamp_ms, payload)| simple_block(1, *timestamp_ms as i16, payload))
+            .collect();
+        let mks = build_mks(
+            &[track_entry(1, MATROSKA_CODEC_PGS, None, "jpn", &[])],
+            &[cluster(0, &blocks)],
+        );
+
+        let display_sets = extract_pgs_display_sets_from_mks(&mks, None).expect("display sets");
+        let timed: Vec<u32> = display_sets
+            .iter()
+            .filter(|display_set| display_set.composition.is_some())
+            .map(|display_set| display_set.pts)
+            .collect();
+        assert_eq!(timed, vec![90_000, 270_000, 405_000]);
+    }
+}
@@ -1,14 +1,19 @@
 //! PGS (Presentation Graphic Stream) subtitle format parsing and rendering.
 //!
-//! This module implements the Blu-ray PGS subtitle format (.sup files).
+//! This module implements the Blu-ray PGS subtitle format (.sup files and
+//! PGS streams muxed in M2TS/TS).
 
 mod composition;
 mod display_set;
+mod encoder;
+mod matroska;
 mod object;
 mod palette;
 mod parser;
 mod rle;
 mod segment;
+mod transport;
+mod validate;
 mod window;
 
 pub(crate) const MAX_PGS_OBJECT_DATA_LEN: usize = 0x00FF_FFFF;
@@ -16,9 +21,13 @@ pub(crate) const MAX_PGS_BITMAP_PIXELS: usize = 16_777_216;
 
 pub use composition::*;
 pub use display_set::*;
+pub use encoder::*;
+pub use matroska::*;
 pub use object::*;
 pub use palette::*;
 pub use parser::*;
 pub use rle::*;
 pub use segment::*;
+pub use transport::*;
+pub use validate::*;
 pub use window::*;
@@ -2,23 +2,33 @@
 
 use memchr::memchr;
 use std::collections::HashMap;
+use std::io::{Read, Seek};
 
+use super::matroska::frame_display_sets;
 use super::{
-    AssembledObject, DisplaySet, DisplaySetParseAttempt, MAX_PGS_BITMAP_PIXELS,
+    AssembledObject, CompositionObject, DisplaySet, DisplaySetParseAttempt, MAX_PGS_BITMAP_PIXELS,
     ObjectDefinitionSegment, PaletteDefinitionSegment, WindowDefinition, apply_palette_rgba_bytes,
-    decode_rle_to_indexed,
+    decode_rle_to_indexed, extract_pgs_display_sets, extract_pgs_display_sets_from_mks,
+    list_pgs_streams,
 };
+use crate::mkv::{MATROSKA_CODEC_PGS, MatroskaReader};
 use crate::utils::binary_search_timestamp;
 
 const MAX_PENDING_BYTES: usize = 32 * 1024 * 1024;
 const MAX_FRAME_COMPOSITIONS: usize = 256;
+/// Fallback on-screen duration for a cue that is never cleared or replaced.
+const DEFAULT_LAST_CUE_DURATION_MS: u32 = 5000;
 
 /// PGS subtitle parser and renderer.
 pub struct PgsParser {
     /// All parsed display sets
     display_sets: Vec<DisplaySet>,
-    /// Timestamps in milliseconds for quick lookup
+    /// Visible presentations built from the display sets
+    cues: Vec<PgsCue>,
+    /// Cue start timestamps in milliseconds for quick lookup
     timestamps_ms: Vec<u32>,
+    /// Palette-only updates applied to cues, in stream order
+    palette_keyframes: Vec<PgsPaletteKeyframe>,
     /// Cache for decoded indexed pixels (before palette application)
     indexed_cache: HashMap<(u16, u8), DecodedBitmap>,
     /// Last rendered boundary index (for cache invalidation)
@@ -29,6 +39,8 @@ pub struct PgsParser {
     cached_context_index: Option<usize>,
     /// Last non-fatal render issue for diagnostics.
     last_render_issue: Option<String>,
+    /// Only render composition objects flagged as forced.
+    forced_only: bool,
     pending: Vec<u8>,
 }
 
@@ -44,19 +56,24 @@ impl PgsParser {
     pub fn new() -> Self {
         Self {
             display_sets: Vec::new(),
+            cues: Vec::new(),
             timestamps_ms: Vec::new(),
+            palette_keyframes: Vec::new(),
             indexed_cache: HashMap::new(),
             last_boundary_index: None,
             cached_context: None,
             cached_context_index: None,
             last_render_issue: None,
+            forced_only: false,
             pending: Vec::new(),
         }
     }
 
     pub fn reset(&mut self) {
         self.display_sets.clear();
+        self.cues.clear();
         self.timestamps_ms.clear();
+        self.palette_keyframes.clear();
         self.indexed_cache.clear();
         self.last_boundary_index = None;
         self.cached_context = None;
@@ -66,7 +83,7 @@ impl PgsParser {
     }
 
     /// Parse a PGS file from binary data.
-    /// Returns the number of display sets parsed.
+    /// Returns the number of cues found.
     pub fn parse(&mut self, data: &[u8]) -> usize {
         self.reset();
 
@@ -76,14 +93,12 @@ impl PgsParser {
         // Roughly 1 display set per 2-5KB, use conservative estimate
         let estimated_count = (len / 3000).max(16);
         self.display_sets.reserve(estimated_count);
-        self.timestamps_ms.reserve(estimated_count);
 
         let mut offset = 0;
 
         while offset < len {
             if let Some((display_set, consumed)) = DisplaySet::parse(&data[offset..], true) {
-                self.timestamps_ms.push(display_set.pts_ms());
-                self.display_sets.push(display_set);
+                self.push_display_set(display_set);
                 offset += consumed;
             } else {
                 // Try to recover by scanning for next magic number using SIMD-accelerated search
@@ -104,7 +119,7 @@ impl PgsParser {
             }
         }
 
-        self.display_sets.len()
+        self.cues.len()
     }
 
     pub fn feed(&mut self, chunk: &[u8]) -> usize {
@@ -119,15 +134,14 @@ impl PgsParser {
         }
         self.pending.extend_from_slice(chunk);
 
-        let before = self.display_sets.len();
+        let before = self.cues.len();
         let mut offset = 0usize;
         let len = self.pending.len();
 
         while offset < len {
             match DisplaySet::try_parse(&self.pending[offset..], true) {
                 DisplaySetParseAttempt::Complete(display_set, consumed) => {
-                    self.timestamps_ms.push(display_set.pts_ms());
-                    self.display_sets.push(display_set);
+                    self.push_display_set(display_set);
                     offset += consumed;
                 }
                 DisplaySetParseAttempt::Incomplete => {
@@ -154,23 +168,218 @@ impl PgsParser {
             self.pending.drain(..offset);
         }
 
-        self.display_sets.len() - before
+        self.cues.len() - before
     }
 
     pub fn finish_feed(&mut self) -> usize {
         self.pending.clear();
-        self.display_sets.len()
+        self.cues.len()
     }
 
     pub fn pending_len(&self) -> usize {
         self.pending.len()
     }
 
-    /// Get the number of display sets.
+    /// Parse a PGS stream from an M2TS/TS transport stream.
+    /// Uses the first PGS stream when `pid` is `None`.
+    /// Returns the number of cues found.
+    pub fn parse_transport_stream(
+        &mut self,
+        data: &[u8],
+        pid: Option<u16>,
+    ) -> Result<usize, String> {
+        let pid = match pid {
+            Some(pid) => pid,
+            None => list_pgs_streams(data)
+                .first()
+                .map(|stream| stream.pid)
+                .ok_or_else(|| "No PGS stream found in transport stream".to_string())?,
+        };
+
+        self.reset();
+        for display_set in extract_pgs_display_sets(data, pid) {
+            self.push_display_set(display_set);
+        }
+        Ok(self.cues.len())
+    }
+
+    /// Parse a PGS track (`S_HDMV/PGS`) from Matroska data. Uses the first PGS
+    /// track when `track_number` is `None`.
+    /// Returns the number of cues found.
+    pub fn parse_matroska(
+        &mut self,
+        data: &[u8],
+        track_number: Option<u64>,
+    ) -> Result<usize, String> {
+        let display_sets = extract_pgs_display_sets_from_mks(data, track_number)?;
+        self.reset();
+        for display_set in display_sets {
+            self.push_display_set(display_set);
+        }
+        Ok(self.cues.len())
+    }
+
+    /// Parse a PGS track from a seekable Matroska source, reading only the
+    /// headers, Cues and the track's blocks. Uses the first PGS track when
+    /// `track_number` is `None`.
+    /// Returns the number of cues found.
+    pub fn parse_matroska_reader<R: Read + Seek>(
+        &mut self,
+        source: R,
+        track_number: Option<u64>,
+    ) -> Result<usize, String> {
+        let mut reader = MatroskaReader::open(source)?;
+        reader.select_track(MATROSKA_CODEC_PGS, track_number)?;
+        self.start_matroska_reader(&reader)?;
+        while self.read_matroska_next(&mut reader)?.is_some() {}
+        Ok(self.cues.len())
+    }
+
+    /// Clear the parser before loading the reader's selected PGS track with
+    /// `read_matroska_next`.
+    pub fn start_matroska_reader<R: Read + Seek>(
+        &mut self,
+        reader: &MatroskaReader<R>,
+    ) -> Result<(), String> {
+        match reader.selected_track() {
+            Some(track) if track.codec_id == MATROSKA_CODEC_PGS => {
+                self.reset();
+                Ok(())
+            }
+            Some(_) => Err("Selected Matroska track is not a PGS track".to_string()),
+            None => Err("No Matroska track selected".to_string()),
+        }
+    }
+
+    /// Read the next cluster of the reader's selected track and append its
+    /// display sets, so cues are available while the file is still being
+    /// read. Returns the number of new cues, or `None` once the track is
+    /// complete.
+    pub fn read_matroska_next<R: Read + Seek>(
+        &mut self,
+        reader: &mut MatroskaReader<R>,
+    ) -> Result<Option<usize>, String> {
+        let read = reader.frames().len();
+        if reader.read_next()?.is_none() {
+            return Ok(None);
+        }
+
+        let before = self.cues.len();
+        for frame in &reader.frames()[read..] {
+            for display_set in frame_display_sets(frame, &[]) {
+                self.push_display_set(display_set);
+            }
+        }
+        Ok(Some(self.cues.len() - before))
+    }
+
+    /// Append a parsed display set and update the cue model.
+    ///
+    /// Palette-only updates become keyframes of the open cue. Every other
+    /// composition closes the open cue; only non-empty compositions start a
+    /// new one.
+    fn push_display_set(&mut self, display_set: DisplaySet) {
+        let display_set_index = self.display_sets.len();
+        let pts_ms = display_set.pts_ms();
+
+        if let Some(composition) = display_set.composition.as_ref() {
+            let open_cue = self
+                .cues
+                .len()
+                .checked_sub(1)
+                .filter(|&index| self.cues[index].end_ms.is_none());
+
+            if let Some(cue_index) = open_cue
+                && composition.is_palette_update_only()
+                && !composition.composition_objects.is_empty()
+            {
+                self.palette_keyframes.push(PgsPaletteKeyframe {
+                    cue_index,
+                    display_set_index,
+                    time_ms: pts_ms,
+                    palette_id: composition.palette_id,
+                });
+                self.display_sets.push(display_set);
+                return;
+            }
+
+            if let Some(open) = self.cues.last_mut().filter(|cue| cue.end_ms.is_none()) {
+                open.end_ms = Some(pts_ms.max(open.start_ms));
+            }
+
+            if !composition.composition_objects.is_empty() {
+                self.cues.push(PgsCue {
+                    display_set_index,
+                    start_ms: pts_ms,
+                    end_ms: None,
+                });
+                self.timestamps_ms.push(pts_ms);
+            }
+        }
+
+        self.display_sets.push(display_set);
+    }
+
+    /// Get the number of cues (visible presentations).
     pub fn count(&self) -> usize {
+        self.cues.len()
+    }
+
+    /// Get the number of raw display sets, including ones that clear the screen.
+    pub fn display_set_count(&self) -> usize {
         self.display_sets.len()
     }
 
+    /// Get all parsed display sets in stream order.
+    pub fn display_sets(&self) -> &[DisplaySet] {
+        &self.display_sets
+    }
+
+    /// Get all cues with their on-screen intervals.
+    pub fn cues(&self) -> &[PgsCue] {
+        &self.cues
+    }
+
+    /// Get all palette-only updates (animation keyframes) in stream order.
+    pub fn palette_keyframes(&self) -> &[PgsPaletteKeyframe] {
+        &self.palette_keyframes
+    }
+
+    /// Get the palette-only updates that animate a cue.
+    pub fn get_cue_palette_keyframes(&self, index: usize) -> &[PgsPaletteKeyframe] {
+        let start = self
+            .palette_keyframes
+            .partition_point(|keyframe| keyframe.cue_index < index);
+        let end = self
+            .palette_keyframes
+            .partition_point(|keyframe| keyframe.cue_index <= index);
+        &self.palette_keyframes[start..end]
+    }
+
+    /// Get the keyframe timestamps in milliseconds for a cue.
+    pub fn get_cue_keyframe_timestamps(&self, index: usize) -> Vec<f64> {
+        self.get_cue_palette_keyframes(index)
+            .iter()
+            .map(|keyframe| keyframe.time_ms as f64)
+            .collect()
+    }
+
+    /// Find the palette keyframe of a cue active at a timestamp in milliseconds.
+    /// Returns -1 when the cue's initial palette is still in effect.
+    pub fn find_keyframe_at_timestamp(&self, index: usize, time_ms: f64) -> i32 {
+        let time_ms_u32 = time_ms as u32;
+        self.get_cue_palette_keyframes(index)
+            .partition_point(|keyframe| keyframe.time_ms <= time_ms_u32) as i32
+            - 1
+    }
+
+    /// Get the display set index a cue is presented from.
+    pub fn get_cue_display_set_index(&self, index: usize) -> i32 {
+        self.cues
+            .get(index)
+            .map_or(-1, |cue| cue.display_set_index as i32)
+    }
+
     /// Get the presentation width for this subtitle track.
     pub fn screen_width(&self) -> u16 {
         self.display_sets
@@ -191,12 +400,20 @@ impl PgsParser {
             .unwrap_or(0)
     }
 
-    /// Get all timestamps in milliseconds.
+    /// Get all cue start timestamps in milliseconds.
     pub fn get_timestamps(&self) -> Vec<f64> {
         self.timestamps_ms.iter().map(|&ts| ts as f64).collect()
     }
 
-    /// Find the display set index for a given timestamp in milliseconds.
+    /// Get all cue end timestamps in milliseconds.
+    pub fn get_end_timestamps(&self) -> Vec<f64> {
+        self.cues
+            .iter()
+            .map(|cue| cue.effective_end_ms() as f64)
+            .collect()
+    }
+
+    /// Find the cue index visible at a given timestamp in milliseconds.
     pub fn find_index_at_timestamp(&self, time_ms: f64) -> i32 {
         if self.timestamps_ms.is_empty() {
             return -1;
@@ -210,6 +427,10 @@ impl PgsParser {
             return -1;
         }
 
+        if time_ms_u32 >= self.cues[index].effective_end_ms() {
+            return -1;
+        }
+
         index as i32
     }
 
@@ -222,24 +443,23 @@ impl PgsParser {
     }
 
     /// Get the cue end time in milliseconds.
+    ///
+    /// This is the time of the display set that clears or replaces the cue.
     pub fn get_cue_end_time(&self, index: usize) -> f64 {
-        let Some(&start_time) = self.timestamps_ms.get(index) else {
-            return -1.0;
-        };
-
-        let end_time = self
-            .timestamps_ms
-            .get(index + 1)
-            .copied()
-            .unwrap_or_else(|| start_time.saturating_add(5000));
+        self.cues
+            .get(index)
+            .map_or(-1.0, |cue| cue.effective_end_ms() as f64)
+    }
 
-        end_time as f64
+    /// Get the display set for a cue.
+    fn cue_display_set(&self, index: usize) -> Option<&DisplaySet> {
+        let cue = self.cues.get(index)?;
+        self.display_sets.get(cue.display_set_index)
     }
 
     /// Get the number of composition objects in a cue.
     pub fn get_cue_composition_count(&self, index: usize) -> u32 {
-        self.display_sets
-            .get(index)
+        self.cue_display_set(index)
             .and_then(|ds| ds.composition.as_ref())
             .map_or(0, |composition| {
                 composition.composition_objects.len() as u32
@@ -248,30 +468,92 @@ impl PgsParser {
 
     /// Get the cue palette ID.
     pub fn get_cue_palette_id(&self, index: usize) -> i32 {
-        self.display_sets
-            .get(index)
+        self.cue_display_set(index)
             .and_then(|ds| ds.composition.as_ref())
             .map_or(-1, |composition| composition.palette_id as i32)
     }
 
     /// Get the cue composition state.
     pub fn get_cue_composition_state(&self, index: usize) -> i32 {
-        self.display_sets
-            .get(index)
+        self.cue_display_set(index)
             .and_then(|ds| ds.composition.as_ref())
             .map_or(-1, |composition| composition.composition_state as i32)
     }
 
+    /// Check if a cue contains at least one forced composition object.
+    pub fn is_cue_forced(&self, index: usize) -> bool {
+        self.cue_display_set(index)
+            .and_then(|ds| ds.composition.as_ref())
+            .is_some_and(|composition| composition.has_forced_objects())
+    }
+
+    /// Get the indices of all cues that contain forced composition objects.
+    pub fn get_forced_cue_indices(&self) -> Vec<u32> {
+        (0..self.cues.len())
+            .filter(|&index| self.is_cue_forced(index))
+            .map(|index| index as u32)
+            .collect()
+    }
+
+    /// Only render forced composition objects (e.g. when subtitles are "off").
+    pub fn set_forced_only(&mut self, forced_only: bool) {
+        self.forced_only = forced_only;
+    }
+
+    /// Check whether forced-only rendering is enabled.
+    pub fn forced_only(&self) -> bool {
+        self.forced_only
+    }
+
     /// Render subtitle at the given index and return RGBA data.
     /// Returns null if index is invalid or no subtitle data.
     pub fn render_at_index(&mut self, index: usize) -> Option<SubtitleFrame> {
         self.last_render_issue = None;
 
-        if index >= self.display_sets.len() {
+        let Some(index) = self.cues.get(index).map(|cue| cue.display_set_index) else {
+            self.last_render_issue = Some("INDEX_OUT_OF_RANGE".to_string());
+            return None;
+        };
+
+        self.render_display_set(index)
+    }
+
+    /// Render a cue with the palette from one of its keyframes applied.
+    ///
+    /// Object bitmaps stay cached as indexed pixels, so stepping through the
+    /// keyframes of a fade only re-applies the palette.
+    pub fn render_keyframe_at_index(
+        &mut self,
+        index: usize,
+        keyframe_index: usize,
+    ) -> Option<SubtitleFrame> {
+        self.last_render_issue = None;
+
+        let Some(keyframe) = self.get_cue_palette_keyframes(index).get(keyframe_index) else {
             self.last_render_issue = Some("INDEX_OUT_OF_RANGE".to_string());
             return None;
+        };
+
+        self.render_display_set(keyframe.display_set_index)
+    }
+
+    /// Render the cue visible at a timestamp in seconds, with the palette of
+    /// its keyframe active at that time, so fades play during playback.
+    pub fn render_at_timestamp(&mut self, time_seconds: f64) -> Option<SubtitleFrame> {
+        let time_ms = time_seconds * 1000.0;
+        let index = self.find_index_at_timestamp(time_ms);
+        if index < 0 {
+            return None;
         }
 
+        let index = index as usize;
+        match self.find_keyframe_at_timestamp(index, time_ms) {
+            keyframe if keyframe >= 0 => self.render_keyframe_at_index(index, keyframe as usize),
+            _ => self.render_at_index(index),
+        }
+    }
+
+    fn render_display_set(&mut self, index: usize) -> Option<SubtitleFrame> {
         // Find boundary (epoch start or acquisition point) for context building
         let boundary_index = self.find_boundary_index(index);
         self.ensure_context_for_index(boundary_index, index);
@@ -312,6 +594,10 @@ impl PgsParser {
                 self.last_render_issue = Some("FRAME_COMPOSITION_LIMIT_EXCEEDED".to_string());
                 break;
             }
+            if self.forced_only && !comp_obj.is_forced() {
+                continue;
+            }
+
             // Get assembled object
             let obj = match context.objects.get(&comp_obj.object_id) {
                 Some(obj) => obj,
@@ -319,7 +605,7 @@ impl PgsParser {
             };
 
             // Window lookup is optional - don't fail if not found
-            let _window = context.windows.get(&comp_obj.window_id);
+            let window = context.windows.get(&comp_obj.window_id);
 
             // Decode or get cached indexed pixels
             let cache_key = (obj.id, obj.version);
@@ -345,7 +631,13 @@ impl PgsParser {
                 self.indexed_cache.get(&cache_key).unwrap()
             };
 
-            let pixel_count = match Self::bitmap_pixel_count(decoded.width, decoded.height) {
+            // Crop the object and clip it to its window and the screen
+            let Some(region) = VisibleRegion::compute(comp_obj, decoded, window, width, height)
+            else {
+                continue;
+            };
+
+            let pixel_count = match Self::bitmap_pixel_count(region.width, region.height) {
                 Some(pixel_count) => pixel_count,
                 None => continue,
             };
@@ -363,13 +655,26 @@ impl PgsParser {
             };
 
             let mut rgba = vec![0u8; rgba_len];
-            apply_palette_rgba_bytes(&decoded.indexed, &palette.rgba, &mut rgba);
+            if region.covers(decoded) {
+                apply_palette_rgba_bytes(&decoded.indexed, &palette.rgba, &mut rgba);
+            } else {
+                let src_stride = decoded.width as usize;
+                let row_len = region.width as usize;
+                for (row, target) in rgba.chunks_exact_mut(row_len * 4).enumerate() {
+                    let start = (region.src_y as usize + row) * src_stride + region.src_x as usize;
+                    apply_palette_rgba_bytes(
+                        &decoded.indexed[start..start + row_len],
+                        &palette.rgba,
+                        target,
+                    );
+                }
+            }
 
             compositions.push(SubtitleComposition {
-                x: comp_obj.x,
-                y: comp_obj.y,
-                width: decoded.width,
-                height: decoded.height,
+                x: region.x,
+                y: region.y,
+                width: region.width,
+                height: region.height,
                 rgba,
             });
         }
@@ -407,7 +712,11 @@ impl PgsParser {
                 .is_none_or(|cached_index| target_index < cached_index);
 
         if needs_rebuild {
-            self.indexed_cache.clear();
+            // Object versions are stable within a boundary, so rewinding to an
+            // earlier display set (e.g. a palette keyframe) keeps decoded bitmaps.
+            if self.last_boundary_index != Some(boundary_index) {
+                self.indexed_cache.clear();
+            }
             self.last_boundary_index = Some(boundary_index);
 
             let mut context = RenderContext::new();
@@ -474,6 +783,84 @@ impl PgsParser {
     }
 }
 
+/// Portion of a decoded object that is visible on screen after cropping and clipping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct VisibleRegion {
+    /// Left edge of the visible area within the decoded object
+    src_x: u16,
+    /// Top edge of the visible area within the decoded object
+    src_y: u16,
+    /// Screen position of the visible area
+    x: u16,
+    y: u16,
+    width: u16,
+    height: u16,
+}
+
+impl VisibleRegion {
+    /// Apply the composition object's crop rectangle, then clip the result to
+    /// its window (when defined) and to the presentation bounds.
+    fn compute(
+        comp_obj: &CompositionObject,
+        decoded: &DecodedBitmap,
+        window: Option<&WindowDefinition>,
+        screen_width: u16,
+        screen_height: u16,
+    ) -> Option<Self> {
+        // Source rectangle in object coordinates, as (left, top, right, bottom)
+        let (mut left, mut top, mut right, mut bottom) =
+            (0u32, 0u32, decoded.width as u32, decoded.height as u32);
+        if comp_obj.has_cropping() {
+            left = (comp_obj.crop_x as u32).min(right);
+            top = (comp_obj.crop_y as u32).min(bottom);
+            right = right.min(left + comp_obj.crop_width as u32);
+            bottom = bottom.min(top + comp_obj.crop_height as u32);
+        }
+
+        // The cropped area is presented at the composition position
+        let origin_x = comp_obj.x as u32;
+        let origin_y = comp_obj.y as u32;
+        let mut dst_left = origin_x;
+        let mut dst_top = origin_y;
+        let mut dst_right = origin_x + (right - left);
+        let mut dst_bottom = origin_y + (bottom - top);
+
+        if let Some(window) = window {
+            dst_left = dst_left.max(window.x as u32);
+            dst_top = dst_top.max(window.y as u32);
+            dst_right = dst_right.min(window.x as u32 + window.width as u32);
+            dst_bottom = dst_bottom.min(window.y as u32 + window.height as u32);
+        }
+
+        if screen_width > 0 && screen_height > 0 {
+            dst_right = dst_right.min(screen_width as u32);
+            dst_bottom = dst_bottom.min(screen_height as u32);
+        }
+
+        if dst_left >= dst_right || dst_top >= dst_bottom {
+            return None;
+        }
+
+        Some(Self {
+            src_x: (left + dst_left - origin_x) as u16,
+            src_y: (top + dst_top - origin_y) as u16,
+            x: dst_left as u16,
+            y: dst_top as u16,
+            width: (dst_right - dst_left) as u16,
+            height: (dst_bottom - dst_top) as u16,
+        })
+    }
+
+    /// Check whether the region spans the whole decoded bitmap.
+    #[inline]
+    fn covers(&self, decoded: &DecodedBitmap) -> bool {
+        self.src_x == 0
+            && self.src_y == 0
+            && self.width == decoded.width
+            && self.height == decoded.height
+    }
+}
+
 impl Default for PgsParser {
     fn default() -> Self {
         Self::new()
@@ -539,6 +926,40 @@ impl RenderContext {
     }
 }
 
+/// A visible PGS presentation and its on-screen interval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PgsCue {
+    /// Index of the display set that starts the presentation
+    pub display_set_index: usize,
+    /// Start time in milliseconds
+    pub start_ms: u32,
+    /// End time in milliseconds, from the display set that clears or replaces
+    /// this one (`None` while no such display set has been seen)
+    pub end_ms: Option<u32>,
+}
+
+impl PgsCue {
+    /// Get the end time, falling back to a default duration for open cues.
+    #[inline]
+    pub fn effective_end_ms(&self) -> u32 {
+        self.end_ms
+            .unwrap_or_else(|| self.start_ms.saturating_add(DEFAULT_LAST_CUE_DURATION_MS))
+    }
+}
+
+/// A palette-only update that re-colours a visible cue (e.g. a fade step).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PgsPaletteKeyframe {
+    /// Index of the cue being animated
+    pub cue_index: usize,
+    /// Index of the palette-only display set
+    pub display_set_index: usize,
+    /// Time at which the new palette takes effect, in milliseconds
+    pub time_ms: u32,
+    /// Palette ID referenced by the update
+    pub palette_id: u8,
+}
+
 /// A single subtitle composition element.
 #[derive(Clone)]
 pub struct SubtitleComposition {
@@ -598,12 +1019,32 @@ impl SubtitleFrame {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::pgs::{CompositionObject, PresentationCompositionSegment};
+    use crate::pgs::{PresentationCompositionSegment, WindowDefinitionSegment};
+
+    /// Build a display set whose composition references `object_count` objects.
+    fn composition_display_set(pts_ms: u32, object_count: usize) -> DisplaySet {
+        DisplaySet {
+            pts: pts_ms * 90,
+            composition: Some(PresentationCompositionSegment {
+                width: 1920,
+                height: 1080,
+                frame_rate: 0,
+                composition_number: 0,
+                composition_state: 0,
+                palette_update_flag: 0,
+                palette_id: 0,
+                composition_objects: vec![CompositionObject::default(); object_count],
+            }),
+            ..DisplaySet::new()
+        }
+    }
 
     #[test]
     fn find_index_at_timestamp_returns_none_before_first_pts() {
         let mut parser = PgsParser::new();
-        parser.timestamps_ms = vec![1200, 2400, 3600];
+        for pts_ms in [1200, 2400, 3600] {
+            parser.push_display_set(composition_display_set(pts_ms, 1));
+        }
 
         assert_eq!(parser.find_index_at_timestamp(0.0), -1);
         assert_eq!(parser.find_index_at_timestamp(1199.0), -1);
@@ -611,87 +1052,331 @@ mod tests {
         assert_eq!(parser.find_index_at_timestamp(2500.0), 1);
     }
 
+    #[test]
+    fn clearing_display_sets_end_cues_instead_of_starting_them() {
+        let mut parser = PgsParser::new();
+        parser.push_display_set(composition_display_set(1000, 1));
+        parser.push_display_set(composition_display_set(2500, 0));
+        parser.push_display_set(composition_display_set(4000, 2));
+        parser.push_display_set(composition_display_set(4800, 1));
+        parser.push_display_set(composition_display_set(6000, 0));
+        parser.push_display_set(composition_display_set(9000, 1));
+
+        assert_eq!(parser.display_set_count(), 6);
+        assert_eq!(parser.count(), 4);
+        assert_eq!(
+            parser.get_timestamps(),
+            vec![1000.0, 4000.0, 4800.0, 9000.0]
+        );
+        assert_eq!(
+            parser.get_end_timestamps(),
+            vec![2500.0, 4800.0, 6000.0, 14_000.0]
+        );
+        assert_eq!(parser.cues()[3].end_ms, None);
+        assert_eq!(parser.get_cue_display_set_index(1), 2);
+        assert_eq!(parser.get_cue_composition_count(1), 2);
+
+        assert_eq!(parser.find_index_at_timestamp(2000.0), 0);
+        assert_eq!(parser.find_index_at_timestamp(3000.0), -1);
+        assert_eq!(parser.find_index_at_timestamp(5000.0), 2);
+        assert_eq!(parser.find_index_at_timestamp(7000.0), -1);
+    }
+
     #[test]
     fn test_render_at_index_skips_oversized_objects() {
-        let mut parser = PgsParser {
-            display_sets: vec![DisplaySet {
-                pts: 0,
-                dts: 0,
-                composition: Some(PresentationCompositionSegment {
-                    width: 1920,
-                    height: 1080,
-                    frame_rate: 0,
-                    composition_number: 0,
-                    composition_state: 0,
-                    palette_update_flag: 0,
-                    palette_id: 0,
-                    composition_objects: vec![CompositionObject {
-                        object_id: 1,
-                        window_id: 0,
-                        cropped_flag: 0,
-                        x: 0,
-                        y: 0,
-                        crop_x: 0,
-                        crop_y: 0,
-                        crop_width: 0,
-                        crop_height: 0,
-                    }],
-                }),
-                palettes: vec![PaletteDefinitionSegment {
-                    id: 0,
-                    version: 0,
-                    rgba: vec![0u32; 256],
-                }],
-                objects: vec![ObjectDefinitionSegment {
-                    id: 1,
-                    version: 0,
-                    sequence_flag: 0xC0,
-                    data_length: 1,
-                    width: 5000,
-                    height: 5000,
-                    data: vec![1],
+        let mut parser = PgsParser::new();
+        parser.push_display_set(DisplaySet {
+            pts: 0,
+            dts: 0,
+            composition: Some(PresentationCompositionSegment {
+                width: 1920,
+                height: 1080,
+                frame_rate: 0,
+                composition_number: 0,
+                composition_state: 0,
+                palette_update_flag: 0,
+                palette_id: 0,
+                composition_objects: vec![CompositionObject {
+                    object_id: 1,
+                    window_id: 0,
+                    cropped_flag: 0,
+                    x: 0,
+                    y: 0,
+                    crop_x: 0,
+                    crop_y: 0,
+                    crop_width: 0,
+                    crop_height: 0,
                 }],
-                windows: Vec::new(),
+            }),
+            palettes: vec![PaletteDefinitionSegment {
+                id: 0,
+                version: 0,
+                rgba: vec![0u32; 256],
             }],
-            timestamps_ms: vec![0],
-            indexed_cache: HashMap::new(),
-            last_boundary_index: None,
-            cached_context: None,
-            cached_context_index: None,
-            last_render_issue: None,
-            pending: Vec::new(),
-        };
+            objects: vec![ObjectDefinitionSegment {
+                id: 1,
+                version: 0,
+                sequence_flag: 0xC0,
+                data_length: 1,
+                width: 5000,
+                height: 5000,
+                data: vec![1],
+            }],
+            windows: Vec::new(),
+        });
+
+        let frame = parser.render_at_index(0).expect("frame should exist");
+
+        assert_eq!(frame.composition_count(), 0);
+    }
+
+    /// Build a parser holding one epoch-start display set with a 4x4 object whose
+    /// pixels carry their own column index (1..=4) as palette index.
+    fn parser_with_gradient_object(
+        comp_obj: CompositionObject,
+        windows: Vec<WindowDefinition>,
+    ) -> PgsParser {
+        let mut rgba = vec![0u32; 256];
+        for index in 1..=4u8 {
+            rgba[index as usize] = u32::from_le_bytes([index, 0, 0, 255]);
+        }
+        let row = [1u8, 2, 3, 4];
+        let data: Vec<u8> = row
+            .iter()
+            .chain(&[0, 0])
+            .copied()
+            .cycle()
+            .take(24)
+            .collect();
+
+        let mut parser = PgsParser::new();
+        parser.push_display_set(DisplaySet {
+            pts: 0,
+            dts: 0,
+            composition: Some(PresentationCompositionSegment {
+                width: 64,
+                height: 64,
+                frame_rate: 0,
+                composition_number: 0,
+                composition_state: 0x80,
+                palette_update_flag: 0,
+                palette_id: 0,
+                composition_objects: vec![comp_obj],
+            }),
+            palettes: vec![PaletteDefinitionSegment {
+                id: 0,
+                version: 0,
+                rgba,
+            }],
+            objects: vec![ObjectDefinitionSegment {
+                id: 1,
+                version: 0,
+                sequence_flag: 0xC0,
+                data_length: 4 + data.len() as u32,
+                width: 4,
+                height: 4,
+                data,
+            }],
+            windows: vec![WindowDefinitionSegment { windows }],
+        });
+        parser
+    }
+
+    fn red_channel(composition: &SubtitleComposition) -> Vec<u8> {
+        composition.rgba.chunks_exact(4).map(|px| px[0]).collect()
+    }
+
+    #[test]
+    fn render_at_index_applies_crop_rectangle() {
+        let mut parser = parser_with_gradient_object(
+            CompositionObject {
+                object_id: 1,
+                cropped_flag: 0x80,
+                x: 10,
+                y: 20,
+                crop_x: 1,
+                crop_y: 2,
+                crop_width: 2,
+                crop_height: 8,
+                ..Default::default()
+            },
+            Vec::new(),
+        );
+
+        let frame = parser.render_at_index(0).expect("frame should exist");
+        let comp = frame.get_composition(0).expect("composition");
+
+        assert_eq!((comp.x, comp.y, comp.width, comp.height), (10, 20, 2, 2));
+        assert_eq!(red_channel(&comp), vec![2, 3, 2, 3]);
+    }
+
+    #[test]
+    fn render_at_index_clips_object_to_window() {
+        let mut parser = parser_with_gradient_object(
+            CompositionObject {
+                object_id: 1,
+                x: 10,
+                y: 10,
+                ..Default::default()
+            },
+            vec![WindowDefinition {
+                id: 0,
+                x: 12,
+                y: 0,
+                width: 10,
+                height: 11,
+            }],
+        );
 
         let frame = parser.render_at_index(0).expect("frame should exist");
+        let comp = frame.get_composition(0).expect("composition");
+
+        assert_eq!((comp.x, comp.y, comp.width, comp.height), (12, 10, 2, 1));
+        assert_eq!(red_channel(&comp), vec![3, 4]);
+    }
+
+    #[test]
+    fn render_at_index_drops_objects_outside_their_window() {
+        let mut parser = parser_with_gradient_object(
+            CompositionObject {
+                object_id: 1,
+                x: 40,
+                y: 40,
+                ..Default::default()
+            },
+            vec![WindowDefinition {
+                id: 0,
+                x: 0,
+                y: 0,
+                width: 16,
+                height: 16,
+            }],
+        );
+
+        let frame = parser.render_at_index(0).expect("frame shell should exist");
+        assert_eq!(frame.composition_count(), 0);
+        assert_eq!(parser.last_render_issue(), "EMPTY_RENDER");
+    }
 
+    #[test]
+    fn forced_only_mode_skips_unforced_objects() {
+        let mut parser = parser_with_gradient_object(
+            CompositionObject {
+                object_id: 1,
+                ..Default::default()
+            },
+            Vec::new(),
+        );
+        assert!(!parser.is_cue_forced(0));
+        assert!(parser.get_forced_cue_indices().is_empty());
+
+        parser.set_forced_only(true);
+        let frame = parser.render_at_index(0).expect("frame shell should exist");
         assert_eq!(frame.composition_count(), 0);
+
+        let mut parser = parser_with_gradient_object(
+            CompositionObject {
+                object_id: 1,
+                cropped_flag: 0x40,
+                ..Default::default()
+            },
+            Vec::new(),
+        );
+        assert!(parser.is_cue_forced(0));
+        assert_eq!(parser.get_forced_cue_indices(), vec![0]);
+
+        parser.set_forced_only(true);
+        let frame = parser.render_at_index(0).expect("frame should exist");
+        assert_eq!(frame.composition_count(), 1);
+    }
+
+    #[test]
+    fn palette_only_updates_become_keyframes_of_the_open_cue() {
+        let mut parser = parser_with_gradient_object(
+            CompositionObject {
+                object_id: 1,
+                ..Default::default()
+            },
+            Vec::new(),
+        );
+
+        let mut faded = parser.display_sets[0].clone();
+        faded.pts = 500 * 90;
+        faded.objects.clear();
+        let composition = faded.composition.as_mut().unwrap();
+        composition.composition_state = 0;
+        composition.palette_update_flag = 0x80;
+        for palette in &mut faded.palettes {
+            palette.version = 1;
+            for entry in &mut palette.rgba {
+                *entry &= 0x00FF_FFFF;
+                *entry |= 0x8000_0000;
+            }
+        }
+        parser.push_display_set(faded);
+        parser.push_display_set(composition_display_set(900, 0));
+
+        assert_eq!(parser.count(), 1);
+        assert_eq!(parser.get_end_timestamps(), vec![900.0]);
+        assert_eq!(parser.get_cue_keyframe_timestamps(0), vec![500.0]);
+        assert_eq!(parser.find_keyframe_at_timestamp(0, 100.0), -1);
+        assert_eq!(parser.find_keyframe_at_timestamp(0, 600.0), 0);
+
+        let faded_frame = parser.render_keyframe_at_index(0, 0).expect("keyframe");
+        let faded_comp = faded_frame.get_composition(0).unwrap();
+        assert!(faded_comp.rgba.chunks_exact(4).all(|px| px[3] == 0x80));
+
+        let base_frame = parser.render_at_index(0).expect("base frame");
+        let base_comp = base_frame.get_composition(0).unwrap();
+        assert!(base_comp.rgba.chunks_exact(4).all(|px| px[3] == 0xFF));
+        assert_eq!(red_channel(&base_comp), red_channel(&faded_comp));
+        assert_eq!(parser.indexed_cache.len(), 1);
+
+        let alpha_at = |parser: &mut PgsParser, time_seconds: f64| {
+            let frame = parser.render_at_timestamp(time_seconds).expect("frame");
+            frame.get_composition(0).unwrap().rgba[3]
+        };
+        assert_eq!(alpha_at(&mut parser, 0.1), 0xFF);
+        assert_eq!(alpha_at(&mut parser, 0.6), 0x80);
+        assert!(parser.render_at_timestamp(0.95).is_none());
     }
 
-    fn build_end_only_display_set(pts: u32) -> Vec<u8> {
+    fn build_single_object_display_set(pts: u32) -> Vec<u8> {
+        let mut pcs = Vec::new();
+        pcs.extend_from_slice(&1920u16.to_be_bytes());
+        pcs.extend_from_slice(&1080u16.to_be_bytes());
+        pcs.extend_from_slice(&[0x10, 0x00, 0x00, 0x80, 0x00, 0x00, 0x01]);
+        pcs.extend_from_slice(&[0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+
         let mut bytes = Vec::new();
-        bytes.extend_from_slice(&0x5047u16.to_be_bytes()); // magic
-        bytes.extend_from_slice(&pts.to_be_bytes());
-        bytes.extend_from_slice(&0u32.to_be_bytes()); // dts
-        bytes.push(0x80); // END segment
-        bytes.extend_from_slice(&0u16.to_be_bytes()); // size
+        for (segment_type, payload) in [(0x16u8, pcs.as_slice()), (0x80, &[])] {
+            bytes.extend_from_slice(&0x5047u16.to_be_bytes()); // magic
+            bytes.extend_from_slice(&pts.to_be_bytes());
+            bytes.extend_from_slice(&0u32.to_be_bytes()); // dts
+            bytes.push(segment_type);
+            bytes.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+            bytes.extend_from_slice(payload);
+        }
         bytes
     }
 
     #[test]
     fn feed_indexes_complete_display_sets_across_chunk_boundaries() {
-        let first = build_end_only_display_set(90_000);
-        let second = build_end_only_display_set(180_000);
+        let first = build_single_object_display_set(90_000);
+        let second = build_single_object_display_set(180_000);
         let combined = [first.as_slice(), second.as_slice()].concat();
 
         let mut parser = PgsParser::new();
         let split = 7;
+        let second_start = first.len() + 10;
         assert_eq!(parser.feed(&combined[..split]), 0);
         assert!(parser.pending_len() > 0);
-        assert_eq!(parser.feed(&combined[split..split + 10]), 1);
+        assert_eq!(parser.feed(&combined[split..second_start]), 1);
         assert_eq!(parser.count(), 1);
-        assert_eq!(parser.feed(&combined[split + 10..]), 1);
+        assert_eq!(parser.feed(&combined[second_start..]), 1);
         assert_eq!(parser.finish_feed(), 2);
         assert_eq!(parser.get_timestamps(), vec![1000.0, 2000.0]);
+        assert_eq!(parser.get_end_timestamps(), vec![2000.0, 7000.0]);
         assert_eq!(parser.pending_len(), 0);
     }
 }
@@ -1,4 +1,4 @@
-//! Run-length encoding decoder for PGS subtitle bitmaps.
+//! Run-length encoding decoder and encoder for PGS subtitle bitmaps.
 //!
 //! PGS uses a variant of RLE encoding where:
 //! - Non-zero bytes are literal palette indices
@@ -250,6 +250,49 @@ pub fn apply_palette_rgba_bytes(indexed: &[u8], palette: &[u32], target: &mut [u
     }
 }
 
+/// Longest run a single PGS RLE code can express (14-bit length).
+const MAX_RLE_RUN: usize = 0x3FFF;
+
+/// Encode indexed pixel rows as PGS RLE data.
+///
+/// This is the inverse of [`decode_rle_to_indexed`]: each row is terminated
+/// with an end-of-line marker, color 0 is always written as a run (a zero
+/// byte starts a control sequence), and short runs of other colors are
+/// written as literals when that is not larger.
+pub fn encode_rle_from_indexed(indexed: &[u8], width: usize, height: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(indexed.len() / 4 + height * 2);
+    if width == 0 {
+        return out;
+    }
+
+    for row in indexed.chunks(width).take(height) {
+        let mut x = 0;
+        while x < row.len() {
+            let color = row[x];
+            let mut run = 1;
+            while x + run < row.len() && row[x + run] == color && run < MAX_RLE_RUN {
+                run += 1;
+            }
+            push_rle_run(&mut out, color, run);
+            x += run;
+        }
+        out.extend_from_slice(&[0x00, 0x00]);
+    }
+
+    out
+}
+
+#[inline]
+fn push_rle_run(out: &mut Vec<u8>, color: u8, run: usize) {
+    match (color, run) {
+        (0, 1..=63) => out.extend_from_slice(&[0x00, run as u8]),
+        (0, _) => out.extend_from_slice(&[0x00, 0x40 | (run >> 8) as u8, run as u8]),
+        (_, 1..=2) => out.extend(std::iter::repeat_n(color, run)),
+        (_, 3..=63) => out.extend_from_slice(&[0x00, 0x80 | run as u8, color]),
+        (_, _) => out.extend_from_slice(&[0x00, 0xC0 | (run >> 8) as u8, run as u8, color]),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -293,4 +336,20 @@ mod tests {
         assert_eq!(count, 2);
         assert_eq!(&target[..2], &[1, 2]);
     }
+
+    #[test]
+    fn test_encode_roundtrips_through_decoder() {
+        let width = 200;
+        let mut indexed = vec![0u8; width * 3];
+        indexed[5] = 7;
+        indexed[6] = 7;
+        indexed[10..80].fill(3);
+        indexed[width..width + 100].fill(9);
+        indexed[2 * width + 199] = 1;
+
+        let rle = encode_rle_from_indexed(&indexed, width, 3);
+        let mut decoded = vec![0xFFu8; indexed.len()];
+        assert_eq!(decode_rle_to_indexed(&rle, &mut decoded), indexed.len());
+        assert_eq!(decoded, indexed);
+    }
 }
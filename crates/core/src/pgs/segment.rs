@@ -14,6 +14,10 @@ pub enum SegmentType {
     WindowDefinition = 0x17,
     /// End Segment (0x80)
     End = 0x80,
+    /// TextST Dialog Style Segment (0x81)
+    DialogStyle = 0x81,
+    /// TextST Dialog Presentation Segment (0x82)
+    DialogPresentation = 0x82,
 }
 
 impl TryFrom<u8> for SegmentType {
@@ -26,6 +30,8 @@ impl TryFrom<u8> for SegmentType {
             0x16 => Ok(SegmentType::PresentationComposition),
             0x17 => Ok(SegmentType::WindowDefinition),
             0x80 => Ok(SegmentType::End),
+            0x81 => Ok(SegmentType::DialogStyle),
+            0x82 => Ok(SegmentType::DialogPresentation),
             _ => Err(value),
         }
     }
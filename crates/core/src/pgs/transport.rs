@@ -0,0 +1,320 @@
+//! PGS extraction from Blu-ray M2TS / MPEG transport streams.
+//!
+//! HDMV streams carry one PGS segment per PES packet (without the "PG"
+//! header) on PIDs 0x1200-0x121F. Segments are re-framed with their PES
+//! PTS/DTS and grouped into display sets.
+
+use super::{DisplaySet, DisplaySetParseAttempt, SegmentType};
+use crate::ts::{PesAssembler, iter_ts_packets, parse_ts_programs};
+
+/// PMT stream type of HDMV presentation graphics.
+pub const HDMV_PGS_STREAM_TYPE: u8 = 0x90;
+/// First PID reserved for PGS streams on Blu-ray.
+pub const PGS_PID_FIRST: u16 = 0x1200;
+/// Last PID reserved for PGS streams on Blu-ray.
+pub const PGS_PID_LAST: u16 = 0x121F;
+
+/// A PGS stream found in a transport stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgsTransportStream {
+    /// Packet identifier
+    pub pid: u16,
+    /// PMT stream type (0x90 for HDMV PGS)
+    pub stream_type: u8,
+    /// ISO 639 language code from the PMT, if present
+    pub language: Option<String>,
+}
+
+/// Check whether a PID lies in the Blu-ray PGS range.
+#[inline]
+pub fn is_pgs_pid(pid: u16) -> bool {
+    (PGS_PID_FIRST..=PGS_PID_LAST).contains(&pid)
+}
+
+/// List the PGS streams declared in the PAT/PMT of a transport stream.
+///
+/// When no PMT lists a PGS stream, PIDs in the Blu-ray PGS range that carry
+/// packets are reported instead (without a language).
+pub fn list_pgs_streams(data: &[u8]) -> Vec<PgsTransportStream> {
+    let streams: Vec<PgsTransportStream> = parse_ts_programs(data)
+        .into_iter()
+        .filter(|stream| stream.stream_type == HDMV_PGS_STREAM_TYPE)
+        .map(|stream| PgsTransportStream {
+            pid: stream.pid,
+            stream_type: stream.stream_type,
+            language: stream.language(),
+        })
+        .collect();
+    if !streams.is_empty() {
+        return streams;
+    }
+
+    let mut pids: Vec<u16> = iter_ts_packets(data)
+        .map(|packet| packet.pid)
+        .filter(|&pid| is_pgs_pid(pid))
+        .collect();
+    pids.sort_unstable();
+    pids.dedup();
+    pids.into_iter()
+        .map(|pid| PgsTransportStream {
+            pid,
+            stream_type: HDMV_PGS_STREAM_TYPE,
+            language: None,
+        })
+        .collect()
+}
+
+/// Demux a PGS stream from a transport stream and re-frame it as `.sup` data.
+pub fn extract_pgs_sup_from_ts(data: &[u8], pid: u16) -> Vec<u8> {
+    let mut assembler = PesAssembler::new(pid);
+    let mut sup = Vec::new();
+    let mut last_pts = 0u32;
+    let mut last_dts = 0u32;
+
+    let mut append = |pes: crate::ts::PesPacket, sup: &mut Vec<u8>| {
+        // Timestamps wrap into the 32-bit fields of the "PG" header
+        if let Some(pts) = pes.pts {
+            last_pts = pts as u32;
+            last_dts = pes.dts.map_or(last_pts, |dts| dts as u32);
+        }
+        append_pes_segments(sup, last_pts, last_dts, &pes.payload);
+    };
+
+    for packet in iter_ts_packets(data) {
+        if packet.pid != pid || packet.transport_error {
+            continue;
+        }
+        if let Some(pes) = assembler.push_packet(&packet) {
+            append(pes, &mut sup);
+        }
+    }
+    if let Some(pes) = assembler.flush() {
+        append(pes, &mut sup);
+    }
+
+    sup
+}
+
+/// Demux a PGS stream from a transport stream into display sets.
+pub fn extract_pgs_display_sets(data: &[u8], pid: u16) -> Vec<DisplaySet> {
+    parse_sup_display_sets(&extract_pgs_sup_from_ts(data, pid))
+}
+
+/// Parse re-framed `.sup` data into display sets. A corrupt display set is
+/// skipped by resyncing on the next "PG" header, like `PgsParser::parse`.
+pub(super) fn parse_sup_display_sets(sup: &[u8]) -> Vec<DisplaySet> {
+    let mut display_sets = Vec::new();
+    let mut offset = 0;
+
+    while offset < sup.len() {
+        match DisplaySet::try_parse(&sup[offset..], true) {
+            DisplaySetParseAttempt::Complete(display_set, consumed) => {
+                display_sets.push(display_set);
+                offset += consumed;
+            }
+            // All data is present, so an incomplete display set is corrupt too
+            DisplaySetParseAttempt::Incomplete | DisplaySetParseAttempt::Invalid => {
+                match sup[offset + 1..]
+                    .windows(2)
+                    .position(|magic| magic == [0x50, 0x47])
+                {
+                    Some(position) => offset += 1 + position,
+                    None => break,
+                }
+            }
+        }
+    }
+
+    display_sets
+}
+
+/// Prefix each segment of a PES payload with a "PG" header.
+pub(super) fn append_pes_segments(out: &mut Vec<u8>, pts: u32, dts: u32, mut payload: &[u8]) {
+    while payload.len() >= 3 {
+        let segment_type = payload[0];
+        let segment_len = u16::from_be_bytes([payload[1], payload[2]]) as usize;
+        let Some(segment) = payload.get(..3 + segment_len) else {
+            break;
+        };
+
+        out.extend_from_slice(&0x5047u16.to_be_bytes());
+        out.extend_from_slice(&pts.to_be_bytes());
+        // Only the PCS and ODS carry a distinct decode time
+        let segment_dts = match SegmentType::try_from(segment_type) {
+            Ok(SegmentType::PresentationComposition | SegmentType::ObjectDefinition) => dts,
+            _ => 0,
+        };
+        out.extend_from_slice(&segment_dts.to_be_bytes());
+        out.extend_from_slice(segment);
+        payload = &payload[3 + segment_len..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pgs::{PgsBitmap, PgsEncoder, PgsParser};
+    use crate::ts::{M2TS_PACKET_SIZE, TS_PACKET_SIZE, TS_SYNC_BYTE};
+
+    fn encode_timestamp(prefix: u8, ts: u64) -> [u8; 5] {
+        [
+            (prefix << 4) | (((ts >> 30) as u8 & 0x07) << 1) | 1,
+            (ts >> 22) as u8,
+            (((ts >> 15) as u8 & 0x7F) << 1) | 1,
+            (ts >> 7) as u8,
+            ((ts as u8 & 0x7F) << 1) | 1,
+        ]
+    }
+
+    /// Packetize a payload into 192-byte M2TS packets on `pid`.
+    fn m2ts_packets(pid: u16, payload: &[u8], counter: &mut u8) -> Vec<u8> {
+        let mut out = Vec::new();
+        for (index, chunk) in payload.chunks(TS_PACKET_SIZE - 4).enumerate() {
+            let start = if index == 0 { 0x40 } else { 0x00 };
+            out.extend_from_slice(&[0, 0, 0, 0]);
+            let mut packet = vec![
+                TS_SYNC_BYTE,
+                start | (pid >> 8) as u8,
+                pid as u8,
+                0x10 | (*counter & 0x0F),
+            ];
+            *counter = counter.wrapping_add(1);
+            packet.extend_from_slice(chunk);
+            packet.resize(TS_PACKET_SIZE, 0xFF);
+            out.extend_from_slice(&packet);
+        }
+        out
+    }
+
+    fn psi_section(table_id: u8, extension: u16, body: &[u8]) -> Vec<u8> {
+        let mut section = vec![table_id, 0xB0, 0x00];
+        section.extend_from_slice(&extension.to_be_bytes());
+        section.extend_from_slice(&[0xC1, 0x00, 0x00]);
+        section.extend_from_slice(body);
+        section.extend_from_slice(&[0, 0, 0, 0]);
+        let section_len = section.len() - 3;
+        section[1] |= (section_len >> 8) as u8;
+        section[2] = section_len as u8;
+        let mut payload = vec![0x00];
+        payload.extend(section);
+        payload
+    }
+
+    /// Convert a `.sup` stream into an M2TS stream with one segment per PES.
+    fn sup_to_m2ts(sup: &[u8], pid: u16) -> Vec<u8> {
+        let mut pmt_body = vec![0xE1, 0x00, 0xF0, 0x00];
+        pmt_body.extend_from_slice(&[HDMV_PGS_STREAM_TYPE, 0xE0 | (pid >> 8) as u8, pid as u8]);
+        pmt_body.extend_from_slice(&[0xF0, 0x06, 0x0A, 0x04, b'j', b'p', b'n', 0x00]);
+
+        let mut counter = 0;
+        let mut out = m2ts_packets(0, &psi_section(0x00, 1, &[0x00, 0x01, 0xE1, 0x00]), &mut 0);
+        out.extend(m2ts_packets(
+            0x100,
+            &psi_section(0x02, 1, &pmt_body),
+            &mut 0,
+        ));
+
+        let mut offset = 0;
+        while offset + 13 <= sup.len() {
+            let pts = u32::from_be_bytes(sup[offset + 2..offset + 6].try_into().unwrap());
+            let segment_len = u16::from_be_bytes([sup[offset + 11], sup[offset + 12]]) as usize;
+            let segment = &sup[offset + 10..offset + 13 + segment_len];
+            let dts = pts.saturating_sub(9_000) as u64;
+
+            let mut pes = vec![0x00, 0x00, 0x01, 0xBD];
+            pes.extend_from_slice(&((3 + 10 + segment.len()) as u16).to_be_bytes());
+            pes.extend_from_slice(&[0x81, 0xC0, 0x0A]);
+            pes.extend_from_slice(&encode_timestamp(0x3, pts as u64));
+            pes.extend_from_slice(&encode_timestamp(0x1, dts));
+            pes.extend_from_slice(segment);
+            out.extend(m2ts_packets(pid, &pes, &mut counter));
+            offset += 13 + segment_len;
+        }
+        out
+    }
+
+    fn encoded_sup() -> Vec<u8> {
+        let mut encoder = PgsEncoder::new(1920, 1080);
+        for (index, start) in [1000u32, 3000].into_iter().enumerate() {
+            let bitmap = PgsBitmap {
+                x: 10,
+                y: 20,
+                width: 300,
+                height: 100,
+                rgba: [255, 255, 255, 255].repeat(300 * 100),
+                forced: index == 1,
+            };
+            encoder.add_cue(start, start + 1500, &[bitmap]).unwrap();
+        }
+        encoder.finish()
+    }
+
+    #[test]
+    fn lists_pgs_streams_with_language() {
+        let m2ts = sup_to_m2ts(&encoded_sup(), 0x1201);
+        assert_eq!(m2ts.len() % M2TS_PACKET_SIZE, 0);
+
+        assert_eq!(
+            list_pgs_streams(&m2ts),
+            vec![PgsTransportStream {
+                pid: 0x1201,
+                stream_type: HDMV_PGS_STREAM_TYPE,
+                language: Some("jpn".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn corrupt_display_set_does_not_drop_later_ones() {
+        let mut sup = encoded_sup();
+        // Give the second PCS 255 composition objects so parsing overruns it
+        let mut offset = 0;
+        let mut compositions = 0;
+        while offset + 13 <= sup.len() {
+            let segment_len = u16::from_be_bytes([sup[offset + 11], sup[offset + 12]]) as usize;
+            if sup[offset + 10] == 0x16 {
+                compositions += 1;
+                if compositions == 2 {
+                    sup[offset + 13 + 10] = 0xFF;
+                }
+            }
+            offset += 13 + segment_len;
+        }
+        let m2ts = sup_to_m2ts(&sup, 0x1200);
+
+        let display_sets = extract_pgs_display_sets(&m2ts, 0x1200);
+        let timed: Vec<u32> = display_sets
+            .iter()
+            .filter(|display_set| display_set.composition.is_some())
+            .map(|display_set| display_set.pts)
+            .collect();
+        assert_eq!(timed, vec![90_000, 270_000, 405_000]);
+    }
+
+    #[test]
+    fn demuxed_display_sets_match_the_source_stream() {
+        let sup = encoded_sup();
+        let m2ts = sup_to_m2ts(&sup, 0x1200);
+
+        let display_sets = extract_pgs_display_sets(&m2ts, 0x1200);
+        assert_eq!(display_sets.len(), 4);
+        assert_eq!(display_sets[0].pts, 90_000);
+        assert_eq!(display_sets[0].dts, 81_000);
+        assert_eq!(display_sets[2].pts, 270_000);
+
+        let mut from_sup = PgsParser::new();
+        from_sup.parse(&sup);
+        let mut from_ts = PgsParser::new();
+        assert_eq!(from_ts.parse_transport_stream(&m2ts, None), Ok(2));
+        assert_eq!(from_ts.get_timestamps(), from_sup.get_timestamps());
+        assert_eq!(from_ts.get_end_timestamps(), from_sup.get_end_timestamps());
+        assert!(from_ts.is_cue_forced(1));
+
+        let expected = from_sup.render_at_index(0).unwrap();
+        let actual = from_ts.render_at_index(0).unwrap();
+        assert_eq!(
+            actual.get_composition(0).unwrap().rgba,
+            expected.get_composition(0).unwrap().rgba
+        );
+    }
+}
@@ -0,0 +1,526 @@
+//! PGS decoder-model conformance checks.
+//!
+//! Walks display sets the way a hardware HDMV decoder would and reports
+//! violations of the presentation graphics decoder model.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use super::{DisplaySet, PgsParser, WindowDefinition};
+
+/// Maximum number of windows in one epoch.
+pub const MAX_WINDOWS_PER_EPOCH: usize = 2;
+/// Size of the coded data buffer in bytes.
+pub const CODED_DATA_BUFFER_SIZE: usize = 1024 * 1024;
+/// Size of the decoded object buffer in bytes (one byte per pixel).
+pub const DECODED_OBJECT_BUFFER_SIZE: usize = 4 * 1024 * 1024;
+
+/// A single conformance violation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgsDiagnostic {
+    /// Index of the offending display set
+    pub display_set_index: usize,
+    pub kind: PgsDiagnosticKind,
+}
+
+/// The kind of conformance violation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PgsDiagnosticKind {
+    /// More than two windows defined in one epoch
+    TooManyWindows { count: usize },
+    /// A composition object references a window not defined in the epoch
+    UndefinedWindow { object_id: u16, window_id: u8 },
+    /// A composition object references an object not defined in the epoch
+    UndefinedObject { object_id: u16 },
+    /// A composition object extends past its window
+    ObjectOutsideWindow { object_id: u16, window_id: u8 },
+    /// A composition object extends past the screen
+    ObjectOutsideScreen { object_id: u16 },
+    /// A window extends past the screen
+    WindowOutsideScreen { window_id: u8 },
+    /// The composition number did not increase
+    CompositionNumberNotIncreasing { previous: u16, current: u16 },
+    /// A palette was redefined with different entries but the same version
+    PaletteVersionUnchanged { palette_id: u8, version: u8 },
+    /// An object was redefined with different data but the same version
+    ObjectVersionUnchanged { object_id: u16, version: u8 },
+    /// Coded object data of one display set exceeds the coded data buffer
+    CodedDataBufferOverflow { size: usize },
+    /// Decoded objects of one epoch exceed the object buffer
+    ObjectBufferOverflow { size: usize },
+    /// The decoding timestamp is after the presentation timestamp
+    DtsAfterPts { dts: u32, pts: u32 },
+    /// The presentation timestamp did not increase
+    PtsNotIncreasing { previous: u32, current: u32 },
+    /// Decoding starts before the previous display set is presented
+    DecodeOverlapsPrevious { dts: u32, previous_pts: u32 },
+    /// PTS - DTS is shorter than the time needed to decode and draw
+    DecodeBudgetExceeded { required: u32, available: u32 },
+}
+
+impl PgsDiagnosticKind {
+    /// Get a stable diagnostic code.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::TooManyWindows { .. } => "TOO_MANY_WINDOWS",
+            Self::UndefinedWindow { .. } => "UNDEFINED_WINDOW",
+            Self::UndefinedObject { .. } => "UNDEFINED_OBJECT",
+            Self::ObjectOutsideWindow { .. } => "OBJECT_OUTSIDE_WINDOW",
+            Self::ObjectOutsideScreen { .. } => "OBJECT_OUTSIDE_SCREEN",
+            Self::WindowOutsideScreen { .. } => "WINDOW_OUTSIDE_SCREEN",
+            Self::CompositionNumberNotIncreasing { .. } => "COMPOSITION_NUMBER_NOT_INCREASING",
+            Self::PaletteVersionUnchanged { .. } => "PALETTE_VERSION_UNCHANGED",
+            Self::ObjectVersionUnchanged { .. } => "OBJECT_VERSION_UNCHANGED",
+            Self::CodedDataBufferOverflow { .. } => "CODED_DATA_BUFFER_OVERFLOW",
+            Self::ObjectBufferOverflow { .. } => "OBJECT_BUFFER_OVERFLOW",
+            Self::DtsAfterPts { .. } => "DTS_AFTER_PTS",
+            Self::PtsNotIncreasing { .. } => "PTS_NOT_INCREASING",
+            Self::DecodeOverlapsPrevious { .. } => "DECODE_OVERLAPS_PREVIOUS",
+            Self::DecodeBudgetExceeded { .. } => "DECODE_BUDGET_EXCEEDED",
+        }
+    }
+}
+
+impl fmt::Display for PgsDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "display set {}: {}: ",
+            self.display_set_index,
+            self.kind.code()
+        )?;
+        match &self.kind {
+            PgsDiagnosticKind::TooManyWindows { count } => {
+                write!(f, "{count} windows defined in epoch")
+            }
+            PgsDiagnosticKind::UndefinedWindow {
+                object_id,
+                window_id,
+            } => write!(f, "object {object_id} uses undefined window {window_id}"),
+            PgsDiagnosticKind::UndefinedObject { object_id } => {
+                write!(f, "object {object_id} is not defined in epoch")
+            }
+            PgsDiagnosticKind::ObjectOutsideWindow {
+                object_id,
+                window_id,
+            } => write!(f, "object {object_id} overflows window {window_id}"),
+            PgsDiagnosticKind::ObjectOutsideScreen { object_id } => {
+                write!(f, "object {object_id} overflows the screen")
+            }
+            PgsDiagnosticKind::WindowOutsideScreen { window_id } => {
+                write!(f, "window {window_id} overflows the screen")
+            }
+            PgsDiagnosticKind::CompositionNumberNotIncreasing { previous, current } => {
+                write!(f, "composition number {current} follows {previous}")
+            }
+            PgsDiagnosticKind::PaletteVersionUnchanged {
+                palette_id,
+                version,
+            } => write!(f, "palette {palette_id} changed but kept version {version}"),
+            PgsDiagnosticKind::ObjectVersionUnchanged { object_id, version } => {
+                write!(f, "object {object_id} changed but kept version {version}")
+            }
+            PgsDiagnosticKind::CodedDataBufferOverflow { size } => {
+                write!(
+                    f,
+                    "{size} bytes of coded data exceed {CODED_DATA_BUFFER_SIZE}"
+                )
+            }
+            PgsDiagnosticKind::ObjectBufferOverflow { size } => {
+                write!(
+                    f,
+                    "{size} bytes of objects exceed {DECODED_OBJECT_BUFFER_SIZE}"
+                )
+            }
+            PgsDiagnosticKind::DtsAfterPts { dts, pts } => {
+                write!(f, "DTS {dts} is after PTS {pts}")
+            }
+            PgsDiagnosticKind::PtsNotIncreasing { previous, current } => {
+                write!(f, "PTS {current} follows {previous}")
+            }
+            PgsDiagnosticKind::DecodeOverlapsPrevious { dts, previous_pts } => {
+                write!(f, "DTS {dts} is before previous PTS {previous_pts}")
+            }
+            PgsDiagnosticKind::DecodeBudgetExceeded {
+                required,
+                available,
+            } => write!(f, "needs {required} ticks to decode, has {available}"),
+        }
+    }
+}
+
+/// Objects, windows and palettes defined in the current epoch.
+#[derive(Default)]
+struct EpochState {
+    windows: HashMap<u8, WindowDefinition>,
+    palettes: HashMap<u8, (u8, Vec<u32>)>,
+    objects: HashMap<u16, EpochObject>,
+    object_buffer_reported: bool,
+    window_count_reported: bool,
+}
+
+struct EpochObject {
+    version: u8,
+    width: u16,
+    height: u16,
+    data: Vec<u8>,
+}
+
+/// Check display sets against the PGS decoder model.
+///
+/// DTS checks are skipped for display sets with a zero DTS, which many
+/// `.sup` muxers write in place of a real decode time.
+pub fn validate_display_sets(display_sets: &[DisplaySet]) -> Vec<PgsDiagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut epoch = EpochState::default();
+    let mut previous_composition_number: Option<u16> = None;
+    let mut previous_pts: Option<u32> = None;
+
+    for (index, display_set) in display_sets.iter().enumerate() {
+        let mut report = |kind| {
+            diagnostics.push(PgsDiagnostic {
+                display_set_index: index,
+                kind,
+            })
+        };
+        let Some(composition) = display_set.composition.as_ref() else {
+            continue;
+        };
+        if composition.is_epoch_start() {
+            epoch = EpochState::default();
+        }
+
+        // Timing
+        let pts = display_set.pts;
+        let dts = display_set.dts;
+        if let Some(previous) = previous_pts {
+            if pts <= previous {
+                report(PgsDiagnosticKind::PtsNotIncreasing {
+                    previous,
+                    current: pts,
+                });
+            }
+            if dts != 0 && dts < previous {
+                report(PgsDiagnosticKind::DecodeOverlapsPrevious {
+                    dts,
+                    previous_pts: previous,
+                });
+            }
+        }
+        if dts > pts {
+            report(PgsDiagnosticKind::DtsAfterPts { dts, pts });
+        }
+        previous_pts = Some(pts);
+
+        if let Some(previous) = previous_composition_number {
+            let step = composition.composition_number.wrapping_sub(previous);
+            if step == 0 || step >= 0x8000 {
+                report(PgsDiagnosticKind::CompositionNumberNotIncreasing {
+                    previous,
+                    current: composition.composition_number,
+                });
+            }
+        }
+        previous_composition_number = Some(composition.composition_number);
+
+        // Windows
+        for window in display_set.windows.iter().flat_map(|wds| &wds.windows) {
+            if window.x as u32 + window.width as u32 > composition.width as u32
+                || window.y as u32 + window.height as u32 > composition.height as u32
+            {
+                report(PgsDiagnosticKind::WindowOutsideScreen {
+                    window_id: window.id,
+                });
+            }
+            epoch.windows.insert(window.id, *window);
+        }
+        if epoch.windows.len() > MAX_WINDOWS_PER_EPOCH && !epoch.window_count_reported {
+            epoch.window_count_reported = true;
+            report(PgsDiagnosticKind::TooManyWindows {
+                count: epoch.windows.len(),
+            });
+        }
+
+        // Palettes
+        for palette in &display_set.palettes {
+            if let Some((version, rgba)) = epoch.palettes.get(&palette.id)
+                && *version == palette.version
+                && *rgba != palette.rgba
+            {
+                report(PgsDiagnosticKind::PaletteVersionUnchanged {
+                    palette_id: palette.id,
+                    version: palette.version,
+                });
+            }
+            epoch
+                .palettes
+                .insert(palette.id, (palette.version, palette.rgba.clone()));
+        }
+
+        // Objects
+        let mut coded_size = 0usize;
+        let mut decoded_objects: Vec<(u16, u8, u16, u16, Vec<u8>)> = Vec::new();
+        for segment in &display_set.objects {
+            coded_size += segment.data.len();
+            if segment.is_first_in_sequence() {
+                decoded_objects.push((
+                    segment.id,
+                    segment.version,
+                    segment.width,
+                    segment.height,
+                    segment.data.clone(),
+                ));
+            } else if let Some(object) = decoded_objects
+                .iter_mut()
+                .rev()
+                .find(|object| object.0 == segment.id)
+            {
+                object.4.extend_from_slice(&segment.data);
+            }
+        }
+        if coded_size > CODED_DATA_BUFFER_SIZE {
+            report(PgsDiagnosticKind::CodedDataBufferOverflow { size: coded_size });
+        }
+
+        let mut decode_ticks = 0u64;
+        for (object_id, version, width, height, data) in decoded_objects {
+            if let Some(previous) = epoch.objects.get(&object_id)
+                && previous.version == version
+                && previous.data != data
+            {
+                report(PgsDiagnosticKind::ObjectVersionUnchanged { object_id, version });
+            }
+            decode_ticks += transfer_ticks(width, height, 1600);
+            epoch.objects.insert(
+                object_id,
+                EpochObject {
+                    version,
+                    width,
+                    height,
+                    data,
+                },
+            );
+        }
+        let object_buffer: usize = epoch
+            .objects
+            .values()
+            .map(|object| object.width as usize * object.height as usize)
+            .sum();
+        if object_buffer > DECODED_OBJECT_BUFFER_SIZE && !epoch.object_buffer_reported {
+            epoch.object_buffer_reported = true;
+            report(PgsDiagnosticKind::ObjectBufferOverflow {
+                size: object_buffer,
+            });
+        }
+
+        // Composition objects
+        let mut drawn_windows: Vec<u8> = Vec::new();
+        for comp_obj in &composition.composition_objects {
+            let object_id = comp_obj.object_id;
+            let Some(object) = epoch.objects.get(&object_id) else {
+                report(PgsDiagnosticKind::UndefinedObject { object_id });
+                continue;
+            };
+            let (width, height) = if comp_obj.has_cropping() {
+                (comp_obj.crop_width, comp_obj.crop_height)
+            } else {
+                (object.width, object.height)
+            };
+            let right = comp_obj.x as u32 + width as u32;
+            let bottom = comp_obj.y as u32 + height as u32;
+
+            if right > composition.width as u32 || bottom > composition.height as u32 {
+                report(PgsDiagnosticKind::ObjectOutsideScreen { object_id });
+            }
+
+            let window_id = comp_obj.window_id;
+            let Some(window) = epoch.windows.get(&window_id) else {
+                report(PgsDiagnosticKind::UndefinedWindow {
+                    object_id,
+                    window_id,
+                });
+                continue;
+            };
+            if comp_obj.x < window.x
+                || comp_obj.y < window.y
+                || right > window.x as u32 + window.width as u32
+                || bottom > window.y as u32 + window.height as u32
+            {
+                report(PgsDiagnosticKind::ObjectOutsideWindow {
+                    object_id,
+                    window_id,
+                });
+            }
+            if !drawn_windows.contains(&window_id) {
+                drawn_windows.push(window_id);
+                decode_ticks += transfer_ticks(window.width, window.height, 3200);
+            }
+        }
+
+        // Decode-time budget: plane clear, object decoding, window transfer
+        if dts != 0 && dts <= pts {
+            if composition.is_epoch_start() {
+                decode_ticks += transfer_ticks(composition.width, composition.height, 3200);
+            }
+            let required = decode_ticks.min(u32::MAX as u64) as u32;
+            let available = pts - dts;
+            if required > available {
+                report(PgsDiagnosticKind::DecodeBudgetExceeded {
+                    required,
+                    available,
+                });
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// 90kHz ticks to move a `width` x `height` 8-bit area; `divisor` is 1600 for
+/// object decoding (128 Mbit/s) and 3200 for plane writes (256 Mbit/s).
+pub(super) fn transfer_ticks(width: u16, height: u16, divisor: u64) -> u64 {
+    (width as u64 * height as u64 * 9).div_ceil(divisor)
+}
+
+impl PgsParser {
+    /// Check the parsed display sets against the PGS decoder model.
+    pub fn validate(&self) -> Vec<PgsDiagnostic> {
+        validate_display_sets(self.display_sets())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pgs::{PgsBitmap, PgsEncoder};
+
+    fn bitmap(x: u16, y: u16, width: u16, height: u16) -> PgsBitmap {
+        PgsBitmap {
+            x,
+            y,
+            width,
+            height,
+            rgba: [255, 255, 255, 255].repeat(width as usize * height as usize),
+            forced: false,
+        }
+    }
+
+    fn parse(sup: &[u8]) -> PgsParser {
+        let mut parser = PgsParser::new();
+        parser.parse(sup);
+        parser
+    }
+
+    /// Rewrite the PTS/DTS of the `n`th "PG" header.
+    fn set_timestamps(sup: &mut [u8], segment: usize, pts: u32, dts: u32) {
+        let mut offset = 0;
+        for _ in 0..segment {
+            let len = u16::from_be_bytes([sup[offset + 11], sup[offset + 12]]) as usize;
+            offset += 13 + len;
+        }
+        sup[offset + 2..offset + 6].copy_from_slice(&pts.to_be_bytes());
+        sup[offset + 6..offset + 10].copy_from_slice(&dts.to_be_bytes());
+    }
+
+    #[test]
+    fn encoder_output_conforms() {
+        let mut encoder = PgsEncoder::new(1920, 1080);
+        encoder
+            .add_cue(
+                1000,
+                2000,
+                &[bitmap(100, 900, 400, 60), bitmap(100, 980, 400, 60)],
+            )
+            .unwrap();
+        encoder
+            .add_cue(3000, 4000, &[bitmap(200, 900, 300, 60)])
+            .unwrap();
+
+        assert_eq!(parse(&encoder.finish()).validate(), Vec::new());
+    }
+
+    #[test]
+    fn reports_ordering_and_budget_violations() {
+        let mut encoder = PgsEncoder::new(1920, 1080);
+        encoder
+            .add_cue(1000, 2000, &[bitmap(0, 0, 1920, 400)])
+            .unwrap();
+        encoder
+            .add_cue(3000, 4000, &[bitmap(0, 0, 100, 100)])
+            .unwrap();
+        let mut sup = encoder.finish();
+
+        // Decode the large first cue in only 10 ticks
+        set_timestamps(&mut sup, 0, 90_000, 89_990);
+        let diagnostics = parse(&sup).validate();
+        assert!(diagnostics.iter().any(|diagnostic| {
+            diagnostic.display_set_index == 0
+                && matches!(
+                    diagnostic.kind,
+                    PgsDiagnosticKind::DecodeBudgetExceeded { available: 10, .. }
+                )
+        }));
+
+        // Present the second cue before the first clears
+        let parser = parse(&sup);
+        let second_pcs = parser
+            .display_sets()
+            .iter()
+            .position(|ds| ds.pts == 270_000)
+            .unwrap();
+        let mut segment = 0;
+        let mut offset = 0;
+        while u32::from_be_bytes(sup[offset + 2..offset + 6].try_into().unwrap()) != 270_000 {
+            let len = u16::from_be_bytes([sup[offset + 11], sup[offset + 12]]) as usize;
+            offset += 13 + len;
+            segment += 1;
+        }
+        set_timestamps(&mut sup, segment, 150_000, 0);
+        let diagnostics = parse(&sup).validate();
+        assert!(diagnostics.iter().any(|diagnostic| {
+            diagnostic.display_set_index == second_pcs
+                && diagnostic.kind
+                    == PgsDiagnosticKind::PtsNotIncreasing {
+                        previous: 180_000,
+                        current: 150_000,
+                    }
+        }));
+    }
+
+    #[test]
+    fn reports_window_object_and_version_violations() {
+        let mut encoder = PgsEncoder::new(1920, 1080);
+        encoder
+            .add_cue(1000, 2000, &[bitmap(100, 100, 50, 50)])
+            .unwrap();
+        let parser = parse(&encoder.finish());
+        let mut display_sets = parser.display_sets().to_vec();
+
+        // Shrink the window, add two more, then resend the display set with
+        // changed object data under the same version and composition number
+        let first = &mut display_sets[0];
+        let window = &mut first.windows[0].windows[0];
+        window.width = 20;
+        let mut extra = *window;
+        extra.id = 1;
+        first.windows[0].windows.push(extra);
+        extra.id = 2;
+        extra.x = 1910;
+        first.windows[0].windows.push(extra);
+        let mut repeat = display_sets[0].clone();
+        repeat.pts += 90;
+        repeat.composition.as_mut().unwrap().composition_state = 0x00;
+        repeat.objects[0].data[0] ^= 0xFF;
+        display_sets.insert(1, repeat);
+
+        let codes: Vec<(usize, &str)> = validate_display_sets(&display_sets)
+            .iter()
+            .map(|diagnostic| (diagnostic.display_set_index, diagnostic.kind.code()))
+            .collect();
+        assert!(codes.contains(&(0, "WINDOW_OUTSIDE_SCREEN")));
+        assert!(codes.contains(&(0, "TOO_MANY_WINDOWS")));
+        assert!(codes.contains(&(0, "OBJECT_OUTSIDE_WINDOW")));
+        assert!(codes.contains(&(1, "COMPOSITION_NUMBER_NOT_INCREASING")));
+        assert!(codes.contains(&(1, "OBJECT_VERSION_UNCHANGED")));
+    }
+}
@@ -0,0 +1,13 @@
+//! Blu-ray text subtitle (HDMV TextST) parsing.
+//!
+//! Parses dialog style (0x81) and dialog presentation (0x82) segments into
+//! timed text cues with region styles, inline style changes and palettes.
+//! Rendering is left to the caller's text pipeline.
+
+mod parser;
+mod segment;
+mod transport;
+
+pub use parser::*;
+pub use segment::*;
+pub use transport::*;
@@ -0,0 +1,309 @@
+//! High-level TextST parser API.
+
+use super::segment::{
+    DialogPresentationSegment, DialogStyleSegment, SEGMENT_DIALOG_PRESENTATION,
+    SEGMENT_DIALOG_STYLE, TextStDialogRegion, TextStRegionStyle, TextStRun,
+};
+use super::transport::{extract_textst_segments, list_textst_streams};
+use crate::utils::binary_search_timestamp;
+
+/// A timed TextST dialog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextStCue {
+    pub start_ms: u32,
+    pub end_ms: u32,
+    /// Replacement palette (256 RGBA entries) for this dialog only
+    pub palette_update: Option<Vec<u32>>,
+    /// Up to two dialog regions
+    pub regions: Vec<TextStDialogRegion>,
+}
+
+impl TextStCue {
+    /// Get the plain text of all regions, one region per line.
+    pub fn text(&self) -> String {
+        self.regions
+            .iter()
+            .map(TextStDialogRegion::text)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Check whether any region is forced on.
+    pub fn is_forced(&self) -> bool {
+        self.regions.iter().any(|region| region.forced_on)
+    }
+}
+
+/// Blu-ray text subtitle (TextST) parser.
+pub struct TextStParser {
+    style: Option<DialogStyleSegment>,
+    cues: Vec<TextStCue>,
+    /// Cue start timestamps in milliseconds for quick lookup
+    timestamps_ms: Vec<u32>,
+}
+
+impl TextStParser {
+    pub fn new() -> Self {
+        Self {
+            style: None,
+            cues: Vec::new(),
+            timestamps_ms: Vec::new(),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.style = None;
+        self.cues.clear();
+        self.timestamps_ms.clear();
+    }
+
+    /// Parse a TextST elementary stream (concatenated segments, optionally
+    /// `"PG"` framed). Returns the number of cues found.
+    pub fn parse(&mut self, data: &[u8]) -> usize {
+        self.reset();
+        let mut offset = 0;
+
+        while offset < data.len() {
+            // Skip a "PG" + PTS + DTS header when present
+            if data[offset..].starts_with(b"PG") {
+                offset += 10;
+            }
+            let Some(header) = data.get(offset..offset + 3) else {
+                break;
+            };
+            let segment_type = header[0];
+            let segment_len = u16::from_be_bytes([header[1], header[2]]) as usize;
+            let Some(body) = data.get(offset + 3..offset + 3 + segment_len) else {
+                break;
+            };
+            self.push_segment(segment_type, body);
+            offset += 3 + segment_len;
+        }
+
+        self.cues.len()
+    }
+
+    /// Parse a TextST stream from an M2TS/TS transport stream.
+    /// Uses the first TextST stream when `pid` is `None`.
+    /// Returns the number of cues found.
+    pub fn parse_transport_stream(
+        &mut self,
+        data: &[u8],
+        pid: Option<u16>,
+    ) -> Result<usize, String> {
+        let pid = match pid {
+            Some(pid) => pid,
+            None => list_textst_streams(data)
+                .first()
+                .map(|stream| stream.pid)
+                .ok_or_else(|| "No TextST stream found in transport stream".to_string())?,
+        };
+
+        Ok(self.parse(&extract_textst_segments(data, pid)))
+    }
+
+    fn push_segment(&mut self, segment_type: u8, body: &[u8]) {
+        match segment_type {
+            SEGMENT_DIALOG_STYLE => {
+                if let Some(style) = DialogStyleSegment::parse(body) {
+                    self.style = Some(style);
+                }
+            }
+            SEGMENT_DIALOG_PRESENTATION => {
+                if let Some(dialog) = DialogPresentationSegment::parse(body) {
+                    let start_ms = (dialog.start_pts / 90) as u32;
+                    let end_ms = ((dialog.end_pts / 90) as u32).max(start_ms);
+                    // The stream need not be in presentation order; insert each dialog
+                    // at its sorted position so lookups can binary search
+                    let index = self.timestamps_ms.partition_point(|&ts| ts <= start_ms);
+                    self.timestamps_ms.insert(index, start_ms);
+                    self.cues.insert(
+                        index,
+                        TextStCue {
+                            start_ms,
+                            end_ms,
+                            palette_update: dialog.palette_update,
+                            regions: dialog.regions,
+                        },
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Get the number of cues.
+    pub fn count(&self) -> usize {
+        self.cues.len()
+    }
+
+    /// Get the dialog style segment (region styles, user styles, palette).
+    pub fn dialog_style(&self) -> Option<&DialogStyleSegment> {
+        self.style.as_ref()
+    }
+
+    /// Get all cues in presentation order.
+    pub fn cues(&self) -> &[TextStCue] {
+        &self.cues
+    }
+
+    pub fn get_cue(&self, index: usize) -> Option<&TextStCue> {
+        self.cues.get(index)
+    }
+
+    /// Get all cue start timestamps in milliseconds.
+    pub fn get_timestamps(&self) -> Vec<f64> {
+        self.timestamps_ms.iter().map(|&ts| ts as f64).collect()
+    }
+
+    /// Get all cue end timestamps in milliseconds.
+    pub fn get_end_timestamps(&self) -> Vec<f64> {
+        self.cues.iter().map(|cue| cue.end_ms as f64).collect()
+    }
+
+    /// Find the cue index visible at a given timestamp in milliseconds.
+    pub fn find_index_at_timestamp(&self, time_ms: f64) -> i32 {
+        if self.timestamps_ms.is_empty() {
+            return -1;
+        }
+
+        let time_ms_u32 = time_ms as u32;
+        let index = binary_search_timestamp(&self.timestamps_ms, time_ms_u32);
+        let cue = &self.cues[index];
+        if time_ms_u32 < cue.start_ms || time_ms_u32 >= cue.end_ms {
+            return -1;
+        }
+
+        index as i32
+    }
+
+    /// Get the plain text of a cue, with line breaks as `\n`.
+    pub fn get_cue_text(&self, index: usize) -> Option<String> {
+        self.cues.get(index).map(TextStCue::text)
+    }
+
+    /// Check whether a cue has a forced-on region.
+    pub fn is_cue_forced(&self, index: usize) -> bool {
+        self.cues.get(index).is_some_and(TextStCue::is_forced)
+    }
+
+    /// Find the cues whose text contains `query` (case-insensitive).
+    pub fn search(&self, query: &str) -> Vec<u32> {
+        let query = query.to_lowercase();
+        self.cues
+            .iter()
+            .enumerate()
+            .filter(|(_, cue)| cue.text().to_lowercase().contains(&query))
+            .map(|(index, _)| index as u32)
+            .collect()
+    }
+
+    /// Get the palette in effect for a cue (256 RGBA entries).
+    pub fn get_cue_palette(&self, index: usize) -> Option<&[u32]> {
+        let cue = self.cues.get(index)?;
+        cue.palette_update
+            .as_deref()
+            .or_else(|| self.style.as_ref().map(|style| style.palette.as_slice()))
+    }
+
+    /// Get the region style referenced by a dialog region.
+    pub fn region_style(&self, region: &TextStDialogRegion) -> Option<&TextStRegionStyle> {
+        self.style
+            .as_ref()?
+            .find_region_style(region.region_style_id)
+    }
+
+    /// Get the styled text runs of one region of a cue.
+    pub fn get_region_runs(&self, index: usize, region_index: usize) -> Option<Vec<TextStRun>> {
+        let region = self.cues.get(index)?.regions.get(region_index)?;
+        let style = self.region_style(region)?;
+        Some(region.runs(style))
+    }
+}
+
+impl Default for TextStParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::rgb_to_ycbcr;
+
+    fn segment(segment_type: u8, body: &[u8]) -> Vec<u8> {
+        let mut out = vec![segment_type];
+        out.extend_from_slice(&(body.len() as u16).to_be_bytes());
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn palette(entries: &[(u8, [u8; 3])]) -> Vec<u8> {
+        let mut out = ((entries.len() * 5) as u16).to_be_bytes().to_vec();
+        for &(id, [r, g, b]) in entries {
+            let (y, cb, cr) = rgb_to_ycbcr(r, g, b);
+            out.extend_from_slice(&[id, y, cr, cb, 0xFF]);
+        }
+        out
+    }
+
+    fn style_segment() -> Vec<u8> {
+        let mut body = vec![0x00, 0x00, 1, 0];
+        body.push(3); // region style id
+        body.extend_from_slice(&[0, 0, 3, 0x20, 7, 0x80, 0, 200]); // region
+        body.extend_from_slice(&[0, 0]); // background color, reserved
+        body.extend_from_slice(&[0, 10, 0, 10, 6, 0x20, 0, 180]); // text box
+        body.extend_from_slice(&[1, 2, 3, 8, 0, 0x01, 48, 1, 2, 1]);
+        body.extend(palette(&[(1, [255, 255, 255]), (2, [0, 0, 0])]));
+        body.extend_from_slice(&2u16.to_be_bytes());
+        segment(SEGMENT_DIALOG_STYLE, &body)
+    }
+
+    fn dialog_segment(start_pts: u64, end_pts: u64, text: &str, forced: bool) -> Vec<u8> {
+        let mut body = Vec::new();
+        for pts in [start_pts, end_pts] {
+            body.push((pts >> 32) as u8 & 0x01);
+            body.extend_from_slice(&(pts as u32).to_be_bytes());
+        }
+        body.push(0x00); // no palette update
+        body.push(1);
+        body.push(if forced { 0x40 } else { 0x00 });
+        body.push(3);
+        let mut data = vec![0x1B, 0x01, text.len() as u8];
+        data.extend_from_slice(text.as_bytes());
+        body.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        body.extend(data);
+        segment(SEGMENT_DIALOG_PRESENTATION, &body)
+    }
+
+    #[test]
+    fn parses_dialogs_with_styles() {
+        let mut stream = style_segment();
+        stream.extend(dialog_segment(180_000, 360_000, "Second", true));
+        stream.extend(dialog_segment(90_000, 135_000, "First line", false));
+
+        let mut parser = TextStParser::new();
+        assert_eq!(parser.parse(&stream), 2);
+        assert_eq!(parser.get_timestamps(), vec![1000.0, 2000.0]);
+        assert_eq!(parser.get_end_timestamps(), vec![1500.0, 4000.0]);
+        assert_eq!(parser.find_index_at_timestamp(1499.0), 0);
+        assert_eq!(parser.find_index_at_timestamp(1500.0), -1);
+        assert_eq!(parser.get_cue_text(0).as_deref(), Some("First line"));
+        assert!(parser.is_cue_forced(1));
+        assert_eq!(parser.search("SECOND"), vec![1]);
+
+        let style = parser.dialog_style().expect("style");
+        let region_style = &style.region_styles[0];
+        assert_eq!(region_style.region.width, 1920);
+        assert_eq!(region_style.text_halign, 2);
+        assert!(region_style.font_style.is_bold());
+
+        let runs = parser.get_region_runs(1, 0).expect("runs");
+        assert_eq!(runs[0].text, "Second");
+        assert_eq!(runs[0].font_size, 48);
+        let palette = parser.get_cue_palette(1).expect("palette");
+        assert_eq!(palette[runs[0].font_color as usize].to_le_bytes()[3], 0xFF);
+        assert_eq!(palette[0], 0);
+    }
+}
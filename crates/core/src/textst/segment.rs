@@ -0,0 +1,508 @@
+//! TextST dialog style and dialog presentation segment parsing.
+
+use crate::utils::{BigEndianReader, ycbcr_to_rgba};
+
+/// Dialog Style Segment (0x81).
+pub const SEGMENT_DIALOG_STYLE: u8 = 0x81;
+/// Dialog Presentation Segment (0x82).
+pub const SEGMENT_DIALOG_PRESENTATION: u8 = 0x82;
+
+/// Inline style marker that precedes every dialog data item.
+const INLINE_ESCAPE: u8 = 0x1B;
+
+const DATA_TEXT_STRING: u8 = 0x01;
+const DATA_CHANGE_FONT_SET: u8 = 0x02;
+const DATA_CHANGE_FONT_STYLE: u8 = 0x03;
+const DATA_CHANGE_FONT_SIZE: u8 = 0x04;
+const DATA_CHANGE_FONT_COLOR: u8 = 0x05;
+const DATA_LINE_BREAK: u8 = 0x0A;
+const DATA_END_OF_INLINE_STYLE: u8 = 0x0B;
+
+/// A rectangle in screen coordinates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextStRect {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl TextStRect {
+    fn parse(reader: &mut BigEndianReader) -> Option<Self> {
+        Some(Self {
+            x: reader.read_u16()?,
+            y: reader.read_u16()?,
+            width: reader.read_u16()?,
+            height: reader.read_u16()?,
+        })
+    }
+}
+
+/// Font style flags (bit 0 = bold, bit 1 = italic, bit 2 = outline border).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextStFontStyle(pub u8);
+
+impl TextStFontStyle {
+    #[inline]
+    pub fn is_bold(self) -> bool {
+        self.0 & 0x01 != 0
+    }
+
+    #[inline]
+    pub fn is_italic(self) -> bool {
+        self.0 & 0x02 != 0
+    }
+
+    #[inline]
+    pub fn has_outline_border(self) -> bool {
+        self.0 & 0x04 != 0
+    }
+}
+
+/// Region style from the dialog style segment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextStRegionStyle {
+    /// Region style ID referenced by dialog regions
+    pub id: u8,
+    /// Region position and size
+    pub region: TextStRect,
+    /// Palette entry of the region background
+    pub background_color: u8,
+    /// Text box position and size (relative to the region)
+    pub text_box: TextStRect,
+    /// Text flow (1 = left-to-right, 2 = right-to-left, 3 = top-to-bottom)
+    pub text_flow: u8,
+    /// Horizontal alignment (1 = left, 2 = center, 3 = right)
+    pub text_halign: u8,
+    /// Vertical alignment (1 = top, 2 = middle, 3 = bottom)
+    pub text_valign: u8,
+    /// Line spacing in pixels
+    pub line_space: u8,
+    /// Font ID (index into the clip's font list)
+    pub font_id: u8,
+    pub font_style: TextStFontStyle,
+    /// Font size in pixels
+    pub font_size: u8,
+    /// Palette entry of the text
+    pub font_color: u8,
+    /// Palette entry of the outline
+    pub outline_color: u8,
+    /// Outline thickness (1 = thin, 2 = medium, 3 = thick)
+    pub outline_thickness: u8,
+}
+
+impl TextStRegionStyle {
+    fn parse(reader: &mut BigEndianReader) -> Option<Self> {
+        let id = reader.read_u8()?;
+        let region = TextStRect::parse(reader)?;
+        let background_color = reader.read_u8()?;
+        reader.read_u8()?; // reserved
+        Some(Self {
+            id,
+            region,
+            background_color,
+            text_box: TextStRect::parse(reader)?,
+            text_flow: reader.read_u8()?,
+            text_halign: reader.read_u8()?,
+            text_valign: reader.read_u8()?,
+            line_space: reader.read_u8()?,
+            font_id: reader.read_u8()?,
+            font_style: TextStFontStyle(reader.read_u8()?),
+            font_size: reader.read_u8()?,
+            font_color: reader.read_u8()?,
+            outline_color: reader.read_u8()?,
+            outline_thickness: reader.read_u8()?,
+        })
+    }
+}
+
+/// User-selectable style: signed adjustments to a region style.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextStUserStyle {
+    pub id: u8,
+    pub region_x_delta: i16,
+    pub region_y_delta: i16,
+    pub text_box_x_delta: i16,
+    pub text_box_y_delta: i16,
+    pub text_box_width_delta: i16,
+    pub text_box_height_delta: i16,
+    pub font_size_delta: i8,
+    pub line_space_delta: i8,
+}
+
+impl TextStUserStyle {
+    fn parse(reader: &mut BigEndianReader) -> Option<Self> {
+        Some(Self {
+            id: reader.read_u8()?,
+            region_x_delta: read_signed_u16(reader)?,
+            region_y_delta: read_signed_u16(reader)?,
+            text_box_x_delta: read_signed_u16(reader)?,
+            text_box_y_delta: read_signed_u16(reader)?,
+            text_box_width_delta: read_signed_u16(reader)?,
+            text_box_height_delta: read_signed_u16(reader)?,
+            font_size_delta: read_signed_u8(reader)?,
+            line_space_delta: read_signed_u8(reader)?,
+        })
+    }
+}
+
+/// Dialog Style Segment: region styles, user styles and the palette shared
+/// by every dialog in the stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialogStyleSegment {
+    /// Whether the player may substitute its own style
+    pub player_style_flag: bool,
+    pub region_styles: Vec<TextStRegionStyle>,
+    pub user_styles: Vec<TextStUserStyle>,
+    /// RGBA colors indexed by palette entry ID (256 entries)
+    pub palette: Vec<u32>,
+    /// Number of dialog presentation segments that follow
+    pub dialog_count: u16,
+}
+
+impl DialogStyleSegment {
+    /// Parse a dialog style segment body.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let mut reader = BigEndianReader::new(data);
+        let player_style_flag = reader.read_u8()? & 0x80 != 0;
+        reader.read_u8()?; // reserved
+        let region_style_count = reader.read_u8()?;
+        let user_style_count = reader.read_u8()?;
+
+        let region_styles = (0..region_style_count)
+            .map(|_| TextStRegionStyle::parse(&mut reader))
+            .collect::<Option<Vec<_>>>()?;
+        let user_styles = (0..user_style_count)
+            .map(|_| TextStUserStyle::parse(&mut reader))
+            .collect::<Option<Vec<_>>>()?;
+        let mut palette = vec![0u32; 256];
+        parse_palette(&mut reader, &mut palette)?;
+        let dialog_count = reader.read_u16()?;
+
+        Some(Self {
+            player_style_flag,
+            region_styles,
+            user_styles,
+            palette,
+            dialog_count,
+        })
+    }
+
+    /// Find a region style by ID.
+    pub fn find_region_style(&self, id: u8) -> Option<&TextStRegionStyle> {
+        self.region_styles.iter().find(|style| style.id == id)
+    }
+}
+
+/// One item of dialog region data, in stream order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextStContent {
+    /// Text string (decoded as UTF-8)
+    Text(String),
+    /// Inline font change
+    FontId(u8),
+    /// Inline font style change
+    FontStyle {
+        style: TextStFontStyle,
+        outline_color: u8,
+        outline_thickness: u8,
+    },
+    /// Inline font size change
+    FontSize(u8),
+    /// Inline font color change (palette entry)
+    FontColor(u8),
+    LineBreak,
+    /// Revert inline changes to the region style
+    EndOfInlineStyle,
+}
+
+/// A dialog region with its text and inline style changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextStDialogRegion {
+    /// Region continues from the previous dialog without a redraw
+    pub continuous_present: bool,
+    /// Region is shown even when subtitles are turned off
+    pub forced_on: bool,
+    /// Region style ID in the dialog style segment
+    pub region_style_id: u8,
+    pub content: Vec<TextStContent>,
+}
+
+impl TextStDialogRegion {
+    fn parse(reader: &mut BigEndianReader) -> Option<Self> {
+        let flags = reader.read_u8()?;
+        let region_style_id = reader.read_u8()?;
+        let data_len = reader.read_u16()? as usize;
+        let data = reader.read_bytes(data_len)?;
+
+        Some(Self {
+            continuous_present: flags & 0x80 != 0,
+            forced_on: flags & 0x40 != 0,
+            region_style_id,
+            content: parse_region_content(&data),
+        })
+    }
+
+    /// Get the plain text of the region, with line breaks as `\n`.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for item in &self.content {
+            match item {
+                TextStContent::Text(value) => text.push_str(value),
+                TextStContent::LineBreak => text.push('\n'),
+                _ => {}
+            }
+        }
+        text
+    }
+
+    /// Resolve inline style changes against the region style into styled
+    /// text runs.
+    pub fn runs(&self, style: &TextStRegionStyle) -> Vec<TextStRun> {
+        let base = TextStRun::from_style(style);
+        let mut current = base.clone();
+        let mut runs = Vec::new();
+
+        for item in &self.content {
+            match item {
+                TextStContent::Text(value) => runs.push(TextStRun {
+                    text: value.clone(),
+                    ..current.clone()
+                }),
+                TextStContent::FontId(font_id) => current.font_id = *font_id,
+                TextStContent::FontStyle {
+                    style,
+                    outline_color,
+                    outline_thickness,
+                } => {
+                    current.font_style = *style;
+                    current.outline_color = *outline_color;
+                    current.outline_thickness = *outline_thickness;
+                }
+                TextStContent::FontSize(size) => current.font_size = *size,
+                TextStContent::FontColor(color) => current.font_color = *color,
+                TextStContent::LineBreak => current.line += 1,
+                TextStContent::EndOfInlineStyle => {
+                    current = TextStRun {
+                        line: current.line,
+                        ..base.clone()
+                    };
+                }
+            }
+        }
+
+        runs
+    }
+}
+
+/// A run of text with its effective style.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextStRun {
+    pub text: String,
+    /// Zero-based line index within the region
+    pub line: u32,
+    pub font_id: u8,
+    pub font_style: TextStFontStyle,
+    pub font_size: u8,
+    /// Palette entry of the text
+    pub font_color: u8,
+    /// Palette entry of the outline
+    pub outline_color: u8,
+    pub outline_thickness: u8,
+}
+
+impl TextStRun {
+    fn from_style(style: &TextStRegionStyle) -> Self {
+        Self {
+            text: String::new(),
+            line: 0,
+            font_id: style.font_id,
+            font_style: style.font_style,
+            font_size: style.font_size,
+            font_color: style.font_color,
+            outline_color: style.outline_color,
+            outline_thickness: style.outline_thickness,
+        }
+    }
+}
+
+/// Dialog Presentation Segment: one timed dialog with up to two regions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialogPresentationSegment {
+    /// Start presentation timestamp in 90kHz units (33 bits)
+    pub start_pts: u64,
+    /// End presentation timestamp in 90kHz units (33 bits)
+    pub end_pts: u64,
+    /// Replacement palette (256 RGBA entries) when the dialog updates it
+    pub palette_update: Option<Vec<u32>>,
+    pub regions: Vec<TextStDialogRegion>,
+}
+
+impl DialogPresentationSegment {
+    /// Parse a dialog presentation segment body.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let mut reader = BigEndianReader::new(data);
+        let start_pts = read_pts(&mut reader)?;
+        let end_pts = read_pts(&mut reader)?;
+
+        let palette_update = if reader.read_u8()? & 0x80 != 0 {
+            let mut palette = vec![0u32; 256];
+            parse_palette(&mut reader, &mut palette)?;
+            Some(palette)
+        } else {
+            None
+        };
+
+        let region_count = reader.read_u8()?;
+        let regions = (0..region_count)
+            .map(|_| TextStDialogRegion::parse(&mut reader))
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Self {
+            start_pts,
+            end_pts,
+            palette_update,
+            regions,
+        })
+    }
+}
+
+/// Parse a length-prefixed palette (ID, Y, Cr, Cb, T entries) into `rgba`.
+fn parse_palette(reader: &mut BigEndianReader, rgba: &mut [u32]) -> Option<()> {
+    let length = reader.read_u16()? as usize;
+    for _ in 0..length / 5 {
+        let entry_id = reader.read_u8()? as usize;
+        let y = reader.read_u8()?;
+        let cr = reader.read_u8()?;
+        let cb = reader.read_u8()?;
+        let a = reader.read_u8()?;
+        rgba[entry_id] = ycbcr_to_rgba(y, cb, cr, a);
+    }
+    reader.skip(length % 5);
+    Some(())
+}
+
+fn parse_region_content(data: &[u8]) -> Vec<TextStContent> {
+    let mut content = Vec::new();
+    let mut offset = 0;
+
+    while offset + 3 <= data.len() {
+        if data[offset] != INLINE_ESCAPE {
+            offset += 1;
+            continue;
+        }
+        let data_type = data[offset + 1];
+        let len = data[offset + 2] as usize;
+        let Some(value) = data.get(offset + 3..offset + 3 + len) else {
+            break;
+        };
+        offset += 3 + len;
+
+        let item = match data_type {
+            DATA_TEXT_STRING => TextStContent::Text(String::from_utf8_lossy(value).into_owned()),
+            DATA_CHANGE_FONT_SET if len >= 1 => TextStContent::FontId(value[0]),
+            DATA_CHANGE_FONT_STYLE if len >= 3 => TextStContent::FontStyle {
+                style: TextStFontStyle(value[0]),
+                outline_color: value[1],
+                outline_thickness: value[2],
+            },
+            DATA_CHANGE_FONT_SIZE if len >= 1 => TextStContent::FontSize(value[0]),
+            DATA_CHANGE_FONT_COLOR if len >= 1 => TextStContent::FontColor(value[0]),
+            DATA_LINE_BREAK => TextStContent::LineBreak,
+            DATA_END_OF_INLINE_STYLE => TextStContent::EndOfInlineStyle,
+            _ => continue,
+        };
+        content.push(item);
+    }
+
+    content
+}
+
+/// Read a 33-bit PTS stored in 5 bytes after 7 reserved bits.
+fn read_pts(reader: &mut BigEndianReader) -> Option<u64> {
+    let high = (reader.read_u8()? & 0x01) as u64;
+    Some((high << 32) | reader.read_u32()? as u64)
+}
+
+/// Read a sign bit followed by a 15-bit magnitude.
+fn read_signed_u16(reader: &mut BigEndianReader) -> Option<i16> {
+    let value = reader.read_u16()?;
+    let magnitude = (value & 0x7FFF) as i16;
+    Some(if value & 0x8000 != 0 {
+        -magnitude
+    } else {
+        magnitude
+    })
+}
+
+/// Read a sign bit followed by a 7-bit magnitude.
+fn read_signed_u8(reader: &mut BigEndianReader) -> Option<i8> {
+    let value = reader.read_u8()?;
+    let magnitude = (value & 0x7F) as i8;
+    Some(if value & 0x80 != 0 {
+        -magnitude
+    } else {
+        magnitude
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_inline_styles_and_line_breaks() {
+        let mut data = vec![INLINE_ESCAPE, DATA_TEXT_STRING, 5];
+        data.extend_from_slice(b"Hello");
+        data.extend_from_slice(&[INLINE_ESCAPE, DATA_CHANGE_FONT_STYLE, 3, 0x02, 4, 1]);
+        data.extend_from_slice(&[INLINE_ESCAPE, DATA_CHANGE_FONT_COLOR, 1, 7]);
+        data.extend_from_slice(&[INLINE_ESCAPE, DATA_TEXT_STRING, 6]);
+        data.extend_from_slice(" world".as_bytes());
+        data.extend_from_slice(&[INLINE_ESCAPE, DATA_END_OF_INLINE_STYLE, 0]);
+        data.extend_from_slice(&[INLINE_ESCAPE, DATA_LINE_BREAK, 0]);
+        data.extend_from_slice(&[INLINE_ESCAPE, DATA_TEXT_STRING, "été".len() as u8]);
+        data.extend_from_slice("été".as_bytes());
+
+        let region = TextStDialogRegion {
+            continuous_present: false,
+            forced_on: false,
+            region_style_id: 0,
+            content: parse_region_content(&data),
+        };
+        assert_eq!(region.text(), "Hello world\nété");
+
+        let style = TextStRegionStyle {
+            id: 0,
+            region: TextStRect::default(),
+            background_color: 0,
+            text_box: TextStRect::default(),
+            text_flow: 1,
+            text_halign: 2,
+            text_valign: 3,
+            line_space: 10,
+            font_id: 0,
+            font_style: TextStFontStyle(0),
+            font_size: 32,
+            font_color: 1,
+            outline_color: 2,
+            outline_thickness: 1,
+        };
+        let runs = region.runs(&style);
+        assert_eq!(runs.len(), 3);
+        assert_eq!((runs[0].font_color, runs[0].line), (1, 0));
+        assert!(runs[1].font_style.is_italic());
+        assert_eq!((runs[1].font_color, runs[1].outline_color), (7, 4));
+        assert_eq!(runs[2].text, "été");
+        assert_eq!((runs[2].font_color, runs[2].line), (1, 1));
+        assert!(!runs[2].font_style.is_italic());
+    }
+
+    #[test]
+    fn reads_signed_deltas() {
+        let data = [0x80, 0x05, 0x00, 0x03, 0x82];
+        let mut reader = BigEndianReader::new(&data);
+        assert_eq!(read_signed_u16(&mut reader), Some(-5));
+        assert_eq!(read_signed_u16(&mut reader), Some(3));
+        assert_eq!(read_signed_u8(&mut reader), Some(-2));
+    }
+}
@@ -0,0 +1,50 @@
+//! TextST extraction from Blu-ray M2TS / MPEG transport streams.
+
+use crate::ts::{demux_ts_pes, iter_ts_packets, parse_ts_programs};
+
+/// PMT stream type of HDMV text subtitles.
+pub const HDMV_TEXTST_STREAM_TYPE: u8 = 0x92;
+/// PID reserved for the TextST stream on Blu-ray.
+pub const TEXTST_PID: u16 = 0x1800;
+
+/// A TextST stream found in a transport stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextStTransportStream {
+    /// Packet identifier
+    pub pid: u16,
+    /// ISO 639 language code from the PMT, if present
+    pub language: Option<String>,
+}
+
+/// List the TextST streams declared in the PAT/PMT of a transport stream.
+///
+/// Falls back to the Blu-ray TextST PID when no PMT lists one.
+pub fn list_textst_streams(data: &[u8]) -> Vec<TextStTransportStream> {
+    let streams: Vec<TextStTransportStream> = parse_ts_programs(data)
+        .into_iter()
+        .filter(|stream| stream.stream_type == HDMV_TEXTST_STREAM_TYPE)
+        .map(|stream| TextStTransportStream {
+            pid: stream.pid,
+            language: stream.language(),
+        })
+        .collect();
+    if !streams.is_empty() {
+        return streams;
+    }
+
+    if iter_ts_packets(data).any(|packet| packet.pid == TEXTST_PID) {
+        return vec![TextStTransportStream {
+            pid: TEXTST_PID,
+            language: None,
+        }];
+    }
+    Vec::new()
+}
+
+/// Demux a TextST stream into its concatenated segments.
+pub fn extract_textst_segments(data: &[u8], pid: u16) -> Vec<u8> {
+    demux_ts_pes(data, pid)
+        .into_iter()
+        .flat_map(|pes| pes.payload)
+        .collect()
+}
@@ -0,0 +1,12 @@
+//! MPEG transport stream (TS / Blu-ray M2TS) demuxing.
+//!
+//! Provides packet iteration for 188-byte TS and 192-byte M2TS packets,
+//! PAT/PMT parsing, and PES reassembly shared by the PGS and DVB loaders.
+
+mod packet;
+mod pes;
+mod psi;
+
+pub use packet::*;
+pub use pes::*;
+pub use psi::*;
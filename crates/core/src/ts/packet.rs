@@ -0,0 +1,208 @@
+//! Transport stream packet framing.
+
+/// Size of a plain MPEG-TS packet.
+pub const TS_PACKET_SIZE: usize = 188;
+/// Size of a Blu-ray M2TS packet (4-byte TP_extra_header + TS packet).
+pub const M2TS_PACKET_SIZE: usize = 192;
+/// Sync byte at the start of every TS packet.
+pub const TS_SYNC_BYTE: u8 = 0x47;
+/// PID carrying the Program Association Table.
+pub const PAT_PID: u16 = 0x0000;
+/// PID used for null packets.
+pub const NULL_PID: u16 = 0x1FFF;
+
+/// Number of consecutive sync bytes required to lock onto a packet size.
+const SYNC_CONFIRMATIONS: usize = 3;
+
+/// A single parsed 188-byte transport stream packet.
+#[derive(Debug, Clone, Copy)]
+pub struct TsPacket<'a> {
+    /// Packet identifier
+    pub pid: u16,
+    /// Payload unit start indicator (a PES packet or PSI section starts here)
+    pub payload_unit_start: bool,
+    /// Transport error indicator
+    pub transport_error: bool,
+    /// 4-bit continuity counter
+    pub continuity_counter: u8,
+    /// Discontinuity indicator from the adaptation field
+    pub discontinuity: bool,
+    /// Payload bytes after the header and adaptation field
+    pub payload: &'a [u8],
+}
+
+impl<'a> TsPacket<'a> {
+    /// Parse a 188-byte TS packet.
+    pub fn parse(packet: &'a [u8]) -> Option<Self> {
+        if packet.len() < TS_PACKET_SIZE || packet[0] != TS_SYNC_BYTE {
+            return None;
+        }
+
+        let transport_error = (packet[1] & 0x80) != 0;
+        let payload_unit_start = (packet[1] & 0x40) != 0;
+        let pid = u16::from_be_bytes([packet[1] & 0x1F, packet[2]]);
+        let adaptation_control = (packet[3] >> 4) & 0x03;
+        let continuity_counter = packet[3] & 0x0F;
+
+        let mut offset = 4usize;
+        let mut discontinuity = false;
+        if adaptation_control & 0x02 != 0 {
+            let adaptation_len = packet[4] as usize;
+            if adaptation_len > 0 {
+                discontinuity = (packet[5] & 0x80) != 0;
+            }
+            offset += 1 + adaptation_len;
+            if offset > TS_PACKET_SIZE {
+                return None;
+            }
+        }
+
+        let payload = if adaptation_control & 0x01 != 0 {
+            &packet[offset..TS_PACKET_SIZE]
+        } else {
+            &[]
+        };
+
+        Some(Self {
+            pid,
+            payload_unit_start,
+            transport_error,
+            continuity_counter,
+            discontinuity,
+            payload,
+        })
+    }
+
+    /// Check whether the packet carries payload bytes.
+    #[inline]
+    pub fn has_payload(&self) -> bool {
+        !self.payload.is_empty()
+    }
+}
+
+/// Detect the packet size (188 or 192) and the offset of the first packet.
+pub fn detect_packet_size(data: &[u8]) -> Option<(usize, usize)> {
+    for (packet_size, sync_offset) in [(TS_PACKET_SIZE, 0), (M2TS_PACKET_SIZE, 4)] {
+        for start in 0..packet_size {
+            // Short streams are confirmed by the packets they contain
+            let first = start + sync_offset;
+            let confirmed = data.get(first) == Some(&TS_SYNC_BYTE)
+                && (1..SYNC_CONFIRMATIONS).all(|index| {
+                    data.get(first + index * packet_size)
+                        .is_none_or(|&byte| byte == TS_SYNC_BYTE)
+                });
+            if confirmed {
+                return Some((packet_size, start));
+            }
+        }
+    }
+    None
+}
+
+/// Check whether `data` looks like an MPEG-TS or M2TS stream.
+pub fn looks_like_transport_stream(data: &[u8]) -> bool {
+    detect_packet_size(data).is_some_and(|(_, start)| start == 0)
+}
+
+/// Iterate the TS packets in `data`, resynchronizing on lost sync.
+pub fn iter_ts_packets(data: &[u8]) -> TsPacketIter<'_> {
+    let (packet_size, offset) = detect_packet_size(data).unwrap_or((TS_PACKET_SIZE, data.len()));
+    TsPacketIter {
+        data,
+        packet_size,
+        offset,
+    }
+}
+
+/// Iterator over TS packets, created by [`iter_ts_packets`].
+pub struct TsPacketIter<'a> {
+    data: &'a [u8],
+    packet_size: usize,
+    offset: usize,
+}
+
+impl TsPacketIter<'_> {
+    /// Byte offset of the next packet (including any M2TS extra header).
+    pub fn position(&self) -> usize {
+        self.offset
+    }
+
+    /// Packet size in bytes (188 or 192).
+    pub fn packet_size(&self) -> usize {
+        self.packet_size
+    }
+}
+
+impl<'a> Iterator for TsPacketIter<'a> {
+    type Item = TsPacket<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let header_len = self.packet_size - TS_PACKET_SIZE;
+        while self.offset + self.packet_size <= self.data.len() {
+            let start = self.offset + header_len;
+            let packet = &self.data[start..start + TS_PACKET_SIZE];
+            if packet[0] == TS_SYNC_BYTE {
+                self.offset += self.packet_size;
+                if let Some(parsed) = TsPacket::parse(packet) {
+                    return Some(parsed);
+                }
+                continue;
+            }
+
+            // Lost sync: scan forward for the next sync byte
+            match memchr::memchr(TS_SYNC_BYTE, &self.data[start + 1..]) {
+                Some(pos) => self.offset = (start + 1 + pos).saturating_sub(header_len),
+                None => self.offset = self.data.len(),
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(pid: u16, payload: &[u8]) -> Vec<u8> {
+        let mut out = vec![TS_SYNC_BYTE, 0x40 | (pid >> 8) as u8, pid as u8, 0x10];
+        out.extend_from_slice(payload);
+        out.resize(TS_PACKET_SIZE, 0xFF);
+        out
+    }
+
+    #[test]
+    fn iterates_plain_and_m2ts_packets() {
+        let ts: Vec<u8> = (0..4)
+            .flat_map(|pid| packet(0x100 + pid, &[pid as u8]))
+            .collect();
+        let m2ts: Vec<u8> = (0..4)
+            .flat_map(|pid| {
+                let mut out = vec![0, 0, 0, 0];
+                out.extend(packet(0x1200 + pid, &[pid as u8]));
+                out
+            })
+            .collect();
+
+        assert_eq!(detect_packet_size(&ts), Some((TS_PACKET_SIZE, 0)));
+        assert_eq!(detect_packet_size(&m2ts), Some((M2TS_PACKET_SIZE, 0)));
+
+        let pids: Vec<u16> = iter_ts_packets(&m2ts).map(|packet| packet.pid).collect();
+        assert_eq!(pids, vec![0x1200, 0x1201, 0x1202, 0x1203]);
+        let first = iter_ts_packets(&ts).next().unwrap();
+        assert!(first.payload_unit_start);
+        assert_eq!(first.payload[0], 0);
+    }
+
+    #[test]
+    fn skips_adaptation_field() {
+        let mut raw = vec![TS_SYNC_BYTE, 0x01, 0x00, 0x35, 3, 0x80, 0, 0, 0xAB];
+        raw.resize(TS_PACKET_SIZE, 0);
+        let parsed = TsPacket::parse(&raw).unwrap();
+
+        assert_eq!(parsed.pid, 0x100);
+        assert_eq!(parsed.continuity_counter, 5);
+        assert!(parsed.discontinuity);
+        assert_eq!(parsed.payload[0], 0xAB);
+        assert_eq!(parsed.payload.len(), TS_PACKET_SIZE - 8);
+    }
+}
@@ -0,0 +1,290 @@
+//! PES packet reassembly from transport stream payloads.
+
+use super::{TsPacket, iter_ts_packets};
+
+/// Largest PES packet accepted when the header declares an unbounded length.
+const MAX_PES_PACKET_SIZE: usize = 4 * 1024 * 1024;
+
+/// A reassembled PES packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PesPacket {
+    /// PID the packet was carried on
+    pub pid: u16,
+    /// PES stream id (e.g. 0xBD for private stream 1)
+    pub stream_id: u8,
+    /// Presentation timestamp in 90 kHz units (33 bits)
+    pub pts: Option<u64>,
+    /// Decoding timestamp in 90 kHz units (33 bits)
+    pub dts: Option<u64>,
+    /// PES packet data bytes after the header
+    pub payload: Vec<u8>,
+}
+
+impl PesPacket {
+    /// Parse a complete PES packet starting with the `00 00 01` prefix.
+    pub fn parse(pid: u16, data: &[u8]) -> Option<Self> {
+        if data.len() < 9 || data[..3] != [0x00, 0x00, 0x01] {
+            return None;
+        }
+
+        let stream_id = data[3];
+        let packet_length = u16::from_be_bytes([data[4], data[5]]) as usize;
+        let packet_end = if packet_length == 0 {
+            data.len()
+        } else {
+            6 + packet_length
+        };
+        if packet_end > data.len() || packet_end < 9 {
+            return None;
+        }
+
+        // Optional PES header ('10' marker bits)
+        if data[6] & 0xC0 != 0x80 {
+            return None;
+        }
+        let pts_dts_flags = (data[7] >> 6) & 0x03;
+        let header_end = 9 + data[8] as usize;
+        if header_end > packet_end {
+            return None;
+        }
+
+        let header = &data[9..header_end];
+        let pts = if pts_dts_flags & 0x02 != 0 {
+            read_timestamp(header.get(0..5)?)
+        } else {
+            None
+        };
+        let dts = if pts_dts_flags == 0x03 {
+            read_timestamp(header.get(5..10)?)
+        } else {
+            None
+        };
+
+        Some(Self {
+            pid,
+            stream_id,
+            pts,
+            dts,
+            payload: data[header_end..packet_end].to_vec(),
+        })
+    }
+}
+
+/// Decode a 33-bit PTS/DTS field with marker bits.
+pub fn read_timestamp(data: &[u8]) -> Option<u64> {
+    if data.len() < 5 || data[0] & 1 == 0 || data[2] & 1 == 0 || data[4] & 1 == 0 {
+        return None;
+    }
+
+    Some(
+        ((data[0] as u64 & 0x0E) << 29)
+            | ((data[1] as u64) << 22)
+            | ((data[2] as u64 & 0xFE) << 14)
+            | ((data[3] as u64) << 7)
+            | ((data[4] as u64) >> 1),
+    )
+}
+
+/// Reassembles the PES packets carried on one PID.
+#[derive(Debug)]
+pub struct PesAssembler {
+    pid: u16,
+    buffer: Vec<u8>,
+    started: bool,
+    /// Continuity counter of the last payload packet
+    continuity_counter: Option<u8>,
+    continuity_errors: usize,
+}
+
+impl PesAssembler {
+    pub fn new(pid: u16) -> Self {
+        Self {
+            pid,
+            buffer: Vec::new(),
+            started: false,
+            continuity_counter: None,
+            continuity_errors: 0,
+        }
+    }
+
+    /// Push a TS packet of this PID, checking its continuity counter first.
+    /// A gap not flagged as a discontinuity drops the partially assembled
+    /// packet; a repeated packet is ignored.
+    pub fn push_packet(&mut self, packet: &TsPacket) -> Option<PesPacket> {
+        if !packet.has_payload() {
+            return None;
+        }
+        if let Some(last) = self.continuity_counter
+            && !packet.discontinuity
+        {
+            if packet.continuity_counter == last {
+                return None;
+            }
+            if packet.continuity_counter != (last + 1) & 0x0F {
+                self.continuity_errors += 1;
+                self.reset();
+            }
+        }
+        self.continuity_counter = Some(packet.continuity_counter);
+        self.push(packet.payload, packet.payload_unit_start)
+    }
+
+    /// Number of continuity counter gaps seen by [`Self::push_packet`].
+    pub fn continuity_errors(&self) -> usize {
+        self.continuity_errors
+    }
+
+    /// Push a TS packet payload for this PID. Returns the PES packet that was
+    /// completed, either by reaching its declared length or by the start of
+    /// the next one.
+    pub fn push(&mut self, payload: &[u8], payload_unit_start: bool) -> Option<PesPacket> {
+        let mut completed = None;
+        if payload_unit_start {
+            completed = self.flush();
+            self.started = true;
+        } else if !self.started {
+            return None;
+        }
+
+        if self.buffer.len() + payload.len() > MAX_PES_PACKET_SIZE {
+            self.buffer.clear();
+            self.started = false;
+            return completed;
+        }
+        self.buffer.extend_from_slice(payload);
+
+        if completed.is_none() && self.is_complete() {
+            completed = self.flush();
+        }
+        completed
+    }
+
+    /// Return any buffered PES packet (e.g. at end of stream).
+    pub fn flush(&mut self) -> Option<PesPacket> {
+        if !self.started {
+            return None;
+        }
+        self.started = false;
+        let packet = PesPacket::parse(self.pid, &self.buffer);
+        self.buffer.clear();
+        packet
+    }
+
+    /// Drop any partially assembled packet.
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.started = false;
+    }
+
+    fn is_complete(&self) -> bool {
+        if self.buffer.len() < 6 {
+            return false;
+        }
+        let packet_length = u16::from_be_bytes([self.buffer[4], self.buffer[5]]) as usize;
+        packet_length != 0 && self.buffer.len() >= 6 + packet_length
+    }
+}
+
+/// Reassemble every PES packet carried on `pid`.
+pub fn demux_ts_pes(data: &[u8], pid: u16) -> Vec<PesPacket> {
+    let mut assembler = PesAssembler::new(pid);
+    let mut packets = Vec::new();
+
+    for packet in iter_ts_packets(data) {
+        if packet.pid != pid || packet.transport_error {
+            continue;
+        }
+        if let Some(pes) = assembler.push_packet(&packet) {
+            packets.push(pes);
+        }
+    }
+
+    if let Some(pes) = assembler.flush() {
+        packets.push(pes);
+    }
+
+    packets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ts::{TS_PACKET_SIZE, TS_SYNC_BYTE};
+
+    fn encode_timestamp(prefix: u8, ts: u64) -> [u8; 5] {
+        [
+            (prefix << 4) | (((ts >> 30) as u8 & 0x07) << 1) | 1,
+            (ts >> 22) as u8,
+            (((ts >> 15) as u8 & 0x7F) << 1) | 1,
+            (ts >> 7) as u8,
+            ((ts as u8 & 0x7F) << 1) | 1,
+        ]
+    }
+
+    #[test]
+    fn drops_packets_broken_by_continuity_gaps() {
+        let packet = |start: bool, counter: u8, body: &[u8]| {
+            let mut raw = vec![
+                TS_SYNC_BYTE,
+                if start { 0x52 } else { 0x12 },
+                0x00,
+                0x10 | counter,
+            ];
+            raw.extend_from_slice(body);
+            raw.resize(TS_PACKET_SIZE, 0xFF);
+            raw
+        };
+        // Unbounded PES (length 0) split over two packets
+        let header = [0x00, 0x00, 0x01, 0xBD, 0x00, 0x00, 0x80, 0x00, 0x00];
+
+        let mut assembler = PesAssembler::new(0x1200);
+        let packets = [
+            packet(true, 0, &header),
+            packet(false, 1, &[1]),
+            packet(false, 1, &[1]), // repeated
+            packet(true, 2, &header),
+            packet(false, 4, &[2]), // counter 3 lost
+            packet(true, 5, &header),
+        ];
+        let mut completed = Vec::new();
+        for raw in &packets {
+            let parsed = TsPacket::parse(raw).expect("packet");
+            completed.extend(assembler.push_packet(&parsed));
+        }
+        completed.extend(assembler.flush());
+
+        assert_eq!(assembler.continuity_errors(), 1);
+        assert_eq!(completed.len(), 2);
+        assert_eq!(
+            completed[0].payload.len(),
+            2 * (TS_PACKET_SIZE - 4) - header.len()
+        );
+    }
+
+    #[test]
+    fn reassembles_pes_across_packets_with_pts_and_dts() {
+        let body: Vec<u8> = (0..300u32).map(|value| value as u8).collect();
+        let mut pes = vec![0x00, 0x00, 0x01, 0xBD];
+        pes.extend_from_slice(&((3 + 10 + body.len()) as u16).to_be_bytes());
+        pes.extend_from_slice(&[0x81, 0xC0, 0x0A]);
+        pes.extend_from_slice(&encode_timestamp(0x3, 900_000));
+        pes.extend_from_slice(&encode_timestamp(0x1, 890_000));
+        pes.extend_from_slice(&body);
+
+        let mut stream = Vec::new();
+        for (index, chunk) in pes.chunks(TS_PACKET_SIZE - 4).enumerate() {
+            let start = if index == 0 { 0x40 } else { 0x00 };
+            let mut packet = vec![TS_SYNC_BYTE, start | 0x12, 0x00, 0x10 | index as u8];
+            packet.extend_from_slice(chunk);
+            packet.resize(TS_PACKET_SIZE, 0xFF);
+            stream.extend_from_slice(&packet);
+        }
+
+        let packets = demux_ts_pes(&stream, 0x1200);
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].stream_id, 0xBD);
+        assert_eq!(packets[0].pts, Some(900_000));
+        assert_eq!(packets[0].dts, Some(890_000));
+        assert_eq!(packets[0].payload, body);
+    }
+}
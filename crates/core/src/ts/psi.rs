@@ -0,0 +1,282 @@
+//! Program Specific Information (PAT / PMT) parsing.
+
+use std::collections::HashMap;
+
+use super::{PAT_PID, iter_ts_packets};
+
+/// Table id of the Program Association Table.
+pub const TABLE_ID_PAT: u8 = 0x00;
+/// Table id of the Program Map Table.
+pub const TABLE_ID_PMT: u8 = 0x02;
+/// ISO 639 language descriptor tag.
+pub const DESCRIPTOR_ISO_639_LANGUAGE: u8 = 0x0A;
+
+/// A descriptor from a PMT loop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Descriptor {
+    /// Descriptor tag
+    pub tag: u8,
+    /// Descriptor payload (without tag and length)
+    pub data: Vec<u8>,
+}
+
+/// An elementary stream listed in a PMT.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElementaryStream {
+    /// Program number of the PMT that lists this stream
+    pub program_number: u16,
+    /// Stream type (e.g. 0x06 private PES, 0x90 HDMV PGS)
+    pub stream_type: u8,
+    /// Packet identifier carrying the stream
+    pub pid: u16,
+    /// ES_info descriptors
+    pub descriptors: Vec<Descriptor>,
+}
+
+impl ElementaryStream {
+    /// Find the first descriptor with the given tag.
+    pub fn find_descriptor(&self, tag: u8) -> Option<&Descriptor> {
+        self.descriptors
+            .iter()
+            .find(|descriptor| descriptor.tag == tag)
+    }
+
+    /// Get the ISO 639 language code from the language descriptor, if present.
+    pub fn language(&self) -> Option<String> {
+        let descriptor = self.find_descriptor(DESCRIPTOR_ISO_639_LANGUAGE)?;
+        read_language_code(&descriptor.data)
+    }
+}
+
+/// Read a three-letter ISO 639 language code.
+pub fn read_language_code(data: &[u8]) -> Option<String> {
+    let code = data.get(..3)?;
+    if !code.iter().all(u8::is_ascii_alphabetic) {
+        return None;
+    }
+    Some(String::from_utf8_lossy(code).to_ascii_lowercase())
+}
+
+/// Program association entry from the PAT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgramAssociation {
+    pub program_number: u16,
+    pub pmt_pid: u16,
+}
+
+/// Parse a complete PAT section.
+pub fn parse_pat(section: &[u8]) -> Option<Vec<ProgramAssociation>> {
+    let body = section_body(section, TABLE_ID_PAT)?;
+    Some(
+        body.chunks_exact(4)
+            .filter_map(|entry| {
+                let program_number = u16::from_be_bytes([entry[0], entry[1]]);
+                let pmt_pid = u16::from_be_bytes([entry[2] & 0x1F, entry[3]]);
+                // Program 0 points at the network information table
+                (program_number != 0).then_some(ProgramAssociation {
+                    program_number,
+                    pmt_pid,
+                })
+            })
+            .collect(),
+    )
+}
+
+/// Parse a complete PMT section into its elementary streams.
+pub fn parse_pmt(section: &[u8]) -> Option<Vec<ElementaryStream>> {
+    let body = section_body(section, TABLE_ID_PMT)?;
+    let program_number = u16::from_be_bytes([section[3], section[4]]);
+    if body.len() < 4 {
+        return None;
+    }
+
+    let program_info_len = (u16::from_be_bytes([body[2], body[3]]) & 0x0FFF) as usize;
+    let mut offset = 4 + program_info_len;
+    let mut streams = Vec::new();
+
+    while offset + 5 <= body.len() {
+        let stream_type = body[offset];
+        let pid = u16::from_be_bytes([body[offset + 1] & 0x1F, body[offset + 2]]);
+        let es_info_len =
+            (u16::from_be_bytes([body[offset + 3], body[offset + 4]]) & 0x0FFF) as usize;
+        let info_start = offset + 5;
+        let info_end = info_start.checked_add(es_info_len)?;
+        if info_end > body.len() {
+            return None;
+        }
+
+        streams.push(ElementaryStream {
+            program_number,
+            stream_type,
+            pid,
+            descriptors: parse_descriptors(&body[info_start..info_end]),
+        });
+        offset = info_end;
+    }
+
+    Some(streams)
+}
+
+/// Parse a descriptor loop.
+pub fn parse_descriptors(mut data: &[u8]) -> Vec<Descriptor> {
+    let mut descriptors = Vec::new();
+    while data.len() >= 2 {
+        let tag = data[0];
+        let len = data[1] as usize;
+        let Some(payload) = data.get(2..2 + len) else {
+            break;
+        };
+        descriptors.push(Descriptor {
+            tag,
+            data: payload.to_vec(),
+        });
+        data = &data[2 + len..];
+    }
+    descriptors
+}
+
+/// Validate a long-form PSI section and return the bytes between the
+/// section header and the CRC.
+fn section_body(section: &[u8], table_id: u8) -> Option<&[u8]> {
+    if section.len() < 12 || section[0] != table_id {
+        return None;
+    }
+    let section_len = (u16::from_be_bytes([section[1], section[2]]) & 0x0FFF) as usize;
+    let end = 3 + section_len;
+    if section_len < 9 || section.len() < end {
+        return None;
+    }
+    // Skip table id extension, version, section numbers; drop the CRC_32
+    Some(&section[8..end - 4])
+}
+
+/// Reassembles PSI sections that span several TS packets.
+#[derive(Debug, Default)]
+pub struct SectionAssembler {
+    buffer: Vec<u8>,
+    started: bool,
+}
+
+impl SectionAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Push a packet payload and return the section completed by it, if any.
+    pub fn push(&mut self, payload: &[u8], payload_unit_start: bool) -> Option<Vec<u8>> {
+        if payload_unit_start {
+            let pointer = *payload.first()? as usize;
+            let data = payload.get(1 + pointer..)?;
+            self.buffer.clear();
+            self.buffer.extend_from_slice(data);
+            self.started = true;
+        } else if self.started {
+            self.buffer.extend_from_slice(payload);
+        } else {
+            return None;
+        }
+
+        if self.buffer.len() < 3 {
+            return None;
+        }
+        let section_len = (u16::from_be_bytes([self.buffer[1], self.buffer[2]]) & 0x0FFF) as usize;
+        let total = 3 + section_len;
+        if self.buffer.len() < total {
+            return None;
+        }
+
+        self.started = false;
+        let section = self.buffer[..total].to_vec();
+        self.buffer.clear();
+        Some(section)
+    }
+}
+
+/// Scan a transport stream for its PAT and PMTs and list every elementary
+/// stream. Each PMT is read once; later versions are ignored.
+pub fn parse_ts_programs(data: &[u8]) -> Vec<ElementaryStream> {
+    let mut pat = SectionAssembler::new();
+    let mut pmt_assemblers: HashMap<u16, SectionAssembler> = HashMap::new();
+    let mut pmt_order = Vec::new();
+    let mut parsed: HashMap<u16, Vec<ElementaryStream>> = HashMap::new();
+
+    for packet in iter_ts_packets(data) {
+        if packet.transport_error || !packet.has_payload() {
+            continue;
+        }
+
+        if packet.pid == PAT_PID {
+            if pmt_order.is_empty()
+                && let Some(programs) = pat
+                    .push(packet.payload, packet.payload_unit_start)
+                    .and_then(|section| parse_pat(&section))
+            {
+                for program in programs {
+                    pmt_assemblers.insert(program.pmt_pid, SectionAssembler::new());
+                    pmt_order.push(program.pmt_pid);
+                }
+            }
+            continue;
+        }
+
+        if parsed.contains_key(&packet.pid) {
+            continue;
+        }
+        let Some(assembler) = pmt_assemblers.get_mut(&packet.pid) else {
+            continue;
+        };
+        if let Some(streams) = assembler
+            .push(packet.payload, packet.payload_unit_start)
+            .and_then(|section| parse_pmt(&section))
+        {
+            parsed.insert(packet.pid, streams);
+            if parsed.len() == pmt_order.len() {
+                break;
+            }
+        }
+    }
+
+    pmt_order
+        .iter()
+        .filter_map(|pid| parsed.remove(pid))
+        .flatten()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_pmt_streams_and_language_descriptor() {
+        let mut section = vec![TABLE_ID_PMT, 0xB0, 0x00, 0x00, 0x01, 0xC1, 0x00, 0x00];
+        section.extend_from_slice(&[0xE1, 0x00, 0xF0, 0x00]);
+        section.extend_from_slice(&[0x1B, 0xE1, 0x11, 0xF0, 0x00]);
+        section.extend_from_slice(&[0x90, 0xF2, 0x00, 0xF0, 0x06]);
+        section.extend_from_slice(&[DESCRIPTOR_ISO_639_LANGUAGE, 0x04, b'F', b'R', b'A', 0x00]);
+        section.extend_from_slice(&[0, 0, 0, 0]);
+        let section_len = section.len() - 3;
+        section[1] |= (section_len >> 8) as u8;
+        section[2] = section_len as u8;
+
+        let streams = parse_pmt(&section).expect("pmt");
+        assert_eq!(streams.len(), 2);
+        assert_eq!(streams[0].stream_type, 0x1B);
+        assert_eq!(streams[0].language(), None);
+        assert_eq!(streams[1].pid, 0x1200);
+        assert_eq!(streams[1].program_number, 1);
+        assert_eq!(streams[1].language().as_deref(), Some("fra"));
+    }
+
+    #[test]
+    fn assembler_joins_sections_across_packets() {
+        let mut assembler = SectionAssembler::new();
+        assert!(
+            assembler
+                .push(&[0x00, TABLE_ID_PAT, 0xB0, 0x05, 1], true)
+                .is_none()
+        );
+        let section = assembler.push(&[2, 3, 4, 5, 0xFF], false).expect("section");
+        assert_eq!(section, vec![TABLE_ID_PAT, 0xB0, 0x05, 1, 2, 3, 4, 5]);
+    }
+}
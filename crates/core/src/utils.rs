@@ -88,6 +88,25 @@ pub fn ycbcr_to_rgba(y: u8, cb: u8, cr: u8, a: u8) -> u32 {
     u32::from_le_bytes([r, g, b, a])
 }
 
+/// Convert RGB to YCbCr, the inverse of [`ycbcr_to_rgba`].
+/// Returns `(y, cb, cr)`.
+#[inline]
+pub fn rgb_to_ycbcr(r: u8, g: u8, b: u8) -> (u8, u8, u8) {
+    let r = r as f32;
+    let g = g as f32;
+    let b = b as f32;
+
+    let y = 0.299 * r + 0.587 * g + 0.114 * b;
+    let cb = 128.0 + (b - y) / 1.77200;
+    let cr = 128.0 + (r - y) / 1.40200;
+
+    (
+        clamp(y.round() as i32, 0, 255) as u8,
+        clamp(cb.round() as i32, 0, 255) as u8,
+        clamp(cr.round() as i32, 0, 255) as u8,
+    )
+}
+
 /// Convert RGB to packed RGBA u32.
 #[inline]
 pub fn rgb_to_rgba(r: u8, g: u8, b: u8, a: u8) -> u32 {
@@ -151,4 +170,15 @@ mod tests {
         assert_eq!(bytes[2], 255); // B
         assert_eq!(bytes[3], 255); // A
     }
+
+    #[test]
+    fn test_rgb_to_ycbcr_roundtrip() {
+        for (r, g, b) in [(255, 255, 255), (0, 0, 0), (200, 40, 90), (16, 180, 240)] {
+            let (y, cb, cr) = rgb_to_ycbcr(r, g, b);
+            let bytes = ycbcr_to_rgba(y, cb, cr, 255).to_le_bytes();
+            for (actual, expected) in bytes[..3].iter().zip([r, g, b]) {
+                assert!(actual.abs_diff(expected) <= 2, "{bytes:?} vs {r},{g},{b}");
+            }
+        }
+    }
 }
@@ -0,0 +1,768 @@
+//! DVD VIDEO_TS reader.
+//!
+//! Reads the subpicture palette and stream attributes from a title set
+//! information file (`VTS_xx_0.IFO`) and demuxes the subpicture packs from its
+//! VOB files (`VTS_xx_1.VOB`, `VTS_xx_2.VOB`, ...). The demuxed packs have the
+//! same layout as a VobSub `.sub` file.
+//!
+//! A title set holds one or more program chains (PGCs), each playing a list
+//! of cells of the VOBs. Subtitles are read for one PGC at a time: its cells
+//! select the packs and turn their presentation timestamps, which restart
+//! freely between cells, into title time.
+
+use super::mks_parser::encode_pts;
+use super::{VobSubPalette, is_vobsub_stream_id};
+use crate::utils::{BigEndianReader, ycbcr_to_rgba};
+
+/// DVD sector size; VOB files are a sequence of packs of this size.
+pub const DVD_SECTOR_SIZE: usize = 2048;
+
+const VTS_IDENTIFIER: &[u8] = b"DVDVIDEO-VTS";
+const VTS_PGCIT_SECTOR_OFFSET: usize = 0xCC;
+const VTS_VIDEO_ATTRIBUTES_OFFSET: usize = 0x200;
+const VTS_SUBPICTURE_COUNT_OFFSET: usize = 0x254;
+const VTS_SUBPICTURE_ATTRIBUTES_OFFSET: usize = 0x256;
+const MAX_SUBPICTURE_STREAMS: usize = 32;
+const PGC_SUBPICTURE_CONTROL_OFFSET: usize = 0x1C;
+const PGC_CELL_COUNT_OFFSET: usize = 0x03;
+const PGC_PALETTE_OFFSET: usize = 0xA4;
+const PGC_CELL_PLAYBACK_OFFSET: usize = 0xE8;
+const PGC_CELL_POSITION_OFFSET: usize = 0xEA;
+const CELL_PLAYBACK_ENTRY_SIZE: usize = 24;
+const CELL_POSITION_ENTRY_SIZE: usize = 4;
+/// Offset of `VOBU_S_PTM` in the PCI packet of a navigation pack
+const PCI_VOBU_START_PTM_OFFSET: usize = 12;
+
+/// A subpicture stream declared in the title set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DvdSubpictureStream {
+    /// Logical stream number (position in the IFO attribute table)
+    pub index: usize,
+    /// ISO 639 language code, if declared
+    pub language: Option<String>,
+    /// Language code extension (1 = normal, 2 = large, 3 = children, 5 = normal captions, ...)
+    pub code_extension: u8,
+    /// Physical sub-stream id (0x20-0x3F) used for 4:3 display
+    pub stream_id: u8,
+    /// Physical sub-stream id used for 16:9 (wide) display
+    pub stream_id_wide: u8,
+    /// Physical sub-stream id used for letterbox display
+    pub stream_id_letterbox: u8,
+    /// Physical sub-stream id used for pan & scan display
+    pub stream_id_pan_scan: u8,
+}
+
+/// A cell of a program chain: a run of VOB sectors played in sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DvdCell {
+    /// VOB id of the cell
+    pub vob_id: u16,
+    /// Cell id within the VOB
+    pub cell_id: u8,
+    /// First sector of the cell, counted from the start of `VTS_xx_1.VOB`
+    pub first_sector: u32,
+    /// Last sector of the cell (inclusive)
+    pub last_sector: u32,
+    /// Playback duration in milliseconds
+    pub duration_ms: u32,
+}
+
+/// Subtitle-related information of a DVD title set (`VTS_xx_0.IFO`).
+#[derive(Debug, Clone)]
+pub struct DvdTitleSetInfo {
+    /// Video frame width
+    pub width: u16,
+    /// Video frame height
+    pub height: u16,
+    /// Whether the title set is PAL (625/50) rather than NTSC (525/60)
+    pub is_pal: bool,
+    /// Whether the display aspect ratio is 16:9
+    pub is_widescreen: bool,
+    /// Colour lookup table of the selected program chain
+    pub palette: VobSubPalette,
+    /// Declared subpicture streams, mapped to physical streams by the
+    /// selected program chain
+    pub subpicture_streams: Vec<DvdSubpictureStream>,
+    /// Number of program chains in the title set
+    pub pgc_count: usize,
+    /// Cells of the selected program chain, in playback order
+    pub cells: Vec<DvdCell>,
+}
+
+impl DvdSubpictureStream {
+    /// Get the physical sub-stream id to use for the given display aspect.
+    pub fn stream_id_for(&self, widescreen: bool) -> u8 {
+        if widescreen {
+            self.stream_id_wide
+        } else {
+            self.stream_id
+        }
+    }
+}
+
+/// Parse a title set information file (`VTS_xx_0.IFO` or its `.BUP` backup)
+/// for its first program chain.
+pub fn parse_vts_ifo(data: &[u8]) -> Result<DvdTitleSetInfo, String> {
+    parse_vts_ifo_pgc(data, 0)
+}
+
+/// Parse a title set information file for the program chain `pgc_index`
+/// (0-based), which supplies the palette, stream mapping and cells.
+pub fn parse_vts_ifo_pgc(data: &[u8], pgc_index: usize) -> Result<DvdTitleSetInfo, String> {
+    if !data.starts_with(VTS_IDENTIFIER) {
+        return Err("Not a DVD title set IFO (missing DVDVIDEO-VTS)".to_string());
+    }
+    if data.len() < VTS_SUBPICTURE_ATTRIBUTES_OFFSET + MAX_SUBPICTURE_STREAMS * 6 {
+        return Err("Truncated DVD title set IFO".to_string());
+    }
+
+    // Video attributes: standard in bits 5-4 and aspect ratio in bits 3-2 of
+    // the first byte, picture size in bits 3-2 of the second
+    let video_attributes = data[VTS_VIDEO_ATTRIBUTES_OFFSET];
+    let picture_size = (data[VTS_VIDEO_ATTRIBUTES_OFFSET + 1] >> 2) & 0x03;
+    let is_pal = (video_attributes >> 4) & 0x03 == 1;
+    let is_widescreen = (video_attributes >> 2) & 0x03 == 3;
+    let full_height = if is_pal { 576 } else { 480 };
+    let (width, height) = match picture_size {
+        0 => (720, full_height),
+        1 => (704, full_height),
+        2 => (352, full_height),
+        _ => (352, full_height / 2),
+    };
+
+    let stream_count = u16::from_be_bytes([
+        data[VTS_SUBPICTURE_COUNT_OFFSET],
+        data[VTS_SUBPICTURE_COUNT_OFFSET + 1],
+    ]) as usize;
+    let stream_count = stream_count.min(MAX_SUBPICTURE_STREAMS);
+    let mut subpicture_streams = Vec::with_capacity(stream_count);
+    for index in 0..stream_count {
+        let offset = VTS_SUBPICTURE_ATTRIBUTES_OFFSET + index * 6;
+        let attributes = &data[offset..offset + 6];
+        let has_language = attributes[0] & 0x03 == 1;
+        let language = if has_language && attributes[2].is_ascii_alphabetic() {
+            Some(
+                String::from_utf8_lossy(&attributes[2..4])
+                    .trim_end_matches('\0')
+                    .to_ascii_lowercase(),
+            )
+        } else {
+            None
+        };
+        let physical = 0x20 + index as u8;
+        subpicture_streams.push(DvdSubpictureStream {
+            index,
+            language,
+            code_extension: attributes[5],
+            stream_id: physical,
+            stream_id_wide: physical,
+            stream_id_letterbox: physical,
+            stream_id_pan_scan: physical,
+        });
+    }
+
+    let pgcit = read_pgc_table(data);
+    let pgc_count = pgcit.map_or(0, |(_, count)| count);
+    let (palette, cells) = match pgcit {
+        Some((pgcit, _)) => {
+            let pgc = read_pgc(pgcit, pgc_index)
+                .ok_or_else(|| format!("DVD title set has no program chain {pgc_index}"))?;
+            apply_subpicture_control(pgc, &mut subpicture_streams);
+            (read_pgc_palette(pgc), read_pgc_cells(pgc))
+        }
+        // Without a PGC table only the default palette and raw timing remain
+        None if pgc_index == 0 => (VobSubPalette::default(), Vec::new()),
+        None => return Err(format!("DVD title set has no program chain {pgc_index}")),
+    };
+
+    Ok(DvdTitleSetInfo {
+        width,
+        height,
+        is_pal,
+        is_widescreen,
+        palette,
+        subpicture_streams,
+        pgc_count,
+        cells,
+    })
+}
+
+/// Locate the title set's PGC table and its number of program chains.
+fn read_pgc_table(data: &[u8]) -> Option<(&[u8], usize)> {
+    let mut reader = BigEndianReader::new(data);
+    reader.set_position(VTS_PGCIT_SECTOR_OFFSET);
+    let pgcit_start = (reader.read_u32()? as usize).checked_mul(DVD_SECTOR_SIZE)?;
+    if pgcit_start == 0 {
+        return None;
+    }
+
+    let pgcit = data.get(pgcit_start..)?;
+    let pgc_count = BigEndianReader::new(pgcit).read_u16()? as usize;
+    (pgc_count > 0).then_some((pgcit, pgc_count))
+}
+
+/// Locate a program chain through its PGC table search pointer.
+fn read_pgc(pgcit: &[u8], index: usize) -> Option<&[u8]> {
+    let mut reader = BigEndianReader::new(pgcit);
+    if index >= reader.read_u16()? as usize {
+        return None;
+    }
+    // Count, reserved and end address, then 8-byte search pointers
+    // (category, offset)
+    reader.set_position(8 + index * 8 + 4);
+    let pgc_offset = reader.read_u32()? as usize;
+
+    let pgc = pgcit.get(pgc_offset..)?;
+    (pgc.len() >= PGC_CELL_POSITION_OFFSET + 2).then_some(pgc)
+}
+
+/// Read the cell playback and cell position tables of a PGC.
+fn read_pgc_cells(pgc: &[u8]) -> Vec<DvdCell> {
+    let cell_count = pgc[PGC_CELL_COUNT_OFFSET] as usize;
+    let table_offset = |offset: usize| u16::from_be_bytes([pgc[offset], pgc[offset + 1]]) as usize;
+    let playback = table_offset(PGC_CELL_PLAYBACK_OFFSET);
+    let position = table_offset(PGC_CELL_POSITION_OFFSET);
+    if cell_count == 0 || playback == 0 || position == 0 {
+        return Vec::new();
+    }
+
+    let mut cells = Vec::with_capacity(cell_count);
+    for index in 0..cell_count {
+        let entry_start = playback + index * CELL_PLAYBACK_ENTRY_SIZE;
+        let position_start = position + index * CELL_POSITION_ENTRY_SIZE;
+        let (Some(entry), Some(position)) = (
+            pgc.get(entry_start..entry_start + CELL_PLAYBACK_ENTRY_SIZE),
+            pgc.get(position_start..position_start + CELL_POSITION_ENTRY_SIZE),
+        ) else {
+            break;
+        };
+        let sector = |offset: usize| {
+            u32::from_be_bytes([
+                entry[offset],
+                entry[offset + 1],
+                entry[offset + 2],
+                entry[offset + 3],
+            ])
+        };
+        cells.push(DvdCell {
+            vob_id: u16::from_be_bytes([position[0], position[1]]),
+            cell_id: position[3],
+            first_sector: sector(0x08),
+            last_sector: sector(0x14),
+            duration_ms: playback_time_ms(&entry[0x04..0x08]),
+        });
+    }
+    cells
+}
+
+/// Convert a BCD playback time (hours, minutes, seconds, then the frame rate
+/// in bits 7-6 and frames in bits 5-0 of the last byte) to milliseconds.
+fn playback_time_ms(time: &[u8]) -> u32 {
+    let bcd = |byte: u8| (byte >> 4) as u32 * 10 + (byte & 0x0F) as u32;
+    let frame_rate = if time[3] >> 6 == 1 { 25 } else { 30 };
+    bcd(time[0]) * 3_600_000
+        + bcd(time[1]) * 60_000
+        + bcd(time[2]) * 1_000
+        + bcd(time[3] & 0x3F) * 1_000 / frame_rate
+}
+
+/// Read the 16-entry colour lookup table (0, Y, Cr, Cb per entry) of a PGC.
+fn read_pgc_palette(pgc: &[u8]) -> VobSubPalette {
+    let mut palette = VobSubPalette::default();
+    let table = &pgc[PGC_PALETTE_OFFSET..PGC_PALETTE_OFFSET + 64];
+    for (color, entry) in palette.rgba.iter_mut().zip(table.chunks_exact(4)) {
+        *color = ycbcr_to_rgba(entry[1], entry[3], entry[2], 255);
+    }
+    palette
+}
+
+/// Apply the PGC subpicture stream control table (logical to physical stream
+/// mapping per display mode).
+fn apply_subpicture_control(pgc: &[u8], streams: &mut [DvdSubpictureStream]) {
+    for stream in streams.iter_mut() {
+        let offset = PGC_SUBPICTURE_CONTROL_OFFSET + stream.index * 4;
+        let control = u32::from_be_bytes([
+            pgc[offset],
+            pgc[offset + 1],
+            pgc[offset + 2],
+            pgc[offset + 3],
+        ]);
+        // Bit 31: stream available in this PGC
+        if control & 0x8000_0000 == 0 {
+            continue;
+        }
+        stream.stream_id = 0x20 | ((control >> 24) & 0x1F) as u8;
+        stream.stream_id_wide = 0x20 | ((control >> 16) & 0x1F) as u8;
+        stream.stream_id_letterbox = 0x20 | ((control >> 8) & 0x1F) as u8;
+        stream.stream_id_pan_scan = 0x20 | (control & 0x1F) as u8;
+    }
+}
+
+/// Get the offset of the first PES packet of a VOB pack, after the pack
+/// header and any system header.
+fn pack_pes_offset(pack: &[u8]) -> Option<usize> {
+    if !pack.starts_with(&[0x00, 0x00, 0x01, 0xBA]) {
+        return None;
+    }
+
+    // MPEG-2 pack header is 14 bytes plus stuffing; MPEG-1 is 12 bytes
+    let mut offset = if pack.get(4)? & 0xC0 == 0x40 {
+        14 + (pack.get(13)? & 0x07) as usize
+    } else {
+        12
+    };
+
+    // Skip a system header if present
+    if pack.get(offset..offset + 4)? == [0x00, 0x00, 0x01, 0xBB] {
+        let length = u16::from_be_bytes([*pack.get(offset + 4)?, *pack.get(offset + 5)?]);
+        offset += 6 + length as usize;
+    }
+    Some(offset)
+}
+
+/// Get the sub-stream id of a VOB pack carrying subpicture data.
+fn pack_subpicture_stream_id(pack: &[u8]) -> Option<u8> {
+    let offset = pack_pes_offset(pack)?;
+    if pack.get(offset..offset + 4)? != [0x00, 0x00, 0x01, 0xBD] {
+        return None;
+    }
+    let header_data_length = *pack.get(offset + 8)? as usize;
+    let sub_stream_id = *pack.get(offset + 9 + header_data_length)?;
+    is_vobsub_stream_id(sub_stream_id).then_some(sub_stream_id)
+}
+
+/// Get the offset of the PTS field of a subpicture pack, if it carries one.
+fn pack_pts_offset(pack: &[u8]) -> Option<usize> {
+    let offset = pack_pes_offset(pack)?;
+    let has_pts = pack.get(offset + 7)? & 0x80 != 0;
+    (has_pts && pack.len() >= offset + 14).then_some(offset + 9)
+}
+
+/// Get `VOBU_S_PTM`, the start time of the VOBU, of a navigation pack.
+fn nav_pack_start_ptm(pack: &[u8]) -> Option<u64> {
+    let offset = pack_pes_offset(pack)?;
+    // Private Stream 2 carrying the PCI (sub-stream 0x00)
+    if pack.get(offset..offset + 4)? != [0x00, 0x00, 0x01, 0xBF] || *pack.get(offset + 6)? != 0 {
+        return None;
+    }
+    let ptm = offset + 7 + PCI_VOBU_START_PTM_OFFSET;
+    let bytes = pack.get(ptm..ptm + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as u64)
+}
+
+/// Decode a 33-bit PES timestamp (90 kHz).
+fn read_pts(bytes: &[u8]) -> u64 {
+    (((bytes[0] >> 1) & 0x07) as u64) << 30
+        | (bytes[1] as u64) << 22
+        | ((bytes[2] >> 1) as u64) << 15
+        | (bytes[3] as u64) << 7
+        | (bytes[4] >> 1) as u64
+}
+
+/// Visit the packs of VOB data with their byte offsets, resynchronising on
+/// the next pack start code after garbage.
+fn for_each_pack(vob_data: &[u8], mut visit: impl FnMut(usize, &[u8])) {
+    let mut offset = 0;
+
+    while offset + 4 <= vob_data.len() {
+        let pack_end = (offset + DVD_SECTOR_SIZE).min(vob_data.len());
+        let pack = &vob_data[offset..pack_end];
+        if !pack.starts_with(&[0x00, 0x00, 0x01, 0xBA]) {
+            match memchr::memmem::find(&vob_data[offset + 1..], &[0x00, 0x00, 0x01, 0xBA]) {
+                Some(pos) => {
+                    offset += 1 + pos;
+                    continue;
+                }
+                None => break,
+            }
+        }
+
+        visit(offset, pack);
+        offset = pack_end;
+    }
+}
+
+/// Demux the subpicture packs (Private Stream 1, sub-streams 0x20-0x3F) of a
+/// VOB set into `.sub` data. Pass `stream_id` to keep a single sub-stream.
+///
+/// The VOB files of a title set can be demuxed one by one and the results
+/// concatenated, since files always end on a pack boundary. Timestamps are
+/// left as they are in the VOBs; use `DvdSubpictureDemuxer::for_title` to
+/// get title time.
+pub fn demux_vob_subpictures(vob_data: &[u8], stream_id: Option<u8>) -> Vec<u8> {
+    let mut sub_data = Vec::new();
+    for_each_pack(vob_data, |_, pack| {
+        if let Some(pack_stream_id) = pack_subpicture_stream_id(pack)
+            && stream_id.is_none_or(|wanted| wanted == pack_stream_id)
+        {
+            sub_data.extend_from_slice(pack);
+        }
+    });
+    sub_data
+}
+
+/// Incremental VOB demuxer for title sets too large to hold in memory.
+///
+/// Push the VOB files in order, in chunks of any size; only the subpicture
+/// packs are kept.
+#[derive(Debug, Clone, Default)]
+pub struct DvdSubpictureDemuxer {
+    stream_id: Option<u8>,
+    pending: Vec<u8>,
+    sub_data: Vec<u8>,
+    /// Bytes of VOB data demuxed so far, to locate packs in the cells
+    consumed: usize,
+    cells: Vec<DvdCell>,
+    /// Title time at which each cell starts, in 90 kHz ticks
+    cell_starts: Vec<u64>,
+    /// First VOBU start time seen in each cell, in 90 kHz ticks
+    cell_bases: Vec<Option<u64>>,
+}
+
+impl DvdSubpictureDemuxer {
+    /// Create a demuxer. Pass `stream_id` to keep a single sub-stream.
+    pub fn new(stream_id: Option<u8>) -> Self {
+        Self {
+            stream_id,
+            ..Self::default()
+        }
+    }
+
+    /// Create a demuxer for the program chain `info` was parsed for. Only
+    /// the packs of its cells are kept, and their timestamps are rewritten to
+    /// title time: the cell's start in the PGC plus the time since the
+    /// cell's first VOBU. Without cells this is the same as `new`.
+    pub fn for_title(info: &DvdTitleSetInfo, stream_id: Option<u8>) -> Self {
+        let mut cell_starts = Vec::with_capacity(info.cells.len());
+        let mut start = 0u64;
+        for cell in &info.cells {
+            cell_starts.push(start);
+            start += cell.duration_ms as u64 * 90;
+        }
+        Self {
+            stream_id,
+            cells: info.cells.clone(),
+            cell_starts,
+            cell_bases: vec![None; info.cells.len()],
+            ..Self::default()
+        }
+    }
+
+    /// Demux the complete packs of a chunk of VOB data. A trailing partial
+    /// pack is kept until the next chunk.
+    pub fn push(&mut self, chunk: &[u8]) {
+        self.pending.extend_from_slice(chunk);
+        let complete = self.pending.len() - self.pending.len() % DVD_SECTOR_SIZE;
+        if complete == 0 {
+            return;
+        }
+        let pending = std::mem::take(&mut self.pending);
+        self.demux(&pending[..complete]);
+        self.pending = pending;
+        self.pending.drain(..complete);
+    }
+
+    /// Get the number of subpicture bytes demuxed so far.
+    pub fn sub_data_len(&self) -> usize {
+        self.sub_data.len()
+    }
+
+    /// Demux any remaining data and return the `.sub` data.
+    pub fn finish(mut self) -> Vec<u8> {
+        let pending = std::mem::take(&mut self.pending);
+        self.demux(&pending);
+        self.sub_data
+    }
+
+    fn demux(&mut self, vob_data: &[u8]) {
+        if self.cells.is_empty() {
+            self.sub_data
+                .extend(demux_vob_subpictures(vob_data, self.stream_id));
+            self.consumed += vob_data.len();
+            return;
+        }
+
+        for_each_pack(vob_data, |offset, pack| {
+            let sector = ((self.consumed + offset) / DVD_SECTOR_SIZE) as u32;
+            let Some(cell) = self
+                .cells
+                .iter()
+                .position(|cell| (cell.first_sector..=cell.last_sector).contains(&sector))
+            else {
+                return;
+            };
+
+            if let Some(start_ptm) = nav_pack_start_ptm(pack) {
+                self.cell_bases[cell].get_or_insert(start_ptm);
+                return;
+            }
+            let Some(pack_stream_id) = pack_subpicture_stream_id(pack) else {
+                return;
+            };
+            if self
+                .stream_id
+                .is_some_and(|wanted| wanted != pack_stream_id)
+            {
+                return;
+            }
+
+            let start = self.sub_data.len();
+            self.sub_data.extend_from_slice(pack);
+            if let Some(pts_offset) = pack_pts_offset(pack) {
+                let pts_field = start + pts_offset..start + pts_offset + 5;
+                let pts = read_pts(&self.sub_data[pts_field.clone()]);
+                let base = *self.cell_bases[cell].get_or_insert(pts);
+                let title_pts = self.cell_starts[cell] + pts.saturating_sub(base);
+                self.sub_data[pts_field].copy_from_slice(&encode_pts(title_pts));
+            }
+        });
+        self.consumed += vob_data.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::rgb_to_ycbcr;
+
+    fn vts_ifo() -> Vec<u8> {
+        let mut ifo = vec![0u8; DVD_SECTOR_SIZE * 2];
+        ifo[..12].copy_from_slice(VTS_IDENTIFIER);
+        ifo[VTS_PGCIT_SECTOR_OFFSET..VTS_PGCIT_SECTOR_OFFSET + 4]
+            .copy_from_slice(&1u32.to_be_bytes());
+        ifo[VTS_VIDEO_ATTRIBUTES_OFFSET] = 0x1C; // PAL, 16:9
+        ifo[VTS_SUBPICTURE_COUNT_OFFSET + 1] = 2;
+        let attributes = VTS_SUBPICTURE_ATTRIBUTES_OFFSET;
+        ifo[attributes..attributes + 6].copy_from_slice(&[0x01, 0, b'e', b'n', 1, 0]);
+        ifo[attributes + 6..attributes + 12].copy_from_slice(&[0x01, 0, b'd', b'e', 0, 3]);
+
+        // PGC table with one PGC at offset 0x100
+        let pgcit = DVD_SECTOR_SIZE;
+        ifo[pgcit + 1] = 1;
+        ifo[pgcit + 12..pgcit + 16].copy_from_slice(&0x100u32.to_be_bytes());
+        let pgc = pgcit + 0x100;
+        let control = pgc + PGC_SUBPICTURE_CONTROL_OFFSET;
+        ifo[control..control + 4].copy_from_slice(&[0x80, 0x00, 0x01, 0x02]);
+        ifo[control + 4..control + 8].copy_from_slice(&[0x83, 0x04, 0x05, 0x06]);
+        let (y, cb, cr) = rgb_to_ycbcr(255, 0, 0);
+        let palette = pgc + PGC_PALETTE_OFFSET;
+        ifo[palette + 4..palette + 8].copy_from_slice(&[0, y, cr, cb]);
+        ifo
+    }
+
+    /// Give the PGC at `pgc` cells of (first sector, last sector, BCD
+    /// playback time).
+    fn set_cells(ifo: &mut [u8], pgc: usize, cells: &[(u32, u32, [u8; 4])]) {
+        ifo[pgc + PGC_CELL_COUNT_OFFSET] = cells.len() as u8;
+        let playback = PGC_CELL_PLAYBACK_OFFSET;
+        ifo[pgc + playback..pgc + playback + 2].copy_from_slice(&0x100u16.to_be_bytes());
+        let position = PGC_CELL_POSITION_OFFSET;
+        ifo[pgc + position..pgc + position + 2].copy_from_slice(&0x180u16.to_be_bytes());
+        for (index, &(first, last, time)) in cells.iter().enumerate() {
+            let entry = pgc + 0x100 + index * CELL_PLAYBACK_ENTRY_SIZE;
+            ifo[entry + 0x04..entry + 0x08].copy_from_slice(&time);
+            ifo[entry + 0x08..entry + 0x0C].copy_from_slice(&first.to_be_bytes());
+            ifo[entry + 0x14..entry + 0x18].copy_from_slice(&last.to_be_bytes());
+            let position = pgc + 0x180 + index * CELL_POSITION_ENTRY_SIZE;
+            ifo[position..position + 4].copy_from_slice(&[0, 1, 0, index as u8 + 1]);
+        }
+    }
+
+    /// Build a navigation pack whose PCI starts the VOBU at `start_ms`.
+    fn nav_pack(start_ms: u32) -> Vec<u8> {
+        let mut pack = vec![
+            0x00, 0x00, 0x01, 0xBA, 0x44, 0x00, 0x04, 0x00, 0x04, 0x01, 0x01, 0x89, 0xC3, 0xF8,
+        ];
+        pack.extend_from_slice(&[0x00, 0x00, 0x01, 0xBB, 0x00, 0x12]);
+        pack.extend_from_slice(&[0u8; 0x12]);
+        pack.extend_from_slice(&[0x00, 0x00, 0x01, 0xBF, 0x03, 0xD4, 0x00]);
+        let mut pci = vec![0u8; 0x3D3];
+        pci[PCI_VOBU_START_PTM_OFFSET..PCI_VOBU_START_PTM_OFFSET + 4]
+            .copy_from_slice(&(start_ms * 90).to_be_bytes());
+        pack.extend(pci);
+        pack.resize(DVD_SECTOR_SIZE, 0xFF);
+        pack
+    }
+
+    /// Build a 2048-byte pack with one Private Stream 1 PES and padding.
+    fn pack(sub_stream_id: u8, pts_ms: u32, payload: &[u8]) -> Vec<u8> {
+        let mut pack = vec![
+            0x00, 0x00, 0x01, 0xBA, 0x44, 0x00, 0x04, 0x00, 0x04, 0x01, 0x01, 0x89, 0xC3, 0xF8,
+        ];
+        let pts = pts_ms as u64 * 90;
+        pack.extend_from_slice(&[0x00, 0x00, 0x01, 0xBD]);
+        pack.extend_from_slice(&((payload.len() + 9) as u16).to_be_bytes());
+        pack.extend_from_slice(&[
+            0x81,
+            0x80,
+            0x05,
+            0x21 | ((pts >> 29) as u8 & 0x0E),
+            (pts >> 22) as u8,
+            ((pts >> 14) as u8 & 0xFE) | 0x01,
+            (pts >> 7) as u8,
+            ((pts << 1) as u8 & 0xFE) | 0x01,
+            sub_stream_id,
+        ]);
+        pack.extend_from_slice(payload);
+
+        let padding = DVD_SECTOR_SIZE - pack.len() - 6;
+        pack.extend_from_slice(&[0x00, 0x00, 0x01, 0xBE]);
+        pack.extend_from_slice(&(padding as u16).to_be_bytes());
+        pack.resize(DVD_SECTOR_SIZE, 0xFF);
+        pack
+    }
+
+    /// A 2x1 subtitle using palette entry 1 for both pixels.
+    const SPU: [u8; 30] = [
+        0x00, 0x1E, 0x00, 0x06, 0x90, 0x00, // size, control offset, RLE
+        0x00, 0x00, 0x00, 0x06, 0x01, // start display
+        0x03, 0x00, 0x10, 0x04, 0x0F, 0xF0, // palette, alpha
+        0x05, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, // area 2x1
+        0x06, 0x00, 0x04, 0x00, 0x05, 0xFF, // field offsets, end
+    ];
+
+    #[test]
+    fn parses_title_set_palette_and_streams() {
+        let info = parse_vts_ifo(&vts_ifo()).unwrap();
+
+        assert!(info.is_pal && info.is_widescreen);
+        assert_eq!((info.width, info.height), (720, 576));
+        assert_eq!(info.subpicture_streams.len(), 2);
+        let german = &info.subpicture_streams[1];
+        assert_eq!(german.language.as_deref(), Some("de"));
+        assert_eq!(german.stream_id, 0x23);
+        assert_eq!(german.code_extension, 3);
+        assert_eq!(info.subpicture_streams[0].code_extension, 0);
+        assert_eq!(german.stream_id_for(true), 0x24);
+        assert_eq!(info.subpicture_streams[0].stream_id_wide, 0x20);
+
+        let [r, g, b, a] = info.palette.rgba[1].to_le_bytes();
+        assert!(r > 240 && g < 16 && b < 16 && a == 255);
+
+        assert!(parse_vts_ifo(b"DVDVIDEO-VMG").is_err());
+    }
+
+    #[test]
+    fn reads_picture_size_bits() {
+        let mut ifo = vts_ifo();
+        // CBR flag (bit 4) set, picture size 0
+        ifo[VTS_VIDEO_ATTRIBUTES_OFFSET + 1] = 0x10;
+        let info = parse_vts_ifo(&ifo).unwrap();
+        assert_eq!((info.width, info.height), (720, 576));
+
+        ifo[VTS_VIDEO_ATTRIBUTES_OFFSET + 1] = 0x0C;
+        let info = parse_vts_ifo(&ifo).unwrap();
+        assert_eq!((info.width, info.height), (352, 288));
+    }
+
+    #[test]
+    fn demuxes_subpicture_packs_only() {
+        let mut vob = pack(0x20, 0, &SPU);
+        vob.extend(pack(0x80, 0, &[0u8; 16])); // AC-3 audio
+        vob.extend(pack(0x21, 0, &SPU));
+        vob.extend(pack(0x20, 0, &SPU));
+
+        assert_eq!(demux_vob_subpictures(&vob, None).len(), 3 * DVD_SECTOR_SIZE);
+        let english = demux_vob_subpictures(&vob, Some(0x20));
+        assert_eq!(english.len(), 2 * DVD_SECTOR_SIZE);
+        assert_eq!(
+            pack_subpicture_stream_id(&english[DVD_SECTOR_SIZE..]),
+            Some(0x20)
+        );
+    }
+
+    #[test]
+    fn demuxer_accepts_unaligned_chunks() {
+        let mut vob = pack(0x20, 0, &SPU);
+        vob.extend(pack(0x80, 0, &[0u8; 16]));
+        vob.extend(pack(0x21, 0, &SPU));
+
+        let mut demuxer = DvdSubpictureDemuxer::new(None);
+        for chunk in vob[..5000].chunks(1000) {
+            demuxer.push(chunk);
+        }
+        // The third pack is still partial
+        assert_eq!(demuxer.sub_data_len(), DVD_SECTOR_SIZE);
+        demuxer.push(&vob[5000..]);
+        assert_eq!(demuxer.finish(), demux_vob_subpictures(&vob, None));
+    }
+
+    #[test]
+    fn loads_title_set_into_vobsub_parser() {
+        let mut vob = pack(0x20, 1000, &SPU);
+        vob.extend(pack(0x24, 2000, &SPU));
+
+        let mut parser = crate::vobsub::VobSubParser::new();
+        parser.load_from_dvd(&vts_ifo(), &vob, 0).unwrap();
+
+        assert_eq!(parser.track_count(), 2);
+        assert_eq!(parser.screen_height(), 576);
+        assert_eq!(parser.language(), "en");
+        assert_eq!(parser.get_timestamps(), vec![1000.0]);
+
+        assert!(parser.select_track(1));
+        assert_eq!(parser.track_stream_index(1), 4);
+        assert_eq!(parser.get_timestamps(), vec![2000.0]);
+        let frame = parser.render_at_index(0).expect("frame");
+        let pixel = &frame.get_rgba()[..4];
+        assert!(pixel[0] > 240 && pixel[1] < 16 && pixel[3] == 255);
+
+        // Stream switching goes through the IFO tracks, not a SUB rescan
+        let palette = parser.palette().map(|palette| palette.rgba);
+        assert!(parser.has_idx_metadata());
+        assert!(!parser.select_sub_stream(0x20));
+        assert!(parser.select_track(0));
+        assert_eq!(parser.track_count(), 2);
+        assert_eq!(parser.language(), "en");
+        assert_eq!(parser.palette().map(|palette| palette.rgba), palette);
+        assert_eq!(parser.get_timestamps(), vec![1000.0]);
+    }
+
+    #[test]
+    fn maps_cell_timestamps_to_title_time() {
+        let mut ifo = vts_ifo();
+        let pgcit = DVD_SECTOR_SIZE;
+        // Sectors 0-2 play for 5 s, then sectors 3-5 for 2 s (25 fps)
+        set_cells(
+            &mut ifo,
+            pgcit + 0x100,
+            &[(0, 2, [0, 0, 0x05, 0x40]), (3, 5, [0, 0, 0x02, 0x40])],
+        );
+        // A second PGC plays the second cell alone
+        ifo[pgcit + 1] = 2;
+        ifo[pgcit + 20..pgcit + 24].copy_from_slice(&0x400u32.to_be_bytes());
+        set_cells(&mut ifo, pgcit + 0x400, &[(3, 5, [0, 0, 0x02, 0x40])]);
+
+        let info = parse_vts_ifo_pgc(&ifo, 0).unwrap();
+        assert_eq!(info.pgc_count, 2);
+        assert_eq!(info.cells.len(), 2);
+        assert_eq!(info.cells[1].first_sector, 3);
+        assert_eq!(info.cells[1].duration_ms, 2000);
+        assert!(parse_vts_ifo_pgc(&ifo, 2).is_err());
+
+        // The second cell restarts its timestamps
+        let mut vob = nav_pack(60_000);
+        vob.extend(pack(0x20, 60_500, &SPU));
+        vob.extend(pack(0x20, 64_000, &SPU));
+        vob.extend(nav_pack(0));
+        vob.extend(pack(0x20, 300, &SPU));
+        vob.extend(pack(0x20, 1_000, &SPU));
+
+        let mut parser = crate::vobsub::VobSubParser::new();
+        parser.load_from_dvd(&ifo, &vob, 0).unwrap();
+        assert_eq!(parser.get_timestamps(), vec![500.0, 4000.0, 5300.0, 6000.0]);
+
+        parser.load_from_dvd(&ifo, &vob, 1).unwrap();
+        assert_eq!(parser.get_timestamps(), vec![300.0, 1000.0]);
+        assert!(parser.load_from_dvd(&ifo, &vob, 2).is_err());
+
+        let mut demuxer = DvdSubpictureDemuxer::for_title(&info, None);
+        for chunk in vob.chunks(1500) {
+            demuxer.push(chunk);
+        }
+        parser
+            .load_from_dvd_subpictures(&ifo, demuxer.finish(), 0)
+            .unwrap();
+        assert_eq!(parser.get_timestamps(), vec![500.0, 4000.0, 5300.0, 6000.0]);
+    }
+}
@@ -0,0 +1,627 @@
+//! VobSub (.idx/.sub) encoder building DVD subpicture units from RGBA bitmaps.
+//!
+//! Each cue is quantized to four colours taken from a fixed 16-colour palette,
+//! encoded as interlaced 2-bit RLE with a start and a stop control sequence,
+//! and split into 2048-byte MPEG-2 program stream packs.
+
+use std::collections::HashMap;
+
+use super::mks_parser::{encode_pts, format_timestamp};
+use super::{MAX_VOBSUB_IMAGE_PIXELS, VOBSUB_STREAM_ID_FIRST, VobSubPalette};
+use crate::vobsub::DVD_SECTOR_SIZE;
+
+/// Largest subpicture unit (its size field is 16 bits).
+const MAX_SPU_SIZE: usize = 0xFFFF;
+/// Largest coordinate of the 12-bit display area fields.
+const MAX_AREA_COORDINATE: u32 = 0xFFF;
+/// Pack header (14 bytes) + PES header with PTS (9 + 5) + sub-stream id.
+const FIRST_PES_OVERHEAD: usize = 14 + 6 + 3 + 5 + 1;
+/// Pack header (14 bytes) + PES header without PTS (9) + sub-stream id.
+const NEXT_PES_OVERHEAD: usize = 14 + 6 + 3 + 1;
+/// Alpha nibble below which a pixel is treated as transparent.
+const MIN_VISIBLE_ALPHA: u8 = 1;
+/// Colour slot reserved for the transparent background.
+const BACKGROUND_SLOT: usize = 0;
+
+/// An RGBA bitmap to place on screen.
+#[derive(Debug, Clone)]
+pub struct VobSubBitmap {
+    /// Horizontal screen position
+    pub x: u16,
+    /// Vertical screen position
+    pub y: u16,
+    /// Bitmap width in pixels
+    pub width: u16,
+    /// Bitmap height in pixels
+    pub height: u16,
+    /// RGBA pixel data, row-major (`width * height * 4` bytes)
+    pub rgba: Vec<u8>,
+    /// Display with FSTA_DSP (forced start display)
+    pub forced: bool,
+}
+
+/// A bitmap reduced to the four colours of one subpicture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuantizedSubpicture {
+    /// Palette index of each 2-bit pixel value
+    pub color_indices: [u8; 4],
+    /// Alpha (0-15) of each 2-bit pixel value
+    pub alpha_values: [u8; 4],
+    /// 2-bit pixel values, row-major
+    pub pixels: Vec<u8>,
+}
+
+/// Encoded `.idx` and `.sub` file contents.
+#[derive(Debug, Clone)]
+pub struct EncodedVobSub {
+    pub idx_content: String,
+    pub sub_data: Vec<u8>,
+}
+
+/// Quantize an RGBA bitmap to four (palette colour, alpha) pairs.
+///
+/// Each visible pixel is matched to its nearest palette colour and a 4-bit
+/// alpha. Value 0 is the transparent background; the three most frequent
+/// remaining pairs get values 1-3 and other pixels use the closest of them.
+pub fn quantize_to_subpicture(rgba: &[u8], palette: &VobSubPalette) -> QuantizedSubpicture {
+    let palette_rgb: Vec<[u8; 3]> = palette
+        .rgba
+        .iter()
+        .map(|color| {
+            let [r, g, b, _] = color.to_le_bytes();
+            [r, g, b]
+        })
+        .collect();
+
+    let mut nearest_cache: HashMap<[u8; 3], u8> = HashMap::new();
+    let mut pairs = Vec::with_capacity(rgba.len() / 4);
+    let mut counts: HashMap<(u8, u8), usize> = HashMap::new();
+    for pixel in rgba.chunks_exact(4) {
+        let alpha = ((pixel[3] as u32 * 15 + 127) / 255) as u8;
+        if alpha < MIN_VISIBLE_ALPHA {
+            pairs.push(None);
+            continue;
+        }
+        let rgb = [pixel[0], pixel[1], pixel[2]];
+        let index = *nearest_cache
+            .entry(rgb)
+            .or_insert_with(|| nearest_palette_index(rgb, &palette_rgb));
+        *counts.entry((index, alpha)).or_insert(0) += 1;
+        pairs.push(Some((index, alpha)));
+    }
+
+    // Most frequent first; ties broken by palette index and alpha for stable output
+    let mut ranked: Vec<((u8, u8), usize)> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    let mut color_indices = [0u8; 4];
+    let mut alpha_values = [0u8; 4];
+    let chosen: Vec<(u8, u8)> = ranked.iter().take(3).map(|(pair, _)| *pair).collect();
+    for (slot, &(index, alpha)) in chosen.iter().enumerate() {
+        color_indices[slot + 1] = index;
+        alpha_values[slot + 1] = alpha;
+    }
+
+    let mut slot_cache: HashMap<(u8, u8), u8> = HashMap::new();
+    let pixels = pairs
+        .into_iter()
+        .map(|pair| match pair {
+            None => BACKGROUND_SLOT as u8,
+            Some(pair) => *slot_cache.entry(pair).or_insert_with(|| {
+                let (slot, _) = chosen
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, candidate)| pair_distance(pair, **candidate, &palette_rgb))
+                    .expect("a visible pixel implies a chosen colour");
+                slot as u8 + 1
+            }),
+        })
+        .collect();
+
+    QuantizedSubpicture {
+        color_indices,
+        alpha_values,
+        pixels,
+    }
+}
+
+fn nearest_palette_index(rgb: [u8; 3], palette_rgb: &[[u8; 3]]) -> u8 {
+    palette_rgb
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, candidate)| rgb_distance(rgb, **candidate))
+        .map_or(0, |(index, _)| index as u8)
+}
+
+#[inline]
+fn rgb_distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    a.iter()
+        .zip(&b)
+        .map(|(&x, &y)| (x as i32 - y as i32).pow(2) as u32)
+        .sum()
+}
+
+fn pair_distance(a: (u8, u8), b: (u8, u8), palette_rgb: &[[u8; 3]]) -> u32 {
+    let color = rgb_distance(palette_rgb[a.0 as usize], palette_rgb[b.0 as usize]);
+    // One alpha step (of 15) weighs like a 17-level step in each channel
+    let alpha = (a.1 as i32 - b.1 as i32).pow(2) as u32 * 17 * 17 * 3;
+    color + alpha
+}
+
+/// Encode 2-bit pixels as DVD subpicture RLE, returning the even (top) and
+/// odd (bottom) field data.
+pub fn encode_vobsub_rle(pixels: &[u8], width: usize, height: usize) -> (Vec<u8>, Vec<u8>) {
+    let mut even = NibbleWriter::default();
+    let mut odd = NibbleWriter::default();
+
+    for (y, row) in pixels.chunks_exact(width).take(height).enumerate() {
+        let field = if y % 2 == 0 { &mut even } else { &mut odd };
+        let mut x = 0;
+        while x < width {
+            let color = row[x] & 0x03;
+            let run = row[x..].iter().take_while(|&&p| p & 0x03 == color).count();
+            if x + run == width && run > 255 {
+                // Run to the end of the line
+                field.push_code(0, color);
+                break;
+            }
+            let run = run.min(255);
+            field.push_code(run, color);
+            x += run;
+        }
+        field.align();
+    }
+
+    (even.bytes, odd.bytes)
+}
+
+#[derive(Default)]
+struct NibbleWriter {
+    bytes: Vec<u8>,
+    half: bool,
+}
+
+impl NibbleWriter {
+    fn push_nibble(&mut self, nibble: u8) {
+        if self.half {
+            *self.bytes.last_mut().expect("half byte pending") |= nibble & 0x0F;
+        } else {
+            self.bytes.push(nibble << 4);
+        }
+        self.half = !self.half;
+    }
+
+    /// Write a run of `run` pixels (0 = to end of line) of a 2-bit colour.
+    fn push_code(&mut self, run: usize, color: u8) {
+        let code = ((run as u16) << 2) | color as u16;
+        let nibbles = match run {
+            1..=3 => 1,
+            4..=15 => 2,
+            16..=63 => 3,
+            _ => 4,
+        };
+        for shift in (0..nibbles).rev() {
+            self.push_nibble((code >> (shift * 4)) as u8 & 0x0F);
+        }
+    }
+
+    /// Pad to a byte boundary (each line starts on a byte).
+    fn align(&mut self) {
+        self.half = false;
+    }
+}
+
+/// Streaming VobSub encoder producing `.idx` and `.sub` contents.
+pub struct VobSubEncoder {
+    width: u16,
+    height: u16,
+    palette: VobSubPalette,
+    language: String,
+    stream_index: u8,
+    timestamps: Vec<(u32, usize)>,
+    last_start: Option<u32>,
+    sub_data: Vec<u8>,
+}
+
+impl VobSubEncoder {
+    /// Create an encoder for the given video size and 16-colour palette.
+    pub fn new(width: u16, height: u16, palette: VobSubPalette) -> Self {
+        Self {
+            width,
+            height,
+            palette,
+            language: "en".to_string(),
+            stream_index: 0,
+            timestamps: Vec::new(),
+            last_start: None,
+            sub_data: Vec::new(),
+        }
+    }
+
+    /// Set the `.idx` language code and stream index (sub-stream 0x20 + index).
+    pub fn with_track(mut self, language: &str, stream_index: u8) -> Self {
+        self.language = language.to_string();
+        self.stream_index = stream_index.min(0x1F);
+        self
+    }
+
+    /// Append a cue shown from `start_ms` until `end_ms`.
+    ///
+    /// Cues must be added in start-time order.
+    pub fn add_cue(
+        &mut self,
+        start_ms: u32,
+        end_ms: u32,
+        bitmap: &VobSubBitmap,
+    ) -> Result<(), String> {
+        if end_ms <= start_ms {
+            return Err("VobSub cue end time must be after its start time".to_string());
+        }
+        if self.last_start.is_some_and(|last| start_ms < last) {
+            return Err("VobSub cues must be added in start-time order".to_string());
+        }
+        self.validate_bitmap(bitmap)?;
+
+        let quantized = quantize_to_subpicture(&bitmap.rgba, &self.palette);
+        let spu = build_spu(bitmap, &quantized, end_ms - start_ms)?;
+
+        self.timestamps.push((start_ms, self.sub_data.len()));
+        self.write_packs(start_ms, &spu);
+        self.last_start = Some(start_ms);
+        Ok(())
+    }
+
+    /// Finish the stream and return the `.idx` and `.sub` contents.
+    pub fn finish(self) -> EncodedVobSub {
+        let mut idx = String::new();
+        idx.push_str("# VobSub index file, v7 (do not modify this line!)\n");
+        idx.push_str(&format!("size: {}x{}\n", self.width, self.height));
+        idx.push_str("org: 0, 0\n");
+        idx.push_str("scale: 100%, 100%\n");
+        idx.push_str("alpha: 100%\n");
+        idx.push_str("fadein/out: 0, 0\n");
+        idx.push_str("forced subs: OFF\n");
+        let palette = self
+            .palette
+            .rgba
+            .iter()
+            .map(|color| {
+                let [r, g, b, _] = color.to_le_bytes();
+                format!("{r:02x}{g:02x}{b:02x}")
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        idx.push_str(&format!("palette: {palette}\n"));
+        idx.push_str("langidx: 0\n");
+        idx.push_str(&format!(
+            "id: {}, index: {}\n",
+            self.language, self.stream_index
+        ));
+        for (timestamp_ms, file_position) in &self.timestamps {
+            idx.push_str(&format!(
+                "timestamp: {}, filepos: {:08X}\n",
+                format_timestamp(*timestamp_ms),
+                file_position
+            ));
+        }
+
+        EncodedVobSub {
+            idx_content: idx,
+            sub_data: self.sub_data,
+        }
+    }
+
+    fn validate_bitmap(&self, bitmap: &VobSubBitmap) -> Result<(), String> {
+        if bitmap.width == 0 || bitmap.height == 0 {
+            return Err("VobSub bitmap must not be empty".to_string());
+        }
+        let pixel_count = bitmap.width as usize * bitmap.height as usize;
+        if pixel_count > MAX_VOBSUB_IMAGE_PIXELS {
+            return Err("VobSub bitmap is too large".to_string());
+        }
+        if bitmap.rgba.len() != pixel_count * 4 {
+            return Err("VobSub bitmap RGBA length does not match its size".to_string());
+        }
+        if bitmap.x as u32 + bitmap.width as u32 > self.width as u32
+            || bitmap.y as u32 + bitmap.height as u32 > self.height as u32
+        {
+            return Err("VobSub bitmap extends beyond the video size".to_string());
+        }
+        if bitmap.x as u32 + bitmap.width as u32 - 1 > MAX_AREA_COORDINATE
+            || bitmap.y as u32 + bitmap.height as u32 - 1 > MAX_AREA_COORDINATE
+        {
+            return Err("VobSub bitmap exceeds the 12-bit display area".to_string());
+        }
+        Ok(())
+    }
+
+    /// Split a subpicture unit into 2048-byte packs; the first carries the PTS.
+    fn write_packs(&mut self, start_ms: u32, spu: &[u8]) {
+        let pts = start_ms as u64 * 90;
+        let sub_stream_id = VOBSUB_STREAM_ID_FIRST + self.stream_index;
+        let mut remaining = spu;
+        let mut first = true;
+
+        while !remaining.is_empty() || first {
+            let overhead = if first {
+                FIRST_PES_OVERHEAD
+            } else {
+                NEXT_PES_OVERHEAD
+            };
+            let take = remaining.len().min(DVD_SECTOR_SIZE - overhead);
+            let free = DVD_SECTOR_SIZE - overhead - take;
+            // Gaps too small for a padding packet become PES header stuffing
+            let stuffing = if free < 6 { free } else { 0 };
+            let header_data: Vec<u8> = if first {
+                encode_pts(pts).to_vec()
+            } else {
+                Vec::new()
+            };
+
+            self.sub_data.extend_from_slice(&pack_header(pts));
+            self.sub_data.extend_from_slice(&[0x00, 0x00, 0x01, 0xBD]);
+            let pes_length = 3 + header_data.len() + stuffing + 1 + take;
+            self.sub_data
+                .extend_from_slice(&(pes_length as u16).to_be_bytes());
+            self.sub_data.push(0x81);
+            self.sub_data.push(if first { 0x80 } else { 0x00 });
+            self.sub_data.push((header_data.len() + stuffing) as u8);
+            self.sub_data.extend_from_slice(&header_data);
+            self.sub_data.extend(std::iter::repeat_n(0xFF, stuffing));
+            self.sub_data.push(sub_stream_id);
+            self.sub_data.extend_from_slice(&remaining[..take]);
+
+            if free >= 6 {
+                let padding = free - 6;
+                self.sub_data.extend_from_slice(&[0x00, 0x00, 0x01, 0xBE]);
+                self.sub_data
+                    .extend_from_slice(&(padding as u16).to_be_bytes());
+                self.sub_data.extend(std::iter::repeat_n(0xFF, padding));
+            }
+
+            remaining = &remaining[take..];
+            first = false;
+        }
+    }
+}
+
+/// Build a subpicture unit: header, both RLE fields and two control sequences
+/// (display at 0, stop after `duration_ms`).
+fn build_spu(
+    bitmap: &VobSubBitmap,
+    quantized: &QuantizedSubpicture,
+    duration_ms: u32,
+) -> Result<Vec<u8>, String> {
+    let (even, odd) = encode_vobsub_rle(
+        &quantized.pixels,
+        bitmap.width as usize,
+        bitmap.height as usize,
+    );
+
+    let top_field_offset = 4;
+    let bottom_field_offset = top_field_offset + even.len();
+    let first_dcsq = bottom_field_offset + odd.len();
+
+    let x1 = bitmap.x as u32;
+    let x2 = x1 + bitmap.width as u32 - 1;
+    let y1 = bitmap.y as u32;
+    let y2 = y1 + bitmap.height as u32 - 1;
+
+    let mut control = Vec::with_capacity(32);
+    // Start sequence: delay 0, next offset patched below
+    control.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+    control.push(if bitmap.forced { 0x00 } else { 0x01 });
+    control.push(0x03);
+    control.extend_from_slice(&pack_nibbles(quantized.color_indices));
+    control.push(0x04);
+    control.extend_from_slice(&pack_nibbles(quantized.alpha_values));
+    control.push(0x05);
+    control.extend_from_slice(&[
+        (x1 >> 4) as u8,
+        (((x1 & 0x0F) << 4) | (x2 >> 8)) as u8,
+        x2 as u8,
+        (y1 >> 4) as u8,
+        (((y1 & 0x0F) << 4) | (y2 >> 8)) as u8,
+        y2 as u8,
+    ]);
+    control.push(0x06);
+    control.extend_from_slice(&(top_field_offset as u16).to_be_bytes());
+    control.extend_from_slice(&(bottom_field_offset as u16).to_be_bytes());
+    control.push(0xFF);
+
+    // Stop sequence, pointing to itself to end the chain
+    let second_dcsq = first_dcsq + control.len();
+    let delay = ((duration_ms as u64 * 90 + 512) / 1024).min(0xFFFF) as u16;
+    control.extend_from_slice(&delay.to_be_bytes());
+    control.extend_from_slice(&(second_dcsq as u16).to_be_bytes());
+    control.push(0x02);
+    control.push(0xFF);
+    control[2..4].copy_from_slice(&(second_dcsq as u16).to_be_bytes());
+
+    let size = first_dcsq + control.len();
+    if size > MAX_SPU_SIZE {
+        return Err("VobSub subpicture exceeds the 64 KiB packet limit".to_string());
+    }
+
+    let mut spu = Vec::with_capacity(size);
+    spu.extend_from_slice(&(size as u16).to_be_bytes());
+    spu.extend_from_slice(&(first_dcsq as u16).to_be_bytes());
+    spu.extend_from_slice(&even);
+    spu.extend_from_slice(&odd);
+    spu.extend_from_slice(&control);
+    Ok(spu)
+}
+
+/// Pack four nibbles in SET_COLOR/SET_CONTR order (value 3 in the high nibble
+/// of the first byte, value 0 in the low nibble of the second).
+fn pack_nibbles(values: [u8; 4]) -> [u8; 2] {
+    [
+        (values[3] << 4) | (values[2] & 0x0F),
+        (values[1] << 4) | (values[0] & 0x0F),
+    ]
+}
+
+/// MPEG-2 pack header with the system clock reference set to `scr` (90 kHz).
+fn pack_header(scr: u64) -> [u8; 14] {
+    let scr = scr & 0x1_FFFF_FFFF;
+    [
+        0x00,
+        0x00,
+        0x01,
+        0xBA,
+        0x44 | (((scr >> 30) as u8 & 0x07) << 3) | ((scr >> 28) as u8 & 0x03),
+        (scr >> 20) as u8,
+        (((scr >> 15) as u8 & 0x1F) << 3) | 0x04 | ((scr >> 13) as u8 & 0x03),
+        (scr >> 5) as u8,
+        (((scr as u8) & 0x1F) << 3) | 0x04,
+        0x01,
+        0x01,
+        0x89,
+        0xC3,
+        0xF8,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::rgb_to_rgba;
+    use crate::vobsub::VobSubParser;
+
+    fn palette() -> VobSubPalette {
+        let mut palette = VobSubPalette::default();
+        palette.rgba[0] = rgb_to_rgba(128, 128, 128, 0);
+        palette.rgba[1] = rgb_to_rgba(255, 255, 255, 255);
+        palette.rgba[2] = rgb_to_rgba(0, 0, 0, 255);
+        palette.rgba[3] = rgb_to_rgba(255, 0, 0, 255);
+        palette
+    }
+
+    /// Clear the colour of transparent pixels so renders compare by appearance.
+    fn visible(rgba: &[u8]) -> Vec<u8> {
+        rgba.chunks_exact(4)
+            .flat_map(|p| {
+                if p[3] == 0 {
+                    [0; 4]
+                } else {
+                    [p[0], p[1], p[2], p[3]]
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rle_uses_short_codes_and_end_of_line() {
+        let mut pixels = vec![1, 1, 2, 2, 2, 2];
+        pixels.extend(vec![3; 300]);
+        let (even, odd) = encode_vobsub_rle(&pixels[..6], 6, 1);
+        // run 2 color 1 (nibble 0x9), run 4 color 2 (0x12), byte aligned
+        assert_eq!(even, vec![0x91, 0x20]);
+        assert!(odd.is_empty());
+
+        let (even, _) = encode_vobsub_rle(&pixels[6..], 300, 1);
+        assert_eq!(even, vec![0x00, 0x03]);
+    }
+
+    #[test]
+    fn quantization_keeps_three_most_common_colors() {
+        let mut rgba = Vec::new();
+        for (color, count) in [
+            ([0, 0, 0, 0], 10),
+            ([250, 250, 250, 255], 6),
+            ([10, 10, 10, 255], 4),
+            ([240, 20, 20, 128], 3),
+            ([200, 30, 30, 128], 1),
+        ] {
+            for _ in 0..count {
+                rgba.extend_from_slice(&color);
+            }
+        }
+
+        let quantized = quantize_to_subpicture(&rgba, &palette());
+        assert_eq!(quantized.color_indices, [0, 1, 2, 3]);
+        assert_eq!(quantized.alpha_values, [0, 15, 15, 8]);
+        assert_eq!(quantized.pixels[0], 0);
+        assert_eq!(*quantized.pixels.last().unwrap(), 3);
+    }
+
+    #[test]
+    fn encoded_stream_roundtrips_through_parser() {
+        let width = 40u16;
+        let height = 30u16;
+        let mut rgba = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                let pixel = match (x + y) % 3 {
+                    0 => [0, 0, 0, 0],
+                    1 => [255, 255, 255, 255],
+                    _ => [255, 0, 0, 255],
+                };
+                rgba.extend_from_slice(&pixel);
+            }
+        }
+        let bitmap = VobSubBitmap {
+            x: 100,
+            y: 400,
+            width,
+            height,
+            rgba: rgba.clone(),
+            forced: true,
+        };
+
+        let mut encoder = VobSubEncoder::new(720, 480, palette()).with_track("de", 1);
+        encoder.add_cue(1000, 2500, &bitmap).unwrap();
+        encoder.add_cue(3000, 4000, &bitmap).unwrap();
+        assert!(encoder.add_cue(2000, 5000, &bitmap).is_err());
+        let encoded = encoder.finish();
+        assert_eq!(encoded.sub_data.len() % DVD_SECTOR_SIZE, 0);
+
+        let mut parser = VobSubParser::new();
+        parser.load_from_data(&encoded.idx_content, encoded.sub_data);
+        assert_eq!(parser.language(), "de");
+        assert_eq!(parser.get_timestamps(), vec![1000.0, 3000.0]);
+        // Stop delays are in 1024/90 ms ticks
+        assert!((parser.get_cue_end_time(0) - 2500.0).abs() < 12.0);
+        assert!(parser.is_cue_forced(1));
+
+        let frame = parser.render_at_index(0).expect("frame");
+        assert_eq!((frame.x(), frame.y()), (100, 400));
+        assert_eq!((frame.width(), frame.height()), (width, height));
+        assert_eq!(visible(frame.get_rgba()), visible(&rgba));
+    }
+
+    #[test]
+    fn large_subpictures_span_several_packs() {
+        let width = 700u16;
+        let height = 100u16;
+        // Alternating pixels defeat RLE so the unit needs several packs
+        let rgba: Vec<u8> = (0..width as usize * height as usize)
+            .flat_map(|i| {
+                if i % 2 == 0 {
+                    [255, 255, 255, 255]
+                } else {
+                    [0, 0, 0, 255]
+                }
+            })
+            .collect();
+        let bitmap = VobSubBitmap {
+            x: 0,
+            y: 0,
+            width,
+            height,
+            rgba: rgba.clone(),
+            forced: false,
+        };
+
+        let mut encoder = VobSubEncoder::new(720, 480, palette());
+        encoder.add_cue(0, 1000, &bitmap).unwrap();
+        let encoded = encoder.finish();
+        assert!(encoded.sub_data.len() > 8 * DVD_SECTOR_SIZE);
+        assert_eq!(encoded.sub_data.len() % DVD_SECTOR_SIZE, 0);
+
+        let mut parser = VobSubParser::new();
+        parser.load_from_data(&encoded.idx_content, encoded.sub_data);
+        let frame = parser.render_at_index(0).expect("frame");
+        assert_eq!(visible(frame.get_rgba()), visible(&rgba));
+    }
+}
@@ -34,6 +34,8 @@ pub struct VobSubTimestamp {
     pub timestamp_ms: u32,
     /// File position in the .sub file
     pub file_position: u64,
+    /// Display duration given by the container (Matroska `BlockDuration`)
+    pub duration_ms: Option<u32>,
 }
 
 /// VobSub metadata.
@@ -60,12 +62,109 @@ impl Default for VobSubMetadata {
     }
 }
 
+/// Replacement colours from the `custom colors:` directive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VobSubCustomColors {
+    /// Pixel values marked transparent by `tridx:` (one flag per 2-bit value)
+    pub transparent: [bool; 4],
+    /// RGBA colour per 2-bit pixel value (packed like `VobSubPalette::rgba`)
+    pub rgba: [u32; 4],
+}
+
+/// Display directives from the IDX header (VobSubSub / VSFilter extensions).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VobSubIdxSettings {
+    /// `forced subs: ON` - only forced subtitles should be shown
+    pub forced_subs: bool,
+    /// `custom colors: ON, ...` - replaces the packet palette lookup
+    pub custom_colors: Option<VobSubCustomColors>,
+    /// `alpha:` - global opacity in percent (0-100)
+    pub alpha_percent: u8,
+    /// `fadein/out:` - fade-in duration in milliseconds
+    pub fade_in_ms: u32,
+    /// `fadein/out:` - fade-out duration in milliseconds
+    pub fade_out_ms: u32,
+    /// `org:` - horizontal offset added to every subtitle
+    pub origin_x: i32,
+    /// `org:` - vertical offset added to every subtitle
+    pub origin_y: i32,
+    /// `scale:` - horizontal scale in percent
+    pub scale_x_percent: u32,
+    /// `scale:` - vertical scale in percent
+    pub scale_y_percent: u32,
+}
+
+impl Default for VobSubIdxSettings {
+    fn default() -> Self {
+        Self {
+            forced_subs: false,
+            custom_colors: None,
+            alpha_percent: 100,
+            fade_in_ms: 0,
+            fade_out_ms: 0,
+            origin_x: 0,
+            origin_y: 0,
+            scale_x_percent: 100,
+            scale_y_percent: 100,
+        }
+    }
+}
+
+impl VobSubIdxSettings {
+    /// Check whether `scale:` changes the subtitle size.
+    pub fn is_scaled(&self) -> bool {
+        self.scale_x_percent != 100 || self.scale_y_percent != 100
+    }
+}
+
+/// A subtitle track (one `id:` section) from an IDX file.
+#[derive(Debug, Clone, Default)]
+pub struct VobSubTrack {
+    /// Language code from the `id:` line
+    pub language: Option<String>,
+    /// Stream index from the `id:` line (sub-stream 0x20 + index)
+    pub index: u32,
+    /// Timestamp entries of this track, with `delay:` applied
+    pub timestamps: Vec<VobSubTimestamp>,
+    /// Last `delay:` shift in effect for this track, in milliseconds
+    pub delay_ms: i32,
+}
+
 /// Parsed IDX file data.
 #[derive(Debug, Clone)]
 pub struct IdxParseResult {
     pub palette: VobSubPalette,
+    /// Timestamps of the selected track
     pub timestamps: Vec<VobSubTimestamp>,
+    /// Metadata, with language and ID of the selected track
     pub metadata: VobSubMetadata,
+    /// All tracks in file order
+    pub tracks: Vec<VobSubTrack>,
+    /// Track selected by `langidx:` (position in `tracks`)
+    pub default_track: usize,
+    /// Currently selected track (position in `tracks`)
+    pub selected_track: usize,
+    /// Display directives
+    pub settings: VobSubIdxSettings,
+}
+
+impl IdxParseResult {
+    /// Select a track, updating `timestamps` and the metadata language/ID.
+    /// Returns false if the track does not exist.
+    pub fn select_track(&mut self, track: usize) -> bool {
+        let Some(selected) = self.tracks.get(track) else {
+            return false;
+        };
+        self.timestamps = selected.timestamps.clone();
+        self.metadata.language = selected.language.clone();
+        // The implicit track of an idx without `id:` lines has no ID
+        self.metadata.id = selected
+            .language
+            .as_ref()
+            .map(|_| selected.index.to_string());
+        self.selected_track = track;
+        true
+    }
 }
 
 /// Parse a VobSub IDX file.
@@ -74,7 +173,15 @@ pub fn parse_idx(idx_content: &str) -> IdxParseResult {
         palette: VobSubPalette::default(),
         timestamps: Vec::new(),
         metadata: VobSubMetadata::default(),
+        tracks: Vec::new(),
+        default_track: 0,
+        selected_track: 0,
+        settings: VobSubIdxSettings::default(),
     };
+    let mut langidx: Option<u32> = None;
+    // `delay:` before the first `id:` applies to every track
+    let mut global_delay_ms: i64 = 0;
+    let mut delay_ms: i64 = 0;
 
     for line in idx_content.lines() {
         let trimmed = line.trim();
@@ -97,33 +204,105 @@ pub fn parse_idx(idx_content: &str) -> IdxParseResult {
         if let Some(rest) = trimmed.strip_prefix("palette:") {
             let colors: Vec<&str> = rest.split(',').map(|s| s.trim()).collect();
             for (i, color_hex) in colors.iter().enumerate().take(16) {
-                let hex = color_hex.trim_start_matches('#');
-                if hex.len() == 6
-                    && let Ok(rgb) = u32::from_str_radix(hex, 16)
-                {
-                    let r = ((rgb >> 16) & 0xFF) as u8;
-                    let g = ((rgb >> 8) & 0xFF) as u8;
-                    let b = (rgb & 0xFF) as u8;
-                    result.palette.rgba[i] = rgb_to_rgba(r, g, b, 255);
+                if let Some(rgba) = parse_hex_color(color_hex) {
+                    result.palette.rgba[i] = rgba;
                 }
             }
             continue;
         }
 
-        // Parse language ID
-        if let Some(rest) = trimmed.strip_prefix("id:") {
-            let parts: Vec<&str> = rest.split(',').collect();
-            if !parts.is_empty() {
-                result.metadata.language = Some(parts[0].trim().to_string());
+        // Parse time shift for the following timestamps (cumulative)
+        if let Some(rest) = trimmed.strip_prefix("delay:") {
+            if let Some(delay) = parse_idx_time(rest.trim()) {
+                delay_ms += delay;
+                match result.tracks.last_mut() {
+                    Some(track) => track.delay_ms = delay_ms as i32,
+                    None => global_delay_ms = delay_ms,
+                }
             }
-            if let Some(idx_part) = parts.get(1)
-                && let Some(idx_str) = idx_part.trim().strip_prefix("index:")
+            continue;
+        }
+
+        if let Some(rest) = trimmed.strip_prefix("forced subs:") {
+            result.settings.forced_subs = rest.trim().eq_ignore_ascii_case("on");
+            continue;
+        }
+
+        // Format: custom colors: ON, tridx: 1000, colors: 000000, ffffff, 808080, 404040
+        if let Some(rest) = trimmed.strip_prefix("custom colors:") {
+            result.settings.custom_colors = parse_custom_colors(rest);
+            continue;
+        }
+
+        if let Some(rest) = trimmed.strip_prefix("alpha:") {
+            if let Some(alpha) = parse_percent(rest) {
+                result.settings.alpha_percent = alpha.min(100) as u8;
+            }
+            continue;
+        }
+
+        if let Some(rest) = trimmed.strip_prefix("fadein/out:") {
+            if let Some((fade_in, fade_out)) = rest.split_once(',')
+                && let (Ok(fade_in), Ok(fade_out)) = (
+                    fade_in.trim().parse::<u32>(),
+                    fade_out.trim().parse::<u32>(),
+                )
+            {
+                result.settings.fade_in_ms = fade_in;
+                result.settings.fade_out_ms = fade_out;
+            }
+            continue;
+        }
+
+        if let Some(rest) = trimmed.strip_prefix("org:") {
+            if let Some((x, y)) = rest.split_once(',')
+                && let (Ok(x), Ok(y)) = (x.trim().parse::<i32>(), y.trim().parse::<i32>())
+            {
+                result.settings.origin_x = x;
+                result.settings.origin_y = y;
+            }
+            continue;
+        }
+
+        if let Some(rest) = trimmed.strip_prefix("scale:") {
+            if let Some((x, y)) = rest.split_once(',')
+                && let (Some(x), Some(y)) = (parse_percent(x), parse_percent(y))
             {
-                result.metadata.id = Some(idx_str.trim().to_string());
+                result.settings.scale_x_percent = x;
+                result.settings.scale_y_percent = y;
             }
             continue;
         }
 
+        // Parse default language index
+        if let Some(rest) = trimmed.strip_prefix("langidx:") {
+            langidx = rest.trim().parse::<u32>().ok();
+            continue;
+        }
+
+        // Parse language ID: starts a new track
+        if let Some(rest) = trimmed.strip_prefix("id:") {
+            let parts: Vec<&str> = rest.split(',').collect();
+            let language = parts
+                .first()
+                .map(|language| language.trim())
+                .filter(|language| !language.is_empty())
+                .map(str::to_string);
+            let index = parts
+                .get(1)
+                .and_then(|idx_part| idx_part.trim().strip_prefix("index:"))
+                .and_then(|idx_str| idx_str.trim().parse::<u32>().ok())
+                .unwrap_or(result.tracks.len() as u32);
+            delay_ms = global_delay_ms;
+            result.tracks.push(VobSubTrack {
+                language,
+                index,
+                timestamps: Vec::new(),
+                delay_ms: delay_ms as i32,
+            });
+            continue;
+        }
+
         // Parse timestamp entries
         // Format: timestamp: HH:MM:SS:mmm, filepos: XXXXXXXX
         if let Some(rest) = trimmed.strip_prefix("timestamp:")
@@ -137,32 +316,113 @@ pub fn parse_idx(idx_content: &str) -> IdxParseResult {
 
             if let Some(filepos_hex) = filepos_str {
                 // Parse timestamp HH:MM:SS:mmm
-                let parts: Vec<&str> = time_str.split(':').collect();
-                if parts.len() == 4
-                    && let (Ok(h), Ok(m), Ok(s), Ok(ms)) = (
-                        parts[0].parse::<u32>(),
-                        parts[1].parse::<u32>(),
-                        parts[2].parse::<u32>(),
-                        parts[3].parse::<u32>(),
-                    )
-                {
-                    let timestamp_ms = h * 3600000 + m * 60000 + s * 1000 + ms;
+                if let Some(time_ms) = parse_idx_time(time_str) {
+                    let timestamp_ms = (time_ms + delay_ms).clamp(0, u32::MAX as i64) as u32;
 
                     // Parse file position (hex)
                     if let Ok(file_position) = u64::from_str_radix(filepos_hex, 16) {
-                        result.timestamps.push(VobSubTimestamp {
-                            timestamp_ms,
-                            file_position,
-                        });
+                        // Timestamps before any `id:` line belong to an implicit track
+                        if result.tracks.is_empty() {
+                            result.tracks.push(VobSubTrack {
+                                delay_ms: delay_ms as i32,
+                                ..VobSubTrack::default()
+                            });
+                        }
+                        if let Some(track) = result.tracks.last_mut() {
+                            track.timestamps.push(VobSubTimestamp {
+                                timestamp_ms,
+                                file_position,
+                                duration_ms: None,
+                            });
+                        }
                     }
                 }
             }
         }
     }
 
+    result.default_track = langidx
+        .and_then(|langidx| {
+            result
+                .tracks
+                .iter()
+                .position(|track| track.index == langidx)
+        })
+        .unwrap_or(0);
+    result.select_track(result.default_track);
     result
 }
 
+/// Parse an IDX time `[-]HH:MM:SS:mmm` into signed milliseconds.
+fn parse_idx_time(value: &str) -> Option<i64> {
+    let (sign, value) = match value.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let parts: Vec<&str> = value.split(':').collect();
+    if parts.len() != 4 {
+        return None;
+    }
+    let h = parts[0].trim().parse::<i64>().ok()?;
+    let m = parts[1].trim().parse::<i64>().ok()?;
+    let s = parts[2].trim().parse::<i64>().ok()?;
+    let ms = parts[3].trim().parse::<i64>().ok()?;
+    Some(sign * (h * 3600000 + m * 60000 + s * 1000 + ms))
+}
+
+/// Parse a `NN%` value.
+fn parse_percent(value: &str) -> Option<u32> {
+    value
+        .trim()
+        .trim_end_matches('%')
+        .trim()
+        .parse::<u32>()
+        .ok()
+}
+
+/// Parse a 6-digit hex RGB colour into packed RGBA.
+fn parse_hex_color(value: &str) -> Option<u32> {
+    let hex = value.trim().trim_start_matches('#');
+    if hex.len() != 6 {
+        return None;
+    }
+    let rgb = u32::from_str_radix(hex, 16).ok()?;
+    Some(rgb_to_rgba(
+        ((rgb >> 16) & 0xFF) as u8,
+        ((rgb >> 8) & 0xFF) as u8,
+        (rgb & 0xFF) as u8,
+        255,
+    ))
+}
+
+/// Parse the body of a `custom colors:` line. Returns `None` unless it is ON.
+fn parse_custom_colors(value: &str) -> Option<VobSubCustomColors> {
+    let (state, rest) = value.split_once(',').unwrap_or((value, ""));
+    if !state.trim().eq_ignore_ascii_case("on") {
+        return None;
+    }
+
+    let mut custom = VobSubCustomColors {
+        transparent: [false; 4],
+        rgba: [0; 4],
+    };
+    let (tridx, colors) = rest.split_once("colors:")?;
+    if let Some(tridx) = tridx
+        .trim()
+        .trim_end_matches(',')
+        .trim()
+        .strip_prefix("tridx:")
+    {
+        for (flag, bit) in custom.transparent.iter_mut().zip(tridx.trim().chars()) {
+            *flag = bit == '1';
+        }
+    }
+    for (color, hex) in custom.rgba.iter_mut().zip(colors.split(',')) {
+        *color = parse_hex_color(hex)?;
+    }
+    Some(custom)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -188,6 +448,75 @@ timestamp: 00:00:05:500, filepos: 00001000
         assert_eq!(result.timestamps[1].timestamp_ms, 5500);
     }
 
+    #[test]
+    fn test_parse_idx_multiple_tracks() {
+        let idx = r#"
+size: 720x576
+langidx: 1
+id: en, index: 0
+timestamp: 00:00:01:000, filepos: 00000000
+timestamp: 00:00:02:000, filepos: 00000800
+id: de, index: 1
+timestamp: 00:00:01:500, filepos: 00001000
+id: fr, index: 2
+"#;
+
+        let mut result = parse_idx(idx);
+
+        assert_eq!(result.tracks.len(), 3);
+        assert_eq!(result.tracks[0].timestamps.len(), 2);
+        assert_eq!(result.tracks[2].timestamps.len(), 0);
+        assert_eq!(result.default_track, 1);
+        assert_eq!(result.metadata.language.as_deref(), Some("de"));
+        assert_eq!(result.metadata.id.as_deref(), Some("1"));
+        assert_eq!(result.timestamps.len(), 1);
+        assert_eq!(result.timestamps[0].file_position, 0x1000);
+
+        assert!(result.select_track(0));
+        assert_eq!(result.metadata.language.as_deref(), Some("en"));
+        assert_eq!(result.timestamps[1].timestamp_ms, 2000);
+        assert!(!result.select_track(3));
+    }
+
+    #[test]
+    fn test_parse_idx_display_directives() {
+        let idx = r#"
+delay: 0:00:01:000
+forced subs: ON
+custom colors: ON, tridx: 1000, colors: 000000, ffffff, 800000, 000080
+alpha: 50%
+fadein/out: 120, 80
+org: 10, -4
+scale: 150%, 50%
+id: en, index: 0
+timestamp: 00:00:02:000, filepos: 00000000
+delay: -0:00:00:500
+timestamp: 00:00:04:000, filepos: 00000800
+id: de, index: 1
+timestamp: 00:00:00:000, filepos: 00001000
+"#;
+
+        let result = parse_idx(idx);
+        let settings = &result.settings;
+
+        assert!(settings.forced_subs);
+        assert_eq!(settings.alpha_percent, 50);
+        assert_eq!((settings.fade_in_ms, settings.fade_out_ms), (120, 80));
+        assert_eq!((settings.origin_x, settings.origin_y), (10, -4));
+        assert_eq!(
+            (settings.scale_x_percent, settings.scale_y_percent),
+            (150, 50)
+        );
+        let custom = settings.custom_colors.expect("custom colors");
+        assert_eq!(custom.transparent, [true, false, false, false]);
+        assert_eq!(custom.rgba[2], rgb_to_rgba(0x80, 0, 0, 255));
+
+        assert_eq!(result.tracks[0].timestamps[0].timestamp_ms, 3000);
+        assert_eq!(result.tracks[0].timestamps[1].timestamp_ms, 4500);
+        assert_eq!(result.tracks[0].delay_ms, 500);
+        assert_eq!(result.tracks[1].timestamps[0].timestamp_ms, 1000);
+    }
+
     #[test]
     fn test_parse_real_vobsub_durations() {
         use crate::vobsub::parse_subtitle_packet;
@@ -3,8 +3,11 @@
 //! This module implements the VobSub subtitle format (.idx + .sub files).
 
 mod deband;
+mod dvd;
+mod encoder;
 mod idx_parser;
 mod mks_parser;
+mod palette;
 mod rle;
 mod sub_parser;
 mod vobsub_parser;
@@ -13,8 +16,11 @@ pub(crate) const MAX_VOBSUB_IMAGE_PIXELS: usize = 16_777_216;
 
 pub use deband::*;
 
+pub use dvd::*;
+pub use encoder::*;
 pub use idx_parser::*;
 pub use mks_parser::*;
+pub use palette::*;
 pub use rle::*;
 pub use sub_parser::*;
 pub use vobsub_parser::*;
@@ -0,0 +1,230 @@
+//! Palette estimation for VobSub streams without an IDX palette.
+//!
+//! Bare .sub files only carry palette indices. Each packet's 2-bit pixel
+//! values are classified as background, fill, outline or anti-alias from their
+//! alpha and from how often they touch the background, and the palette
+//! indices they reference are given matching colours.
+
+use super::{SubtitlePacket, VobSubPalette, decode_vobsub_pixels};
+
+/// Colour given to indices used as the transparent background.
+const BACKGROUND_COLOR: u32 = 0x00000000;
+/// Colour given to indices used for the glyph body.
+const FILL_COLOR: u32 = 0xFFFFFFFF;
+/// Colour given to indices used for the glyph outline.
+const OUTLINE_COLOR: u32 = 0xFF000000;
+/// Colour given to indices used for anti-aliasing between fill and outline.
+const ANTI_ALIAS_COLOR: u32 = 0xFF808080;
+/// Alpha (0-15) below which a visible value is treated as anti-aliasing.
+const OPAQUE_ALPHA: u8 = 15;
+
+/// Role of a 2-bit pixel value within a subpicture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VobSubColorRole {
+    Background,
+    Fill,
+    Outline,
+    AntiAlias,
+}
+
+impl VobSubColorRole {
+    const ALL: [VobSubColorRole; 4] = [
+        VobSubColorRole::Background,
+        VobSubColorRole::Fill,
+        VobSubColorRole::Outline,
+        VobSubColorRole::AntiAlias,
+    ];
+
+    /// Plausible RGBA colour (packed like `VobSubPalette`) for this role.
+    pub fn color(self) -> u32 {
+        match self {
+            VobSubColorRole::Background => BACKGROUND_COLOR,
+            VobSubColorRole::Fill => FILL_COLOR,
+            VobSubColorRole::Outline => OUTLINE_COLOR,
+            VobSubColorRole::AntiAlias => ANTI_ALIAS_COLOR,
+        }
+    }
+}
+
+/// Accumulates role votes per palette index over many packets.
+#[derive(Debug, Clone, Default)]
+pub struct VobSubPaletteEstimator {
+    /// Pixel-weighted votes, indexed by palette index then role
+    votes: [[u64; 4]; 16],
+}
+
+impl VobSubPaletteEstimator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Classify the pixel values of one packet and record a vote for each
+    /// palette index it uses.
+    pub fn add_packet(&mut self, packet: &SubtitlePacket, sub_data: &[u8]) {
+        let pixels = decode_vobsub_pixels(packet, sub_data);
+        if pixels.is_empty() {
+            return;
+        }
+        let roles = classify_values(&pixels, packet.width as usize, &packet.alpha_values);
+
+        let mut counts = [0u64; 4];
+        for &value in &pixels {
+            counts[value as usize & 0x03] += 1;
+        }
+        for (value, role) in roles.iter().enumerate() {
+            if let Some(role) = role {
+                let index = packet.color_indices[value] as usize & 0x0F;
+                self.votes[index][*role as usize] += counts[value];
+            }
+        }
+    }
+
+    /// Role chosen for a palette index, or `None` if no packet used it.
+    pub fn role(&self, index: usize) -> Option<VobSubColorRole> {
+        let votes = self.votes.get(index)?;
+        let (role, &count) = votes
+            .iter()
+            .enumerate()
+            .max_by_key(|(role, count)| (**count, std::cmp::Reverse(*role)))?;
+        (count > 0).then_some(VobSubColorRole::ALL[role])
+    }
+
+    /// Build a palette, keeping `fallback` colours for unused indices.
+    pub fn palette(&self, fallback: &VobSubPalette) -> VobSubPalette {
+        let mut palette = fallback.clone();
+        for (index, color) in palette.rgba.iter_mut().enumerate() {
+            if let Some(role) = self.role(index) {
+                *color = role.color();
+            }
+        }
+        palette
+    }
+}
+
+/// Assign a role to each used 2-bit value of a bitmap.
+///
+/// Fully transparent values are background (or, without one, the most common
+/// value). Of the visible values, the one touching the background least is the
+/// fill and the one touching it most is the outline; a remaining or partially
+/// transparent value is anti-aliasing.
+fn classify_values(
+    pixels: &[u8],
+    width: usize,
+    alpha_values: &[u8; 4],
+) -> [Option<VobSubColorRole>; 4] {
+    let mut counts = [0u64; 4];
+    for &value in pixels {
+        counts[value as usize & 0x03] += 1;
+    }
+
+    let mut is_background = [false; 4];
+    for value in 0..4 {
+        is_background[value] = counts[value] > 0 && alpha_values[value] == 0;
+    }
+    if !is_background.contains(&true) {
+        // Opaque box: the dominant value is the background
+        let (dominant, _) = counts
+            .iter()
+            .enumerate()
+            .max_by_key(|(value, count)| (**count, std::cmp::Reverse(*value)))
+            .expect("four values");
+        is_background[dominant] = true;
+    }
+
+    // Count horizontal and vertical neighbours that are background (or outside)
+    let height = pixels.len() / width.max(1);
+    let mut edge_touches = [0u64; 4];
+    for y in 0..height {
+        for x in 0..width {
+            let value = pixels[y * width + x] as usize & 0x03;
+            if is_background[value] {
+                continue;
+            }
+            let neighbours = [
+                (x > 0).then(|| pixels[y * width + x - 1]),
+                (x + 1 < width).then(|| pixels[y * width + x + 1]),
+                (y > 0).then(|| pixels[(y - 1) * width + x]),
+                (y + 1 < height).then(|| pixels[(y + 1) * width + x]),
+            ];
+            if neighbours
+                .iter()
+                .any(|n| n.is_none_or(|n| is_background[n as usize & 0x03]))
+            {
+                edge_touches[value] += 1;
+            }
+        }
+    }
+
+    let mut roles = [None; 4];
+    let mut visible: Vec<(usize, u64)> = Vec::new();
+    for value in 0..4 {
+        if counts[value] == 0 {
+            continue;
+        }
+        if is_background[value] {
+            roles[value] = Some(VobSubColorRole::Background);
+        } else {
+            // Share of pixels on the background boundary, in 1/1000ths
+            visible.push((value, edge_touches[value] * 1000 / counts[value]));
+        }
+    }
+
+    let full_alpha = visible
+        .iter()
+        .any(|&(value, _)| alpha_values[value] >= OPAQUE_ALPHA);
+    visible.retain(|&(value, _)| {
+        if full_alpha && alpha_values[value] < OPAQUE_ALPHA {
+            roles[value] = Some(VobSubColorRole::AntiAlias);
+            false
+        } else {
+            true
+        }
+    });
+    visible.sort_by_key(|&(value, ratio)| (ratio, value));
+
+    if let Some(&(fill, _)) = visible.first() {
+        roles[fill] = Some(VobSubColorRole::Fill);
+    }
+    if visible.len() > 1
+        && let Some(&(outline, _)) = visible.last()
+    {
+        roles[outline] = Some(VobSubColorRole::Outline);
+    }
+    for &(value, _) in visible.iter().skip(1).take(visible.len().saturating_sub(2)) {
+        roles[value] = Some(VobSubColorRole::AntiAlias);
+    }
+
+    roles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_outlined_glyph() {
+        // 7x5: background (0), outline (2) around a fill (1) bar
+        #[rustfmt::skip]
+        let pixels = [
+            0, 0, 0, 0, 0, 0, 0,
+            0, 2, 2, 2, 2, 2, 0,
+            0, 2, 1, 1, 1, 2, 0,
+            0, 2, 2, 2, 2, 2, 0,
+            0, 0, 0, 0, 0, 0, 0,
+        ];
+        let roles = classify_values(&pixels, 7, &[0, 15, 15, 15]);
+        assert_eq!(
+            roles,
+            [
+                Some(VobSubColorRole::Background),
+                Some(VobSubColorRole::Fill),
+                Some(VobSubColorRole::Outline),
+                None,
+            ]
+        );
+
+        let roles = classify_values(&pixels, 7, &[0, 15, 8, 15]);
+        assert_eq!(roles[1], Some(VobSubColorRole::Fill));
+        assert_eq!(roles[2], Some(VobSubColorRole::AntiAlias));
+    }
+}
@@ -2,14 +2,11 @@
 //!
 //! DVD subtitles use 2-bit RLE encoding with interlaced fields.
 
-use super::{MAX_VOBSUB_IMAGE_PIXELS, SubtitlePacket, VobSubPalette};
+use super::{MAX_VOBSUB_IMAGE_PIXELS, SubtitlePacket, VobSubIdxSettings, VobSubPalette};
 
-/// Decode VobSub RLE-encoded bitmap and render to RGBA.
-pub fn decode_vobsub_rle(
-    packet: &SubtitlePacket,
-    sub_data: &[u8],
-    palette: &VobSubPalette,
-) -> Vec<u8> {
+/// Decode the 2-bit pixel values (0-3) of both fields, row-major.
+/// Returns an empty buffer for empty or oversized bitmaps.
+pub fn decode_vobsub_pixels(packet: &SubtitlePacket, sub_data: &[u8]) -> Vec<u8> {
     let width = packet.width as usize;
     let height = packet.height as usize;
 
@@ -24,55 +21,140 @@ pub fn decode_vobsub_rle(
         return Vec::new();
     }
 
-    let Some(rgba_len) = pixel_count.checked_mul(4) else {
-        return Vec::new();
-    };
-
-    // Allocate RGBA buffer
-    let mut rgba = vec![0u8; rgba_len];
-
-    // Build 4-color lookup table with alpha
-    let mut colors = [[0u8; 4]; 4];
-    for (i, color) in colors.iter_mut().enumerate() {
-        let palette_color = palette.rgba[packet.color_indices[i] as usize];
-        let alpha = ((packet.alpha_values[i] as u32 * 255) / 15) as u8;
-
-        // Extract RGBA from packed u32 (little-endian: R, G, B, A)
-        let bytes = palette_color.to_le_bytes();
-        *color = [bytes[0], bytes[1], bytes[2], alpha];
-    }
+    let mut pixels = vec![0u8; pixel_count];
 
     // Decode even field (lines 0, 2, 4, ...)
     decode_field(
         packet.even_field_data(sub_data),
-        &mut rgba,
+        &mut pixels,
         width,
         height,
         0,
-        &colors,
     );
 
     // Decode odd field (lines 1, 3, 5, ...)
     decode_field(
         packet.odd_field_data(sub_data),
-        &mut rgba,
+        &mut pixels,
         width,
         height,
         1,
-        &colors,
     );
 
+    pixels
+}
+
+/// Decode VobSub RLE-encoded bitmap and render to RGBA.
+pub fn decode_vobsub_rle(
+    packet: &SubtitlePacket,
+    sub_data: &[u8],
+    palette: &VobSubPalette,
+) -> Vec<u8> {
+    decode_vobsub_rle_with_settings(packet, sub_data, palette, &VobSubIdxSettings::default())
+}
+
+/// Decode VobSub RLE-encoded bitmap and render to RGBA, honouring the
+/// `custom colors:` and `alpha:` IDX directives.
+pub fn decode_vobsub_rle_with_settings(
+    packet: &SubtitlePacket,
+    sub_data: &[u8],
+    palette: &VobSubPalette,
+    settings: &VobSubIdxSettings,
+) -> Vec<u8> {
+    let width = packet.width as usize;
+
+    // Decode 2-bit pixel values, then map them through the colour tables
+    let pixels = decode_vobsub_pixels(packet, sub_data);
+    if pixels.is_empty() {
+        return Vec::new();
+    }
+
+    let mut rgba = vec![0u8; pixels.len() * 4];
+    let colors = build_color_table(
+        &packet.color_indices,
+        &packet.alpha_values,
+        palette,
+        settings,
+    );
+
+    if packet.color_controls.is_empty() {
+        for (pixel, value) in rgba.chunks_exact_mut(4).zip(&pixels) {
+            pixel.copy_from_slice(&colors[*value as usize]);
+        }
+        return rgba;
+    }
+
+    // CHG_COLCON: bands of screen lines with per-column colour tables
+    for (y, (row_rgba, row_pixels)) in rgba
+        .chunks_exact_mut(width * 4)
+        .zip(pixels.chunks_exact(width))
+        .enumerate()
+    {
+        let line = packet.y.saturating_add(y as u16);
+        let control = packet.color_control_at_line(line);
+        let mut column_colors = colors;
+        let mut current_column = None;
+
+        for (x, (pixel, value)) in row_rgba.chunks_exact_mut(4).zip(row_pixels).enumerate() {
+            if let Some(control) = control {
+                let column = packet.x.saturating_add(x as u16);
+                let entry = control.column_at(column);
+                let entry_start = entry.map(|entry| entry.start_column);
+                if entry_start != current_column {
+                    current_column = entry_start;
+                    column_colors = match entry {
+                        Some(entry) => build_color_table(
+                            &entry.color_indices,
+                            &entry.alpha_values,
+                            palette,
+                            settings,
+                        ),
+                        None => colors,
+                    };
+                }
+            }
+            pixel.copy_from_slice(&column_colors[*value as usize]);
+        }
+    }
+
     rgba
 }
 
-/// Decode a single field (even or odd lines).
+/// Build the 4-entry RGBA lookup table for a set of palette indices and alpha values.
+fn build_color_table(
+    color_indices: &[u8; 4],
+    alpha_values: &[u8; 4],
+    palette: &VobSubPalette,
+    settings: &VobSubIdxSettings,
+) -> [[u8; 4]; 4] {
+    let mut colors = [[0u8; 4]; 4];
+    let global_alpha = settings.alpha_percent.min(100) as u32;
+    for (i, color) in colors.iter_mut().enumerate() {
+        // Custom colors map the 2-bit pixel value directly, bypassing the palette
+        let (palette_color, transparent) = match &settings.custom_colors {
+            Some(custom) => (custom.rgba[i], custom.transparent[i]),
+            None => (palette.rgba[color_indices[i] as usize & 0x0F], false),
+        };
+        let alpha = if transparent {
+            0
+        } else {
+            ((alpha_values[i] as u32 * 255 * global_alpha) / (15 * 100)) as u8
+        };
+
+        // Extract RGBA from packed u32 (little-endian: R, G, B, A)
+        let bytes = palette_color.to_le_bytes();
+        *color = [bytes[0], bytes[1], bytes[2], alpha];
+    }
+    colors
+}
+
+/// Decode a single field (even or odd lines) into 2-bit pixel values.
 fn decode_field(
     field_data: &[u8],
-    rgba: &mut [u8],
+    pixels: &mut [u8],
     width: usize,
     height: usize,
     start_line: usize,
-    colors: &[[u8; 4]; 4],
 ) {
     let mut byte_pos = 0;
     let mut nibble_pos = 0; // 0 = high nibble, 1 = low nibble
@@ -91,9 +173,9 @@ fn decode_field(
         if byte_pos >= field_data.len() {
             // Fill remaining lines with transparent
             while x < width {
-                let pixel_offset = (y * width + x) * 4;
-                if pixel_offset + 4 <= rgba.len() {
-                    rgba[pixel_offset..pixel_offset + 4].copy_from_slice(&colors[0]);
+                let pixel_offset = y * width + x;
+                if pixel_offset < pixels.len() {
+                    pixels[pixel_offset] = 0;
                 }
                 x += 1;
             }
@@ -116,9 +198,9 @@ fn decode_field(
                 byte_pos += 1;
                 nibble_pos = 0;
                 while x < width {
-                    let pixel_offset = (y * width + x) * 4;
-                    if pixel_offset + 4 <= rgba.len() {
-                        rgba[pixel_offset..pixel_offset + 4].copy_from_slice(&colors[0]);
+                    let pixel_offset = y * width + x;
+                    if pixel_offset < pixels.len() {
+                        pixels[pixel_offset] = 0;
                     }
                     x += 1;
                 }
@@ -127,11 +209,10 @@ fn decode_field(
 
             // End of line (run_length == 0)
             if run_length == 0 {
-                let color = &colors[color_idx];
                 while x < width {
-                    let pixel_offset = (y * width + x) * 4;
-                    if pixel_offset + 4 <= rgba.len() {
-                        rgba[pixel_offset..pixel_offset + 4].copy_from_slice(color);
+                    let pixel_offset = y * width + x;
+                    if pixel_offset < pixels.len() {
+                        pixels[pixel_offset] = color_idx as u8;
                     }
                     x += 1;
                 }
@@ -140,11 +221,10 @@ fn decode_field(
 
             // Fill pixels
             let end_x = (x + run_length).min(width);
-            let color = &colors[color_idx];
             while x < end_x {
-                let pixel_offset = (y * width + x) * 4;
-                if pixel_offset + 4 <= rgba.len() {
-                    rgba[pixel_offset..pixel_offset + 4].copy_from_slice(color);
+                let pixel_offset = y * width + x;
+                if pixel_offset < pixels.len() {
+                    pixels[pixel_offset] = color_idx as u8;
                 }
                 x += 1;
             }
@@ -152,9 +232,9 @@ fn decode_field(
 
         // Fill any remaining pixels
         while x < width {
-            let pixel_offset = (y * width + x) * 4;
-            if pixel_offset + 4 <= rgba.len() {
-                rgba[pixel_offset..pixel_offset + 4].copy_from_slice(&colors[0]);
+            let pixel_offset = y * width + x;
+            if pixel_offset < pixels.len() {
+                pixels[pixel_offset] = 0;
             }
             x += 1;
         }
@@ -267,6 +347,11 @@ mod tests {
             height: 5000,
             color_indices: [0, 1, 2, 3],
             alpha_values: [0, 15, 15, 15],
+            forced: false,
+            stream_id: 0x20,
+            color_controls: Vec::new(),
+            control_sequences: Vec::new(),
+            sub_cues: Vec::new(),
             packet_data: crate::vobsub::SubtitlePacketData::Owned(Vec::new()),
             even_field_range: 0..0,
             odd_field_range: 0..0,
@@ -286,6 +371,11 @@ mod tests {
             height: 1,
             color_indices: [0, 1, 2, 3],
             alpha_values: [0, 15, 15, 15],
+            forced: false,
+            stream_id: 0x20,
+            color_controls: Vec::new(),
+            control_sequences: Vec::new(),
+            sub_cues: Vec::new(),
             packet_data: SubtitlePacketData::Owned(vec![0x00, 0x02]),
             even_field_range: 0..2,
             odd_field_range: 0..0,
@@ -299,4 +389,80 @@ mod tests {
             assert_eq!(pixel, [0, 0, 255, 255]);
         }
     }
+
+    #[test]
+    fn test_decode_vobsub_rle_with_custom_colors_and_alpha() {
+        let packet = SubtitlePacket {
+            timestamp_ms: 0,
+            duration_ms: 1000,
+            x: 0,
+            y: 0,
+            width: 2,
+            height: 1,
+            color_indices: [0, 1, 2, 3],
+            alpha_values: [15, 15, 15, 15],
+            forced: false,
+            stream_id: 0x20,
+            color_controls: Vec::new(),
+            control_sequences: Vec::new(),
+            sub_cues: Vec::new(),
+            // Nibbles: run 1 color 0, run 1 color 2
+            packet_data: SubtitlePacketData::Owned(vec![0x46]),
+            even_field_range: 0..1,
+            odd_field_range: 0..0,
+        };
+        let settings = VobSubIdxSettings {
+            alpha_percent: 50,
+            custom_colors: Some(crate::vobsub::VobSubCustomColors {
+                transparent: [true, false, false, false],
+                rgba: [0, 0, crate::utils::rgb_to_rgba(255, 0, 0, 255), 0],
+            }),
+            ..VobSubIdxSettings::default()
+        };
+
+        let rgba =
+            decode_vobsub_rle_with_settings(&packet, &[], &VobSubPalette::default(), &settings);
+
+        assert_eq!(&rgba[..4], [0, 0, 0, 0]);
+        assert_eq!(&rgba[4..], [255, 0, 0, 127]);
+    }
+
+    #[test]
+    fn test_decode_vobsub_rle_applies_chg_colcon_columns() {
+        let packet = SubtitlePacket {
+            timestamp_ms: 0,
+            duration_ms: 1000,
+            x: 10,
+            y: 4,
+            width: 2,
+            height: 1,
+            color_indices: [0, 1, 2, 3],
+            alpha_values: [15, 15, 15, 15],
+            forced: false,
+            stream_id: 0x20,
+            color_controls: vec![crate::vobsub::VobSubColorControl {
+                start_line: 4,
+                end_line: 4,
+                columns: vec![crate::vobsub::VobSubColumnControl {
+                    start_column: 11,
+                    color_indices: [0, 5, 0, 0],
+                    alpha_values: [15, 8, 15, 15],
+                }],
+            }],
+            control_sequences: Vec::new(),
+            sub_cues: Vec::new(),
+            // Nibbles: run 2 color 1
+            packet_data: SubtitlePacketData::Owned(vec![0x90]),
+            even_field_range: 0..1,
+            odd_field_range: 0..0,
+        };
+        let mut palette = VobSubPalette::default();
+        palette.rgba[1] = crate::utils::rgb_to_rgba(255, 255, 255, 255);
+        palette.rgba[5] = crate::utils::rgb_to_rgba(255, 255, 0, 255);
+
+        let rgba = decode_vobsub_rle(&packet, &[], &palette);
+
+        assert_eq!(&rgba[..4], [255, 255, 255, 255]);
+        assert_eq!(&rgba[4..], [255, 255, 0, 136]);
+    }
 }
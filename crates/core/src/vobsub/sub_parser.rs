@@ -13,6 +13,122 @@ pub enum SubtitlePacketData {
     Owned(Vec<u8>),
 }
 
+/// Column range of a CHG_COLCON line band with its own colours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VobSubColumnControl {
+    /// First screen column this entry applies to (until the next entry)
+    pub start_column: u16,
+    /// 4 color indices into the 16-color palette
+    pub color_indices: [u8; 4],
+    /// 4 alpha values (0-15)
+    pub alpha_values: [u8; 4],
+}
+
+/// A horizontal band of screen lines recoloured by CHG_COLCON (command 0x07).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VobSubColorControl {
+    /// First screen line of the band
+    pub start_line: u16,
+    /// Last screen line of the band (inclusive)
+    pub end_line: u16,
+    /// Column entries, sorted by start column
+    pub columns: Vec<VobSubColumnControl>,
+}
+
+impl VobSubColorControl {
+    /// Find the column entry covering a screen column.
+    pub fn column_at(&self, column: u16) -> Option<&VobSubColumnControl> {
+        self.columns
+            .iter()
+            .rev()
+            .find(|entry| entry.start_column <= column)
+    }
+}
+
+/// Display area from SET_DAREA (command 0x05).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VobSubArea {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+/// One display control sequence (DCSQ) of a subtitle packet.
+///
+/// Each field is `Some` only when the sequence contains the matching command.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VobSubControlSequence {
+    /// Delay from the packet PTS in milliseconds
+    pub delay_ms: u32,
+    /// FSTA_DSP (0x00)
+    pub force_display: bool,
+    /// STA_DSP (0x01)
+    pub start_display: bool,
+    /// STP_DSP (0x02)
+    pub stop_display: bool,
+    /// SET_COLOR (0x03)
+    pub color_indices: Option<[u8; 4]>,
+    /// SET_CONTR (0x04)
+    pub alpha_values: Option<[u8; 4]>,
+    /// SET_DAREA (0x05)
+    pub area: Option<VobSubArea>,
+    /// SET_DSPXA (0x06): top and bottom field offsets
+    pub field_offsets: Option<(u16, u16)>,
+    /// CHG_COLCON (0x07)
+    pub color_controls: Option<Vec<VobSubColorControl>>,
+}
+
+impl VobSubControlSequence {
+    /// Check whether the sequence changes what is drawn.
+    pub fn changes_state(&self) -> bool {
+        self.color_indices.is_some()
+            || self.alpha_values.is_some()
+            || self.area.is_some()
+            || self.field_offsets.is_some()
+            || self.color_controls.is_some()
+    }
+
+    fn apply_to(&self, state: &mut VobSubSubCue, field_offsets: &mut (usize, usize)) {
+        if let Some(color_indices) = self.color_indices {
+            state.color_indices = color_indices;
+        }
+        if let Some(alpha_values) = self.alpha_values {
+            state.alpha_values = alpha_values;
+        }
+        if let Some(area) = self.area {
+            state.x = area.x;
+            state.y = area.y;
+            state.width = area.width;
+            state.height = area.height;
+        }
+        if let Some((top, bottom)) = self.field_offsets {
+            *field_offsets = (top as usize, bottom as usize);
+        }
+        if let Some(color_controls) = &self.color_controls {
+            state.color_controls = color_controls.clone();
+        }
+    }
+}
+
+/// A sub-interval of a packet during which the displayed state is constant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VobSubSubCue {
+    /// Start, relative to the packet PTS, in milliseconds
+    pub start_offset_ms: u32,
+    /// End (exclusive), relative to the packet PTS, in milliseconds
+    pub end_offset_ms: u32,
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+    pub color_indices: [u8; 4],
+    pub alpha_values: [u8; 4],
+    pub color_controls: Vec<VobSubColorControl>,
+    pub(crate) even_field_range: Range<usize>,
+    pub(crate) odd_field_range: Range<usize>,
+}
+
 /// Parsed subtitle packet from the SUB file.
 #[derive(Debug, Clone)]
 pub struct SubtitlePacket {
@@ -32,6 +148,16 @@ pub struct SubtitlePacket {
     pub color_indices: [u8; 4],
     /// 4 alpha values (0-15, where 0 is transparent, 15 is opaque)
     pub alpha_values: [u8; 4],
+    /// Displayed with FSTA_DSP (forced start display, command 0x00)
+    pub forced: bool,
+    /// Private Stream 1 sub-stream id (0x20-0x3F)
+    pub stream_id: u8,
+    /// Per-region colour/contrast overrides from CHG_COLCON
+    pub color_controls: Vec<VobSubColorControl>,
+    /// All control sequences, in stream order
+    pub control_sequences: Vec<VobSubControlSequence>,
+    /// Display intervals; the fields above describe the first one
+    pub sub_cues: Vec<VobSubSubCue>,
     /// Underlying subtitle packet payload.
     pub(crate) packet_data: SubtitlePacketData,
     /// RLE-encoded pixel data range for the even field / top field.
@@ -57,15 +183,103 @@ impl SubtitlePacket {
         let packet = self.packet_slice(sub_data);
         &packet[self.odd_field_range.clone()]
     }
+
+    /// Build a renderable packet for one display interval.
+    pub fn sub_cue_packet(&self, index: usize) -> Option<SubtitlePacket> {
+        let sub_cue = self.sub_cues.get(index)?;
+        Some(SubtitlePacket {
+            timestamp_ms: self.timestamp_ms.saturating_add(sub_cue.start_offset_ms),
+            duration_ms: sub_cue.end_offset_ms - sub_cue.start_offset_ms,
+            x: sub_cue.x,
+            y: sub_cue.y,
+            width: sub_cue.width,
+            height: sub_cue.height,
+            color_indices: sub_cue.color_indices,
+            alpha_values: sub_cue.alpha_values,
+            forced: self.forced,
+            stream_id: self.stream_id,
+            color_controls: sub_cue.color_controls.clone(),
+            control_sequences: Vec::new(),
+            sub_cues: Vec::new(),
+            packet_data: self.packet_data.clone(),
+            even_field_range: sub_cue.even_field_range.clone(),
+            odd_field_range: sub_cue.odd_field_range.clone(),
+        })
+    }
+
+    /// Find the CHG_COLCON band covering a screen line.
+    pub fn color_control_at_line(&self, line: u16) -> Option<&VobSubColorControl> {
+        self.color_controls
+            .iter()
+            .find(|control| control.start_line <= line && line <= control.end_line)
+    }
+}
+
+/// First and last Private Stream 1 sub-stream ids used for DVD subtitles.
+pub const VOBSUB_STREAM_ID_FIRST: u8 = 0x20;
+pub const VOBSUB_STREAM_ID_LAST: u8 = 0x3F;
+
+/// Check whether a Private Stream 1 sub-stream id carries DVD subtitles.
+pub fn is_vobsub_stream_id(stream_id: u8) -> bool {
+    (VOBSUB_STREAM_ID_FIRST..=VOBSUB_STREAM_ID_LAST).contains(&stream_id)
+}
+
+/// Result of parsing a subtitle packet from possibly incomplete SUB data.
+#[derive(Debug, Clone)]
+pub enum SubtitlePacketParseAttempt {
+    /// The packet and the offset just past its last PES
+    Complete(SubtitlePacket, usize),
+    Incomplete,
+    Invalid,
 }
 
 /// Parse a subtitle packet from the SUB file at the given position.
+///
+/// The packet belongs to the sub-stream of the first PES at that position;
+/// PES packets of other sub-streams are skipped while collecting its data.
 pub fn parse_subtitle_packet(
     data: &[u8],
     start_offset: usize,
     _palette: &VobSubPalette,
 ) -> Option<(SubtitlePacket, usize)> {
+    parse_subtitle_packet_for_stream(data, start_offset, None)
+}
+
+/// Parse a subtitle packet of one sub-stream at the given position.
+///
+/// Returns `None` if the first PES at that position belongs to another
+/// sub-stream (or to no subtitle stream at all).
+pub fn parse_subtitle_packet_for_stream(
+    data: &[u8],
+    start_offset: usize,
+    stream_id: Option<u8>,
+) -> Option<(SubtitlePacket, usize)> {
+    collect_subtitle_packet(data, start_offset, stream_id).0
+}
+
+/// Parse a subtitle packet, distinguishing a packet cut off by the end of
+/// `data` (more bytes may complete it) from one that is invalid.
+pub fn try_parse_subtitle_packet(
+    data: &[u8],
+    start_offset: usize,
+    stream_id: Option<u8>,
+) -> SubtitlePacketParseAttempt {
+    match collect_subtitle_packet(data, start_offset, stream_id) {
+        (_, true) => SubtitlePacketParseAttempt::Incomplete,
+        (Some((packet, end)), false) => SubtitlePacketParseAttempt::Complete(packet, end),
+        (None, false) => SubtitlePacketParseAttempt::Invalid,
+    }
+}
+
+/// Collect and parse a packet; the flag is set when the data ended before
+/// the packet was complete.
+fn collect_subtitle_packet(
+    data: &[u8],
+    start_offset: usize,
+    stream_id: Option<u8>,
+) -> (Option<(SubtitlePacket, usize)>, bool) {
     let mut offset = start_offset;
+    let mut packet_stream_id = stream_id;
     let data_len = data.len();
 
     // Safety: limit how far we scan for a single packet (256KB should be more than enough)
@@ -75,6 +289,8 @@ pub fn parse_subtitle_packet(
     let mut data_chunks: Vec<(usize, usize)> = Vec::new();
     let mut expected_size: usize = 0;
     let mut collected_size: usize = 0;
+    // Set when the packet ended (or proved malformed) before the data did
+    let mut finished = false;
 
     // Look for MPEG-2 PS headers and collect all packets
     while offset < max_scan.saturating_sub(4) {
@@ -129,6 +345,7 @@ pub fn parse_subtitle_packet(
             };
 
             if offset + 3 > packet_end {
+                finished = true;
                 break;
             }
 
@@ -138,22 +355,36 @@ pub fn parse_subtitle_packet(
             offset += 3;
 
             if offset + header_data_length > packet_end {
+                finished = true;
                 break;
             }
 
-            // Extract PTS if present and we don't have one yet
-            if (pes_flags & 0x80) != 0 && pts == 0 && offset + 5 <= packet_end {
-                pts = extract_pts(data, offset);
-            }
-
+            let pts_offset = offset;
             offset += header_data_length;
 
-            // Skip stream ID byte
+            // Sub-stream ID byte
             if offset + 1 > packet_end {
+                finished = true;
                 break;
             }
+            let sub_stream_id = data[offset];
             offset += 1;
 
+            let wanted_stream_id = *packet_stream_id.get_or_insert(sub_stream_id);
+            if sub_stream_id != wanted_stream_id || !is_vobsub_stream_id(sub_stream_id) {
+                if data_chunks.is_empty() {
+                    return (None, false);
+                }
+                // Interleaved packet of another stream
+                offset = packet_end;
+                continue;
+            }
+
+            // Extract PTS if present and we don't have one yet
+            if (pes_flags & 0x80) != 0 && pts == 0 && pts_offset + 5 <= packet_end {
+                pts = extract_pts(data, pts_offset);
+            }
+
             // Calculate payload length within this PES packet
             let payload_length = packet_end.saturating_sub(offset);
 
@@ -169,6 +400,7 @@ pub fn parse_subtitle_packet(
 
                 // Check if we've collected enough data
                 if expected_size > 0 && collected_size >= expected_size {
+                    finished = true;
                     break;
                 }
 
@@ -190,6 +422,7 @@ pub fn parse_subtitle_packet(
         // Other stream types
         if stream_id >= 0xBC {
             if !data_chunks.is_empty() {
+                finished = true;
                 break;
             }
             offset += 4;
@@ -204,9 +437,11 @@ pub fn parse_subtitle_packet(
         offset += 1;
     }
 
+    let truncated = !finished && max_scan == data_len;
+
     // Reassemble collected data
     if data_chunks.is_empty() {
-        return None;
+        return (None, truncated);
     }
 
     if data_chunks.len() == 1 {
@@ -222,10 +457,14 @@ pub fn parse_subtitle_packet(
         };
         let subtitle_data = &data[start..trimmed_end];
         if subtitle_data.len() < 4 {
-            return None;
+            return (None, truncated);
         }
 
-        parse_subtitle_data(packet_source, data, pts).map(|packet| (packet, offset))
+        let packet = parse_subtitle_data(packet_source, data, pts).map(|mut packet| {
+            packet.stream_id = packet_stream_id.unwrap_or_default();
+            (packet, offset)
+        });
+        (packet, truncated)
     } else {
         let final_size = if expected_size > 0 {
             expected_size.min(collected_size)
@@ -245,14 +484,61 @@ pub fn parse_subtitle_packet(
         }
 
         if merged.len() < 4 {
-            return None;
+            return (None, truncated);
         }
 
-        parse_subtitle_data(SubtitlePacketData::Owned(merged), data, pts)
-            .map(|packet| (packet, offset))
+        let packet =
+            parse_subtitle_data(SubtitlePacketData::Owned(merged), data, pts).map(|mut packet| {
+                packet.stream_id = packet_stream_id.unwrap_or_default();
+                (packet, offset)
+            });
+        (packet, truncated)
     }
 }
 
+/// List the DVD subtitle sub-streams (0x20-0x3F) present in SUB data, in
+/// ascending order.
+pub fn list_vobsub_streams(data: &[u8]) -> Vec<u8> {
+    let mut found = [false; 32];
+    let mut offset = 0;
+    let len = data.len();
+
+    while offset + 9 <= len {
+        let Some(pos) = memchr(0x00, &data[offset..len - 3]) else {
+            break;
+        };
+        let candidate = offset + pos;
+        if data[candidate + 1] != 0x00 || data[candidate + 2] != 0x01 {
+            offset = candidate + 1;
+            continue;
+        }
+
+        let stream_id = data[candidate + 3];
+        if stream_id == 0xBA || !(0xBD..=0xFF).contains(&stream_id) {
+            offset = candidate + 4;
+            continue;
+        }
+
+        let pes_length = u16::from_be_bytes([data[candidate + 4], data[candidate + 5]]) as usize;
+        let packet_end = (candidate + 6 + pes_length).min(len);
+        if stream_id == 0xBD && candidate + 9 <= packet_end {
+            let header_data_length = data[candidate + 8] as usize;
+            if let Some(&sub_stream_id) = data.get(candidate + 9 + header_data_length)
+                && candidate + 9 + header_data_length < packet_end
+                && is_vobsub_stream_id(sub_stream_id)
+            {
+                found[(sub_stream_id - VOBSUB_STREAM_ID_FIRST) as usize] = true;
+            }
+        }
+        offset = packet_end.max(candidate + 4);
+    }
+
+    (0..32u8)
+        .filter(|&index| found[index as usize])
+        .map(|index| VOBSUB_STREAM_ID_FIRST + index)
+        .collect()
+}
+
 /// Extract PTS (Presentation Time Stamp) from PES header.
 fn extract_pts(data: &[u8], offset: usize) -> u32 {
     if offset + 5 > data.len() {
@@ -297,23 +583,13 @@ fn parse_subtitle_data(
         return None;
     }
 
-    // Parse control sequence
-    let mut x: u16 = 0;
-    let mut y: u16 = 0;
-    let mut width: u16 = 0;
-    let mut height: u16 = 0;
-    let mut duration: u32 = 0;
-    let mut found_stop: bool = false;
-    let mut color_indices = [0u8, 1, 2, 3];
-    let mut alpha_values = [0u8, 15, 15, 15];
-    let mut top_field_offset: usize = 0;
-    let mut bottom_field_offset: usize = 0;
-
+    // Parse the chain of control sequences
+    let mut control_sequences = Vec::new();
     let mut ctrl_offset = packet_start + dcsq_offset;
     let mut iterations = 0;
     const MAX_ITERATIONS: usize = 1000; // Safety limit
 
-    while ctrl_offset < end_offset && iterations < MAX_ITERATIONS && !found_stop {
+    while ctrl_offset < end_offset && iterations < MAX_ITERATIONS {
         iterations += 1;
 
         // Remember where this block started (before reading delay/next_offset)
@@ -332,33 +608,39 @@ fn parse_subtitle_data(
             ((data[ctrl_offset] as usize) << 8) | (data[ctrl_offset + 1] as usize);
         ctrl_offset += 2;
 
+        // Delay is in 1024/90000 sec units
+        let mut sequence = VobSubControlSequence {
+            delay_ms: (delay * 1024) / 90,
+            ..VobSubControlSequence::default()
+        };
+
         // Parse commands
         while ctrl_offset < end_offset {
             let cmd = data[ctrl_offset];
             ctrl_offset += 1;
 
             match cmd {
-                0x00 => {} // Force display
-                0x01 => {} // Start display
-                0x02 => {
-                    // Stop display - delay is when to stop (duration in 1024/90000 sec units)
-                    duration = (delay * 1024) / 90;
-                    found_stop = true;
-                }
+                0x00 => sequence.force_display = true,
+                0x01 => sequence.start_display = true,
+                0x02 => sequence.stop_display = true,
                 0x03 if ctrl_offset + 2 <= end_offset => {
                     // Set palette
-                    color_indices[3] = (data[ctrl_offset] >> 4) & 0x0F;
-                    color_indices[2] = data[ctrl_offset] & 0x0F;
-                    color_indices[1] = (data[ctrl_offset + 1] >> 4) & 0x0F;
-                    color_indices[0] = data[ctrl_offset + 1] & 0x0F;
+                    sequence.color_indices = Some([
+                        data[ctrl_offset + 1] & 0x0F,
+                        (data[ctrl_offset + 1] >> 4) & 0x0F,
+                        data[ctrl_offset] & 0x0F,
+                        (data[ctrl_offset] >> 4) & 0x0F,
+                    ]);
                     ctrl_offset += 2;
                 }
                 0x04 if ctrl_offset + 2 <= end_offset => {
                     // Set alpha
-                    alpha_values[3] = (data[ctrl_offset] >> 4) & 0x0F;
-                    alpha_values[2] = data[ctrl_offset] & 0x0F;
-                    alpha_values[1] = (data[ctrl_offset + 1] >> 4) & 0x0F;
-                    alpha_values[0] = data[ctrl_offset + 1] & 0x0F;
+                    sequence.alpha_values = Some([
+                        data[ctrl_offset + 1] & 0x0F,
+                        (data[ctrl_offset + 1] >> 4) & 0x0F,
+                        data[ctrl_offset] & 0x0F,
+                        (data[ctrl_offset] >> 4) & 0x0F,
+                    ]);
                     ctrl_offset += 2;
                 }
                 0x05 if ctrl_offset + 6 <= end_offset => {
@@ -381,29 +663,41 @@ fn parse_subtitle_data(
                         return None;
                     }
 
-                    x = x1;
-                    y = y1;
-                    width = width_usize as u16;
-                    height = height_usize as u16;
+                    sequence.area = Some(VobSubArea {
+                        x: x1,
+                        y: y1,
+                        width: width_usize as u16,
+                        height: height_usize as u16,
+                    });
                     ctrl_offset += 6;
                 }
                 0x06 if ctrl_offset + 4 <= end_offset => {
                     // Set field offsets
-                    top_field_offset =
-                        ((data[ctrl_offset] as usize) << 8) | (data[ctrl_offset + 1] as usize);
-                    bottom_field_offset =
-                        ((data[ctrl_offset + 2] as usize) << 8) | (data[ctrl_offset + 3] as usize);
+                    sequence.field_offsets = Some((
+                        u16::from_be_bytes([data[ctrl_offset], data[ctrl_offset + 1]]),
+                        u16::from_be_bytes([data[ctrl_offset + 2], data[ctrl_offset + 3]]),
+                    ));
                     ctrl_offset += 4;
                 }
+                0x07 if ctrl_offset + 2 <= end_offset => {
+                    // Change colour and contrast: size includes the size field itself
+                    let size =
+                        ((data[ctrl_offset] as usize) << 8) | (data[ctrl_offset + 1] as usize);
+                    let table_end = ctrl_offset + size.max(2);
+                    if table_end > end_offset {
+                        break;
+                    }
+                    sequence.color_controls =
+                        Some(parse_color_controls(&data[ctrl_offset + 2..table_end]));
+                    ctrl_offset = table_end;
+                }
                 0xFF => break, // End of control sequence
                 _ => {}
             }
-
-            if cmd == 0xFF || cmd == 0x02 {
-                break;
-            }
         }
 
+        control_sequences.push(sequence);
+
         // Check if this is the last control block
         // The end of the chain is indicated by next_ctrl_offset pointing to the current block or earlier
         let next_block_abs = packet_start + next_ctrl_offset;
@@ -418,7 +712,121 @@ fn parse_subtitle_data(
         ctrl_offset = next_block_abs;
     }
 
-    // Calculate field data positions
+    let (sub_cues, duration) =
+        build_sub_cues(&control_sequences, packet_start + dcsq_offset, end_offset);
+    let first = sub_cues.first()?.clone();
+
+    Some(SubtitlePacket {
+        timestamp_ms: pts,
+        duration_ms: duration,
+        x: first.x,
+        y: first.y,
+        width: first.width,
+        height: first.height,
+        color_indices: first.color_indices,
+        alpha_values: first.alpha_values,
+        forced: control_sequences
+            .iter()
+            .any(|sequence| sequence.force_display),
+        stream_id: VOBSUB_STREAM_ID_FIRST,
+        color_controls: first.color_controls,
+        control_sequences,
+        sub_cues,
+        packet_data,
+        even_field_range: first.even_field_range,
+        odd_field_range: first.odd_field_range,
+    })
+}
+
+/// Default duration when a packet has no usable STP_DSP command.
+const DEFAULT_DURATION_MS: u32 = 5000;
+
+/// Replay the control sequences and split the display into intervals of
+/// constant state. Returns the intervals and the packet duration.
+fn build_sub_cues(
+    sequences: &[VobSubControlSequence],
+    field_end: usize,
+    data_end: usize,
+) -> (Vec<VobSubSubCue>, u32) {
+    let mut state = VobSubSubCue {
+        start_offset_ms: 0,
+        end_offset_ms: 0,
+        x: 0,
+        y: 0,
+        width: 0,
+        height: 0,
+        color_indices: [0, 1, 2, 3],
+        alpha_values: [0, 15, 15, 15],
+        color_controls: Vec::new(),
+        even_field_range: 0..0,
+        odd_field_range: 0..0,
+    };
+    let mut field_offsets = (0usize, 0usize);
+    let mut sub_cues: Vec<VobSubSubCue> = Vec::new();
+    let mut open = false;
+    let mut stop_ms = None;
+
+    for sequence in sequences {
+        sequence.apply_to(&mut state, &mut field_offsets);
+        (state.even_field_range, state.odd_field_range) =
+            field_ranges(field_offsets.0, field_offsets.1, field_end, data_end);
+
+        let time_ms = sequence.delay_ms;
+        let starts = sequence.start_display || sequence.force_display;
+        let closing = open && (sequence.changes_state() || starts || sequence.stop_display);
+        if closing {
+            // Close the current interval; drop it if it never became visible
+            if let Some(current) = sub_cues.last_mut() {
+                current.end_offset_ms = time_ms;
+                if current.end_offset_ms <= current.start_offset_ms {
+                    sub_cues.pop();
+                }
+            }
+            open = false;
+        }
+
+        if sequence.stop_display {
+            stop_ms = Some(time_ms);
+        } else if starts || closing {
+            sub_cues.push(VobSubSubCue {
+                start_offset_ms: time_ms,
+                ..state.clone()
+            });
+            open = true;
+        }
+    }
+
+    let mut duration = match stop_ms {
+        Some(stop_ms) if stop_ms > 0 => stop_ms,
+        _ => DEFAULT_DURATION_MS,
+    };
+
+    if open && let Some(current) = sub_cues.last_mut() {
+        if duration <= current.start_offset_ms {
+            duration = current.start_offset_ms.saturating_add(DEFAULT_DURATION_MS);
+        }
+        current.end_offset_ms = duration;
+    }
+
+    // Packets without a usable start/stop pair are shown for the whole duration
+    if sub_cues.is_empty() {
+        sub_cues.push(VobSubSubCue {
+            start_offset_ms: 0,
+            end_offset_ms: duration,
+            ..state
+        });
+    }
+
+    (sub_cues, duration)
+}
+
+/// Compute the even/odd field byte ranges from the SET_DSPXA offsets.
+fn field_ranges(
+    top_field_offset: usize,
+    bottom_field_offset: usize,
+    field_end: usize,
+    data_end: usize,
+) -> (Range<usize>, Range<usize>) {
     let even_start = if top_field_offset > 0 {
         top_field_offset
     } else {
@@ -431,33 +839,82 @@ fn parse_subtitle_data(
     };
 
     let even_field_end = odd_start;
-    let odd_field_end = packet_start + dcsq_offset;
+    let odd_field_end = field_end;
 
-    let even_field_range = if even_start < even_field_end.min(end_offset) {
-        even_start..even_field_end.min(end_offset)
+    let even_field_range = if even_start < even_field_end.min(data_end) {
+        even_start..even_field_end.min(data_end)
     } else {
         0..0
     };
 
-    let odd_field_range = if odd_start < odd_field_end.min(end_offset) {
-        odd_start..odd_field_end.min(end_offset)
+    let odd_field_range = if odd_start < odd_field_end.min(data_end) {
+        odd_start..odd_field_end.min(data_end)
     } else {
         0..0
     };
 
-    Some(SubtitlePacket {
-        timestamp_ms: pts,
-        duration_ms: if duration > 0 { duration } else { 5000 },
-        x,
-        y,
-        width,
-        height,
-        color_indices,
-        alpha_values,
-        packet_data,
-        even_field_range,
-        odd_field_range,
-    })
+    (even_field_range, odd_field_range)
+}
+
+/// Parse the line/column tables of a CHG_COLCON command.
+///
+/// Each band is a 4-byte LN_CTLI (start line, column count, end line) followed
+/// by 6-byte PX_CTLI entries (start column, colours, contrast); the table ends
+/// with `0x0FFFFFFF`.
+fn parse_color_controls(data: &[u8]) -> Vec<VobSubColorControl> {
+    let mut controls = Vec::new();
+    let mut offset = 0;
+
+    while offset + 4 <= data.len() {
+        let line_info = u32::from_be_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ]);
+        offset += 4;
+        if line_info == 0x0FFF_FFFF {
+            break;
+        }
+
+        let start_line = ((line_info >> 16) & 0x0FFF) as u16;
+        let column_count = ((line_info >> 12) & 0x0F) as usize;
+        let end_line = (line_info & 0x0FFF) as u16;
+
+        let mut columns = Vec::with_capacity(column_count);
+        for _ in 0..column_count {
+            let Some(entry) = data.get(offset..offset + 6) else {
+                break;
+            };
+            offset += 6;
+            columns.push(VobSubColumnControl {
+                start_column: u16::from_be_bytes([entry[0], entry[1]]) & 0x0FFF,
+                color_indices: [
+                    entry[3] & 0x0F,
+                    entry[3] >> 4,
+                    entry[2] & 0x0F,
+                    entry[2] >> 4,
+                ],
+                alpha_values: [
+                    entry[5] & 0x0F,
+                    entry[5] >> 4,
+                    entry[4] & 0x0F,
+                    entry[4] >> 4,
+                ],
+            });
+        }
+        columns.sort_by_key(|entry| entry.start_column);
+
+        if start_line <= end_line {
+            controls.push(VobSubColorControl {
+                start_line,
+                end_line,
+                columns,
+            });
+        }
+    }
+
+    controls
 }
 
 #[cfg(test)]
@@ -485,13 +942,106 @@ mod tests {
             0x00, 0x00, 0x00, 0x04, // delay, self-referencing next offset
             0x03, 0xfd, 0x3b, // palette
             0x04, 0xdf, 0xd0, // alpha
-            0x01, 0xff, // start display, end
+            0x00, 0xff, // forced start display, end
         ];
 
         let packet =
             parse_subtitle_data(SubtitlePacketData::Owned(data.to_vec()), &data, 0).unwrap();
 
+        assert!(packet.forced);
         assert_eq!(packet.color_indices, [11, 3, 13, 15]);
         assert_eq!(packet.alpha_values, [0, 13, 15, 13]);
     }
+
+    #[test]
+    fn test_parse_chg_colcon_tables() {
+        let data = [
+            0x00, 0x24, 0x00, 0x04, // packet size, control offset
+            0x00, 0x00, 0x00, 0x04, // delay, self-referencing next offset
+            0x07, 0x00, 0x16, // CHG_COLCON, table size
+            0x00, 0x0A, 0x20, 0x14, // lines 10-20, 2 columns
+            0x00, 0x00, 0x32, 0x10, 0xff, 0xf0, // from column 0
+            0x00, 0x40, 0x76, 0x54, 0xf0, 0xff, // from column 64
+            0x0f, 0xff, 0xff, 0xff, // end of table
+            0x01, 0xff, // start display, end
+        ];
+
+        let packet =
+            parse_subtitle_data(SubtitlePacketData::Owned(data.to_vec()), &data, 0).unwrap();
+
+        assert_eq!(packet.color_controls.len(), 1);
+        let control = packet.color_control_at_line(15).unwrap();
+        assert_eq!((control.start_line, control.end_line), (10, 20));
+        assert_eq!(control.column_at(10).unwrap().color_indices, [0, 1, 2, 3]);
+        assert_eq!(control.column_at(64).unwrap().color_indices, [4, 5, 6, 7]);
+        assert_eq!(control.column_at(64).unwrap().alpha_values, [15, 15, 0, 15]);
+        assert!(packet.color_control_at_line(21).is_none());
+    }
+
+    #[test]
+    fn test_parse_subtitle_packet_skips_interleaved_streams() {
+        let spu = [
+            0x00, 0x0c, 0x00, 0x04, // packet size, control offset
+            0x00, 0x00, 0x00, 0x04, // delay, self-referencing next offset
+            0x00, 0x01, 0x02, 0xff, // forced start, start, stop, end
+        ];
+        let pes = |stream_id: u8, payload: &[u8]| {
+            let mut out = vec![0x00, 0x00, 0x01, 0xBD];
+            out.extend_from_slice(&((payload.len() + 4) as u16).to_be_bytes());
+            out.extend_from_slice(&[0x81, 0x00, 0x00, stream_id]);
+            out.extend_from_slice(payload);
+            out
+        };
+        let mut data = pes(0x21, &spu[..6]);
+        data.extend(pes(0x20, &[0x00, 0x0c, 0x00]));
+        data.extend(pes(0x21, &spu[6..]));
+
+        let (packet, _) = parse_subtitle_packet(&data, 0, &VobSubPalette::default()).unwrap();
+        assert_eq!(packet.stream_id, 0x21);
+        assert!(packet.forced);
+
+        assert!(parse_subtitle_packet_for_stream(&data, 0, Some(0x20)).is_none());
+        assert_eq!(list_vobsub_streams(&data), vec![0x20, 0x21]);
+    }
+
+    #[test]
+    fn test_parse_multiple_control_blocks_into_sub_cues() {
+        let data = [
+            0x00, 0x1b, 0x00, 0x04, // packet size, control offset
+            0x00, 0x00, 0x00, 0x0d, // block 1: delay 0, next at 13
+            0x01, 0x03, 0x32, 0x10, 0xff, // start display, palette
+            0x00, 0x5a, 0x00, 0x15, // block 2: delay 90 (1024 ms), next at 21
+            0x03, 0x76, 0x54, 0xff, // palette change
+            0x00, 0xb4, 0x00, 0x15, // block 3: delay 180 (2048 ms), end of chain
+            0x02, 0xff, // stop display
+        ];
+
+        let packet =
+            parse_subtitle_data(SubtitlePacketData::Owned(data.to_vec()), &data, 500).unwrap();
+
+        assert_eq!(packet.control_sequences.len(), 3);
+        assert!(!packet.forced);
+        assert_eq!(packet.duration_ms, 2048);
+        assert_eq!(packet.sub_cues.len(), 2);
+        assert_eq!(
+            (
+                packet.sub_cues[0].start_offset_ms,
+                packet.sub_cues[0].end_offset_ms
+            ),
+            (0, 1024)
+        );
+        assert_eq!(
+            (
+                packet.sub_cues[1].start_offset_ms,
+                packet.sub_cues[1].end_offset_ms
+            ),
+            (1024, 2048)
+        );
+        assert_eq!(packet.color_indices, [0, 1, 2, 3]);
+
+        let second = packet.sub_cue_packet(1).unwrap();
+        assert_eq!(second.timestamp_ms, 1524);
+        assert_eq!(second.duration_ms, 1024);
+        assert_eq!(second.color_indices, [4, 5, 6, 7]);
+    }
 }
@@ -1,11 +1,16 @@
 //! VobSub parser.
 
-use memchr::memchr;
+use memchr::{memchr, memmem};
 use std::collections::HashMap;
+use std::io::{Read, Seek};
 
 use super::{
-    DebandConfig, ExtractedVobSub, IdxParseResult, SubtitlePacket, VobSubPalette, VobSubTimestamp,
-    apply_deband, decode_vobsub_rle, extract_vobsub_from_mks, parse_idx, parse_subtitle_packet,
+    DebandConfig, DvdSubpictureDemuxer, DvdTitleSetInfo, ExtractedVobSub, IdxParseResult,
+    MAX_VOBSUB_IMAGE_PIXELS, SubtitlePacket, SubtitlePacketParseAttempt, VOBSUB_STREAM_ID_FIRST,
+    VobSubIdxSettings, VobSubMetadata, VobSubPalette, VobSubPaletteEstimator, VobSubTimestamp,
+    VobSubTrack, apply_deband, decode_vobsub_rle_with_settings, extract_vobsub_from_mks_reader,
+    extract_vobsub_track_from_mks, list_vobsub_streams, parse_idx, parse_subtitle_packet,
+    parse_subtitle_packet_for_stream, parse_vts_ifo_pgc, try_parse_subtitle_packet,
 };
 use crate::utils::binary_search_timestamp;
 
@@ -25,6 +30,25 @@ pub struct VobSubParser {
     loaded_from_idx: bool,
     /// Last non-fatal render issue for diagnostics.
     last_render_issue: Option<String>,
+    /// Only show cues displayed with FSTA_DSP (forced start display).
+    forced_only: bool,
+    /// Caller-supplied palette replacing the IDX or estimated one.
+    palette_override: Option<VobSubPalette>,
+    /// Progress of incremental SUB loading through `feed`.
+    feed: Option<SubFeed>,
+}
+
+/// State of incremental SUB ingestion.
+#[derive(Default)]
+struct SubFeed {
+    /// IDX mode: cues whose packets are complete (or proved invalid)
+    ready: Vec<bool>,
+    /// End of the indexed data; bytes past it are pending
+    resolved_len: usize,
+    /// IDX-less mode: sub-stream locked on the first packet found
+    stream_id: Option<u8>,
+    /// IDX-less mode: palette votes of the indexed packets
+    estimator: VobSubPaletteEstimator,
 }
 
 impl VobSubParser {
@@ -38,6 +62,9 @@ impl VobSubParser {
             deband_config: DebandConfig::default(),
             loaded_from_idx: false,
             last_render_issue: None,
+            forced_only: false,
+            palette_override: None,
+            feed: None,
         }
     }
 
@@ -51,6 +78,7 @@ impl VobSubParser {
         self.dispose();
         let idx = parse_idx(idx_content);
         self.timestamps_ms = idx.timestamps.iter().map(|t| t.timestamp_ms).collect();
+        self.forced_only = idx.settings.forced_subs;
         self.idx_data = Some(idx);
         self.sub_data = None;
         self.loaded_from_idx = true;
@@ -60,6 +88,51 @@ impl VobSubParser {
         self.packet_cache.clear();
         self.last_render_issue = None;
         self.sub_data = Some(sub_data);
+        self.feed = None;
+    }
+
+    /// Append a chunk of SUB data as it arrives and return how many cues
+    /// became renderable.
+    ///
+    /// After `load_from_idx`, cues resolve once their packets are complete.
+    /// Otherwise the data is indexed like `load_from_sub_only`, locking on the
+    /// first sub-stream found.
+    pub fn feed(&mut self, chunk: &[u8]) -> usize {
+        if self.feed.is_none() {
+            if !self.loaded_from_idx {
+                self.packet_cache.clear();
+                self.apply_loaded_data(scan_sub_stream(&[], None), Vec::new(), false);
+            }
+            self.feed = Some(SubFeed::default());
+        }
+        self.sub_data
+            .get_or_insert_with(Vec::new)
+            .extend_from_slice(chunk);
+        // Packets that were cut off may parse now
+        self.packet_cache.retain(|_, packet| packet.is_some());
+
+        if self.loaded_from_idx {
+            self.resolve_fed_cues()
+        } else {
+            self.index_fed_packets()
+        }
+    }
+
+    /// End incremental loading and return the cue count. Packets still
+    /// incomplete in IDX-less mode are dropped.
+    pub fn finish_feed(&mut self) -> usize {
+        if self.feed.take().is_some() {
+            self.packet_cache.retain(|_, packet| packet.is_some());
+        }
+        self.count()
+    }
+
+    /// Get the number of fed bytes not yet resolved into complete packets.
+    pub fn pending_len(&self) -> usize {
+        match (&self.feed, &self.sub_data) {
+            (Some(feed), Some(sub_data)) => sub_data.len().saturating_sub(feed.resolved_len),
+            _ => 0,
+        }
     }
 
     pub fn has_sub_data(&self) -> bool {
@@ -67,18 +140,51 @@ impl VobSubParser {
     }
 
     /// Load VobSub from a Matroska subtitle container with embedded S_VOBSUB tracks.
-    pub fn load_from_mks(&mut self, mks_data: &[u8]) -> Result<(), String> {
+    /// Uses the first VobSub track when `track_number` is `None`.
+    pub fn load_from_mks(
+        &mut self,
+        mks_data: &[u8],
+        track_number: Option<u64>,
+    ) -> Result<(), String> {
+        self.dispose();
+        let extracted = extract_vobsub_track_from_mks(mks_data, track_number)?;
+        self.apply_extracted_vobsub(extracted);
+        Ok(())
+    }
+
+    /// Load an S_VOBSUB track from a seekable Matroska source without reading
+    /// the whole file: only headers, Cues and the track's blocks are read.
+    pub fn load_from_mks_reader<R: Read + Seek>(
+        &mut self,
+        source: R,
+        track_number: Option<u64>,
+    ) -> Result<(), String> {
         self.dispose();
+        let extracted = extract_vobsub_from_mks_reader(source, track_number)?;
+        self.apply_extracted_vobsub(extracted);
+        Ok(())
+    }
 
+    fn apply_extracted_vobsub(&mut self, extracted: ExtractedVobSub) {
         let ExtractedVobSub {
             idx_content,
             sub_data,
             language,
             track_id,
-        } = extract_vobsub_from_mks(mks_data)?;
+            durations_ms,
+        } = extracted;
 
         let mut idx = parse_idx(&idx_content);
+        if let Some(track) = idx.tracks.get_mut(idx.selected_track) {
+            for (timestamp, duration_ms) in track.timestamps.iter_mut().zip(&durations_ms) {
+                timestamp.duration_ms = *duration_ms;
+            }
+            idx.timestamps = track.timestamps.clone();
+        }
         if language.is_some() {
+            if let Some(track) = idx.tracks.get_mut(idx.selected_track) {
+                track.language = language.clone();
+            }
             idx.metadata.language = language;
         }
         if track_id.is_some() {
@@ -86,59 +192,150 @@ impl VobSubParser {
         }
 
         self.apply_loaded_data(idx, sub_data, true);
-        Ok(())
     }
 
     /// Load VobSub from SUB file only (scans for timestamps).
+    /// Uses the first subtitle sub-stream found in the data and estimates a
+    /// palette from how the packets use their colours.
     pub fn load_from_sub_only(&mut self, sub_data: Vec<u8>) {
+        let stream_id = list_vobsub_streams(&sub_data).first().copied();
         self.dispose();
+        let idx = scan_sub_stream(&sub_data, stream_id);
+        self.apply_loaded_data(idx, sub_data, false);
+    }
 
-        // Default palette
-        let palette = VobSubPalette::default();
-
-        // Pre-allocate with estimate (roughly 1 subtitle per 10KB)
-        let estimated_count = (sub_data.len() / 10000).max(32);
-        let mut timestamps: Vec<VobSubTimestamp> = Vec::with_capacity(estimated_count);
-        let mut offset = 0;
-        let len = sub_data.len();
-
-        // Look for MPEG-2 PS pack start code
-        while offset < len.saturating_sub(4) {
-            // Find next potential start code (0x00 0x00 0x01 0xBA)
-            if let Some(pos) = memchr(0x00, &sub_data[offset..]) {
-                let candidate = offset + pos;
-
-                // Check for full start code: 00 00 01 BA
-                if candidate + 3 < len
-                    && sub_data[candidate + 1] == 0x00
-                    && sub_data[candidate + 2] == 0x01
-                    && sub_data[candidate + 3] == 0xBA
-                    && let Some((packet, _)) = parse_subtitle_packet(&sub_data, candidate, &palette)
-                    && packet.width > 0
-                    && packet.height > 0
-                {
-                    timestamps.push(VobSubTimestamp {
-                        timestamp_ms: packet.timestamp_ms,
-                        file_position: candidate as u64,
-                    });
-                }
-                offset = candidate + 1;
-            } else {
-                // No more 0x00 bytes found
-                break;
-            }
-        }
+    /// Load one subtitle sub-stream (0x20-0x3F) from SUB file only.
+    pub fn load_from_sub_stream(&mut self, sub_data: Vec<u8>, stream_id: u8) {
+        self.dispose();
+        let idx = scan_sub_stream(&sub_data, Some(stream_id));
+        self.apply_loaded_data(idx, sub_data, false);
+    }
 
-        // Sort and store
-        timestamps.sort_by_key(|t| t.timestamp_ms);
-        self.timestamps_ms = timestamps.iter().map(|t| t.timestamp_ms).collect();
+    /// List the subtitle sub-streams (0x20-0x3F) in the loaded SUB data.
+    pub fn sub_stream_ids(&self) -> Vec<u8> {
+        self.sub_data
+            .as_deref()
+            .map(list_vobsub_streams)
+            .unwrap_or_default()
+    }
 
-        let idx = IdxParseResult {
-            palette,
-            timestamps,
-            metadata: Default::default(),
+    /// Switch a SUB-only parser to another sub-stream, keeping the loaded
+    /// data and settings. Returns false without IDX-less SUB data.
+    pub fn select_sub_stream(&mut self, stream_id: u8) -> bool {
+        if self.loaded_from_idx {
+            return false;
+        }
+        let Some(sub_data) = self.sub_data.take() else {
+            return false;
         };
+
+        let mut idx = scan_sub_stream(&sub_data, Some(stream_id));
+        if let Some(palette) = &self.palette_override {
+            idx.palette = palette.clone();
+        }
+        self.packet_cache.clear();
+        self.last_render_issue = None;
         self.apply_loaded_data(idx, sub_data, false);
+        true
+    }
+
+    /// Replace the palette of the loaded subtitles, e.g. for SUB files whose
+    /// estimated colours are wrong. Kept when switching SUB-only sub-streams.
+    pub fn set_palette(&mut self, palette: VobSubPalette) {
+        if let Some(idx_data) = self.idx_data.as_mut() {
+            idx_data.palette = palette.clone();
+        }
+        self.palette_override = Some(palette);
+    }
+
+    /// Get the palette used for rendering.
+    pub fn palette(&self) -> Option<&VobSubPalette> {
+        self.idx_data.as_ref().map(|idx_data| &idx_data.palette)
+    }
+
+    /// Load the subtitles of program chain `pgc` (0-based) from a title set
+    /// IFO (`VTS_xx_0.IFO`) and its VOB data (`VTS_xx_1.VOB`,
+    /// `VTS_xx_2.VOB`, ... concatenated).
+    ///
+    /// Every subpicture stream declared in the IFO becomes a track, using the
+    /// PGC's physical stream for the title set's display aspect. Only the
+    /// PGC's cells are read, and times are title time following the PGC's
+    /// cell order. Other PGCs (titles, angles) need a load of their own.
+    pub fn load_from_dvd(
+        &mut self,
+        ifo_data: &[u8],
+        vob_data: &[u8],
+        pgc: usize,
+    ) -> Result<(), String> {
+        let info = parse_vts_ifo_pgc(ifo_data, pgc)?;
+        let mut demuxer = DvdSubpictureDemuxer::for_title(&info, None);
+        demuxer.push(vob_data);
+        self.load_dvd_title(info, demuxer.finish())
+    }
+
+    /// Load the subtitles of program chain `pgc` from a title set IFO and
+    /// subpicture data already demuxed from its VOBs, so the VOB files never
+    /// need to be held in memory. Demux with
+    /// `DvdSubpictureDemuxer::for_title` for the same PGC to get title time.
+    pub fn load_from_dvd_subpictures(
+        &mut self,
+        ifo_data: &[u8],
+        sub_data: Vec<u8>,
+        pgc: usize,
+    ) -> Result<(), String> {
+        self.load_dvd_title(parse_vts_ifo_pgc(ifo_data, pgc)?, sub_data)
+    }
+
+    fn load_dvd_title(&mut self, info: DvdTitleSetInfo, sub_data: Vec<u8>) -> Result<(), String> {
+        let mut tracks: Vec<VobSubTrack> = info
+            .subpicture_streams
+            .iter()
+            .map(|stream| {
+                let stream_id = stream.stream_id_for(info.is_widescreen);
+                VobSubTrack {
+                    language: stream.language.clone(),
+                    index: (stream_id - VOBSUB_STREAM_ID_FIRST) as u32,
+                    timestamps: scan_sub_timestamps(&sub_data, Some(stream_id)),
+                    delay_ms: 0,
+                }
+            })
+            .collect();
+        if tracks.is_empty() {
+            // No attributes declared: expose the streams present in the VOBs
+            tracks = list_vobsub_streams(&sub_data)
+                .into_iter()
+                .map(|stream_id| VobSubTrack {
+                    language: None,
+                    index: (stream_id - VOBSUB_STREAM_ID_FIRST) as u32,
+                    timestamps: scan_sub_timestamps(&sub_data, Some(stream_id)),
+                    delay_ms: 0,
+                })
+                .collect();
+        }
+        if tracks.is_empty() {
+            return Err("No subpicture streams found in DVD title set".to_string());
+        }
+
+        let mut idx = IdxParseResult {
+            palette: info.palette,
+            tracks,
+            timestamps: Vec::new(),
+            metadata: VobSubMetadata {
+                width: info.width,
+                height: info.height,
+                language: None,
+                id: None,
+            },
+            default_track: 0,
+            selected_track: 0,
+            settings: VobSubIdxSettings::default(),
+        };
+        idx.select_track(0);
+
+        self.dispose();
+        // The IFO plays the role of the IDX: keep its CLUT and tracks
+        self.apply_loaded_data(idx, sub_data, true);
+        Ok(())
     }
 
     /// Dispose of all resources.
@@ -150,6 +347,9 @@ impl VobSubParser {
         self.deband_config = DebandConfig::default();
         self.loaded_from_idx = false;
         self.last_render_issue = None;
+        self.forced_only = false;
+        self.palette_override = None;
+        self.feed = None;
     }
 
     /// Get the last non-fatal render issue for diagnostics.
@@ -192,7 +392,101 @@ impl VobSubParser {
             .unwrap_or_default()
     }
 
-    /// Check whether IDX metadata was used to load the parser.
+    /// Get the number of subtitle tracks declared in the IDX.
+    pub fn track_count(&self) -> usize {
+        self.idx_data
+            .as_ref()
+            .map_or(0, |idx_data| idx_data.tracks.len())
+    }
+
+    /// Get the language code of a track.
+    pub fn track_language(&self, track: usize) -> String {
+        self.idx_data
+            .as_ref()
+            .and_then(|idx_data| idx_data.tracks.get(track))
+            .and_then(|track| track.language.clone())
+            .unwrap_or_default()
+    }
+
+    /// Get the stream index (`index:` value) of a track, or -1.
+    pub fn track_stream_index(&self, track: usize) -> i32 {
+        self.idx_data
+            .as_ref()
+            .and_then(|idx_data| idx_data.tracks.get(track))
+            .map_or(-1, |track| track.index as i32)
+    }
+
+    /// Get the track chosen by the IDX `langidx:` directive.
+    pub fn default_track(&self) -> usize {
+        self.idx_data
+            .as_ref()
+            .map_or(0, |idx_data| idx_data.default_track)
+    }
+
+    /// Get the currently selected track.
+    pub fn selected_track(&self) -> usize {
+        self.idx_data
+            .as_ref()
+            .map_or(0, |idx_data| idx_data.selected_track)
+    }
+
+    /// Switch to another track, keeping the loaded SUB data.
+    /// Returns false if the track does not exist.
+    pub fn select_track(&mut self, track: usize) -> bool {
+        let Some(idx_data) = self.idx_data.as_mut() else {
+            return false;
+        };
+        if !idx_data.select_track(track) {
+            return false;
+        }
+
+        self.timestamps_ms = idx_data.timestamps.iter().map(|t| t.timestamp_ms).collect();
+        self.packet_cache.clear();
+        self.last_render_issue = None;
+        if let Some(feed) = self.feed.as_mut() {
+            feed.ready.clear();
+            self.resolve_fed_cues();
+        }
+        true
+    }
+
+    /// Get the display directives from the IDX header.
+    pub fn idx_settings(&self) -> Option<&VobSubIdxSettings> {
+        self.idx_data.as_ref().map(|idx_data| &idx_data.settings)
+    }
+
+    /// Check whether the IDX requests forced subtitles only (`forced subs: ON`).
+    pub fn forced_subs(&self) -> bool {
+        self.idx_settings()
+            .is_some_and(|settings| settings.forced_subs)
+    }
+
+    /// Get the opacity (0.0-1.0) of a cue at a given time, following the IDX
+    /// `fadein/out:` durations. Returns 0.0 outside the cue.
+    pub fn get_cue_opacity(&mut self, index: usize, time_ms: f64) -> f64 {
+        let Some(&start_time) = self.timestamps_ms.get(index) else {
+            return 0.0;
+        };
+        let end_time = self.calculate_end_time(index, start_time) as f64;
+        let start_time = start_time as f64;
+        if time_ms < start_time || time_ms >= end_time {
+            return 0.0;
+        }
+
+        let (fade_in, fade_out) = self.idx_settings().map_or((0, 0), |settings| {
+            (settings.fade_in_ms, settings.fade_out_ms)
+        });
+        let mut opacity: f64 = 1.0;
+        if fade_in > 0 {
+            opacity = opacity.min((time_ms - start_time) / fade_in as f64);
+        }
+        if fade_out > 0 {
+            opacity = opacity.min((end_time - time_ms) / fade_out as f64);
+        }
+        opacity.clamp(0.0, 1.0)
+    }
+
+    /// Check whether IDX (or DVD IFO) metadata was used to load the parser.
     pub fn has_idx_metadata(&self) -> bool {
         self.loaded_from_idx
     }
@@ -223,7 +517,7 @@ impl VobSubParser {
         // Calculate end time
         let end_time = self.calculate_end_time(index, start_time);
 
-        if time_ms_u32 < end_time {
+        if time_ms_u32 < end_time && (!self.forced_only || self.is_cue_forced(index)) {
             return index as i32;
         }
 
@@ -231,6 +525,32 @@ impl VobSubParser {
         -1
     }
 
+    /// Check if a cue is displayed with FSTA_DSP (forced start display).
+    pub fn is_cue_forced(&mut self, index: usize) -> bool {
+        self.ensure_packet_cached(index);
+        self.cached_packet(index)
+            .is_some_and(|packet| packet.forced)
+    }
+
+    /// Get the indices of all forced cues.
+    pub fn get_forced_cue_indices(&mut self) -> Vec<u32> {
+        (0..self.timestamps_ms.len())
+            .filter(|&index| self.is_cue_forced(index))
+            .map(|index| index as u32)
+            .collect()
+    }
+
+    /// Only show forced cues (e.g. when subtitles are "off"). Defaults to the
+    /// IDX `forced subs:` directive on load.
+    pub fn set_forced_only(&mut self, forced_only: bool) {
+        self.forced_only = forced_only;
+    }
+
+    /// Check whether forced-only mode is enabled.
+    pub fn forced_only(&self) -> bool {
+        self.forced_only
+    }
+
     /// Get the cue start time in milliseconds.
     pub fn get_cue_start_time(&self, index: usize) -> f64 {
         self.timestamps_ms
@@ -258,6 +578,57 @@ impl VobSubParser {
             .saturating_sub(start_time) as f64
     }
 
+    /// Get the number of display intervals of a cue. Control sequences that
+    /// change colours, area or visibility split a cue into several intervals.
+    pub fn get_sub_cue_count(&mut self, index: usize) -> usize {
+        self.ensure_packet_cached(index);
+        self.cached_packet(index)
+            .map_or(0, |packet| packet.sub_cues.len())
+    }
+
+    /// Get the start and end time in milliseconds of a display interval,
+    /// clamped to the cue's own end time.
+    fn sub_cue_bounds(&mut self, index: usize, sub_index: usize) -> Option<(u32, u32)> {
+        let start_time = *self.timestamps_ms.get(index)?;
+        let end_time = self.calculate_end_time(index, start_time);
+        let sub_cue = self.cached_packet(index)?.sub_cues.get(sub_index)?;
+
+        let sub_start = start_time
+            .saturating_add(sub_cue.start_offset_ms)
+            .min(end_time);
+        let sub_end = start_time
+            .saturating_add(sub_cue.end_offset_ms)
+            .min(end_time);
+        Some((sub_start, sub_end))
+    }
+
+    /// Get the start time in milliseconds of a display interval.
+    pub fn get_sub_cue_start_time(&mut self, index: usize, sub_index: usize) -> f64 {
+        self.sub_cue_bounds(index, sub_index)
+            .map_or(-1.0, |(start, _)| start as f64)
+    }
+
+    /// Get the end time in milliseconds of a display interval.
+    pub fn get_sub_cue_end_time(&mut self, index: usize, sub_index: usize) -> f64 {
+        self.sub_cue_bounds(index, sub_index)
+            .map_or(-1.0, |(_, end)| end as f64)
+    }
+
+    /// Find the display interval of a cue visible at a timestamp in milliseconds.
+    /// Returns -1 if the cue shows nothing at this time.
+    pub fn find_sub_cue_at_timestamp(&mut self, index: usize, time_ms: f64) -> i32 {
+        let time_ms_u32 = time_ms as u32;
+        for sub_index in 0..self.get_sub_cue_count(index) {
+            if let Some((start, end)) = self.sub_cue_bounds(index, sub_index)
+                && time_ms_u32 >= start
+                && time_ms_u32 < end
+            {
+                return sub_index as i32;
+            }
+        }
+        -1
+    }
+
     /// Get the cue file position in the SUB file.
     pub fn get_cue_file_position(&self, index: usize) -> f64 {
         self.idx_data
@@ -273,16 +644,131 @@ impl VobSubParser {
         loaded_from_idx: bool,
     ) {
         self.timestamps_ms = idx_data.timestamps.iter().map(|t| t.timestamp_ms).collect();
+        self.forced_only = idx_data.settings.forced_subs;
         self.idx_data = Some(idx_data);
         self.sub_data = Some(sub_data);
         self.loaded_from_idx = loaded_from_idx;
     }
 
+    /// Mark IDX cues whose packets are now complete. Returns how many became
+    /// renderable.
+    fn resolve_fed_cues(&mut self) -> usize {
+        let (Some(idx_data), Some(sub_data), Some(feed)) = (
+            self.idx_data.as_ref(),
+            self.sub_data.as_deref(),
+            self.feed.as_mut(),
+        ) else {
+            return 0;
+        };
+        feed.ready.resize(idx_data.timestamps.len(), false);
+
+        let mut resolved = 0;
+        for (ready, timestamp) in feed.ready.iter_mut().zip(&idx_data.timestamps) {
+            let position = timestamp.file_position as usize;
+            if *ready || position >= sub_data.len() {
+                continue;
+            }
+            match try_parse_subtitle_packet(sub_data, position, None) {
+                SubtitlePacketParseAttempt::Complete(_, end) => {
+                    *ready = true;
+                    resolved += 1;
+                    feed.resolved_len = feed.resolved_len.max(end);
+                }
+                // Rendering reports the invalid packet
+                SubtitlePacketParseAttempt::Invalid => *ready = true,
+                SubtitlePacketParseAttempt::Incomplete => {}
+            }
+        }
+        resolved
+    }
+
+    /// Index the complete packets of newly fed IDX-less data. Returns how
+    /// many were added.
+    fn index_fed_packets(&mut self) -> usize {
+        const PACK_START_CODE: [u8; 4] = [0x00, 0x00, 0x01, 0xBA];
+
+        let (Some(idx_data), Some(sub_data), Some(feed)) = (
+            self.idx_data.as_mut(),
+            self.sub_data.as_deref(),
+            self.feed.as_mut(),
+        ) else {
+            return 0;
+        };
+
+        let mut added = Vec::new();
+        let mut offset = feed.resolved_len;
+        while let Some(pos) = memmem::find(&sub_data[offset..], &PACK_START_CODE) {
+            let candidate = offset + pos;
+            match try_parse_subtitle_packet(sub_data, candidate, feed.stream_id) {
+                SubtitlePacketParseAttempt::Complete(packet, end) => {
+                    if packet.width > 0 && packet.height > 0 {
+                        feed.stream_id.get_or_insert(packet.stream_id);
+                        feed.estimator.add_packet(&packet, sub_data);
+                        added.push(VobSubTimestamp {
+                            timestamp_ms: packet.timestamp_ms,
+                            file_position: candidate as u64,
+                            duration_ms: None,
+                        });
+                    }
+                    offset = end.max(candidate + 1);
+                }
+                SubtitlePacketParseAttempt::Invalid => offset = candidate + 1,
+                SubtitlePacketParseAttempt::Incomplete => {
+                    offset = candidate;
+                    break;
+                }
+            }
+        }
+        if memmem::find(&sub_data[offset..], &PACK_START_CODE).is_none() {
+            // Keep a start code split across chunks pending
+            offset = offset.max(sub_data.len().saturating_sub(PACK_START_CODE.len() - 1));
+        }
+        feed.resolved_len = offset;
+
+        if added.is_empty() {
+            return 0;
+        }
+        let count = added.len();
+        let in_order = match (idx_data.timestamps.last(), added.first()) {
+            (Some(last), Some(first)) => last.timestamp_ms <= first.timestamp_ms,
+            _ => true,
+        } && added.is_sorted_by_key(|t| t.timestamp_ms);
+        idx_data.timestamps.extend(added);
+        if !in_order {
+            // Indices shift, so cached packets no longer line up
+            idx_data.timestamps.sort_by_key(|t| t.timestamp_ms);
+            self.packet_cache.clear();
+        }
+
+        if let Some(track) = idx_data.tracks.first_mut() {
+            track.timestamps = idx_data.timestamps.clone();
+            if let Some(stream_id) = feed.stream_id {
+                track.index = (stream_id - VOBSUB_STREAM_ID_FIRST) as u32;
+            }
+        }
+        idx_data.palette = match &self.palette_override {
+            Some(palette) => palette.clone(),
+            None => feed.estimator.palette(&VobSubPalette::default()),
+        };
+        self.timestamps_ms = idx_data.timestamps.iter().map(|t| t.timestamp_ms).collect();
+        count
+    }
+
     /// Calculate the end time for a subtitle at the given index.
     fn calculate_end_time(&mut self, index: usize, start_time: u32) -> u32 {
         // Maximum duration for the last subtitle (no next subtitle to clamp to)
         const MAX_LAST_DURATION_MS: u32 = 5000;
 
+        // A container-provided duration is exact
+        if let Some(duration) = self
+            .idx_data
+            .as_ref()
+            .and_then(|idx_data| idx_data.timestamps.get(index))
+            .and_then(|timestamp| timestamp.duration_ms)
+        {
+            return start_time.saturating_add(duration);
+        }
+
         // Try to get explicit duration from control sequence first
         self.ensure_packet_cached(index);
         let explicit_duration = self
@@ -320,9 +806,17 @@ impl VobSubParser {
             return Some(());
         }
 
-        let Some(sub_data) = self.sub_data.as_ref() else {
+        // While feeding, IDX cues wait until their packet is complete
+        if self.loaded_from_idx
+            && self
+                .feed
+                .as_ref()
+                .is_some_and(|feed| !feed.ready.get(index).copied().unwrap_or(false))
+        {
             return None;
-        };
+        }
+
+        let sub_data = self.sub_data.as_ref()?;
 
         let packet = {
             let idx_data = self.idx_data.as_ref()?;
@@ -373,7 +867,75 @@ impl VobSubParser {
             return None;
         };
 
-        Some(self.render_packet(packet, sub_data, &idx_data.palette, &idx_data.metadata))
+        Some(self.render_packet(packet, sub_data, idx_data))
+    }
+
+    /// Render one display interval of a cue and return RGBA data.
+    pub fn render_sub_cue_at_index(
+        &mut self,
+        index: usize,
+        sub_index: usize,
+    ) -> Option<VobSubFrame> {
+        self.last_render_issue = None;
+
+        if index >= self.timestamps_ms.len() {
+            self.last_render_issue = Some("INDEX_OUT_OF_RANGE".to_string());
+            return None;
+        }
+
+        if self.ensure_packet_cached(index).is_none() {
+            self.last_render_issue = Some("NO_DATA".to_string());
+            return None;
+        }
+
+        let (Some(idx_data), Some(sub_data)) = (self.idx_data.as_ref(), self.sub_data.as_ref())
+        else {
+            self.last_render_issue = Some("NO_DATA".to_string());
+            return None;
+        };
+        let Some(packet) = self.cached_packet(index) else {
+            self.last_render_issue = Some("INVALID_PACKET".to_string());
+            return None;
+        };
+        let Some(sub_packet) = packet.sub_cue_packet(sub_index) else {
+            self.last_render_issue = Some("INDEX_OUT_OF_RANGE".to_string());
+            return None;
+        };
+
+        Some(self.render_packet(&sub_packet, sub_data, idx_data))
+    }
+
+    /// Render the display interval visible at a timestamp in seconds, so
+    /// colour, area and visibility changes within a packet play back, with
+    /// the `fadein/out:` opacity applied to the alpha.
+    /// Returns `None` when nothing is shown at that time.
+    pub fn render_at_timestamp(&mut self, time_seconds: f64) -> Option<VobSubFrame> {
+        let time_ms = time_seconds * 1000.0;
+        let index = self.find_index_at_timestamp(time_ms);
+        if index < 0 {
+            return None;
+        }
+
+        let index = index as usize;
+        let mut frame = if self.get_sub_cue_count(index) == 0 {
+            // Reports why the packet is unavailable
+            self.render_at_index(index)
+        } else {
+            let sub_index = self.find_sub_cue_at_timestamp(index, time_ms);
+            if sub_index < 0 {
+                self.last_render_issue = None;
+                return None;
+            }
+            self.render_sub_cue_at_index(index, sub_index as usize)
+        }?;
+
+        let opacity = self.get_cue_opacity(index, time_ms);
+        if opacity < 1.0 {
+            for pixel in frame.rgba.chunks_exact_mut(4) {
+                pixel[3] = (pixel[3] as f64 * opacity).round() as u8;
+            }
+        }
+        Some(frame)
     }
 
     /// Render a packet to a frame.
@@ -381,10 +943,11 @@ impl VobSubParser {
         &self,
         packet: &SubtitlePacket,
         sub_data: &[u8],
-        palette: &VobSubPalette,
-        metadata: &super::VobSubMetadata,
+        idx_data: &IdxParseResult,
     ) -> VobSubFrame {
-        let mut rgba = decode_vobsub_rle(packet, sub_data, palette);
+        let settings = &idx_data.settings;
+        let mut rgba =
+            decode_vobsub_rle_with_settings(packet, sub_data, &idx_data.palette, settings);
 
         // Apply debanding if enabled
         if self.deband_config.enabled {
@@ -396,13 +959,36 @@ impl VobSubParser {
             );
         }
 
+        let mut width = packet.width;
+        let mut height = packet.height;
+        if settings.is_scaled() && !rgba.is_empty() {
+            let scaled_width = scale_dimension(packet.width, settings.scale_x_percent);
+            let scaled_height = scale_dimension(packet.height, settings.scale_y_percent);
+            if scaled_width as usize * scaled_height as usize <= MAX_VOBSUB_IMAGE_PIXELS {
+                rgba = scale_rgba_nearest(
+                    &rgba,
+                    packet.width,
+                    packet.height,
+                    scaled_width,
+                    scaled_height,
+                );
+                width = scaled_width;
+                height = scaled_height;
+            }
+        }
+
+        // `org:` shifts and `scale:` stretches the subtitle position
+        let position = |value: u16, origin: i32, scale: u32| {
+            (origin as i64 + value as i64 * scale as i64 / 100).clamp(0, u16::MAX as i64) as u16
+        };
+
         VobSubFrame {
-            screen_width: metadata.width,
-            screen_height: metadata.height,
-            x: packet.x,
-            y: packet.y,
-            width: packet.width,
-            height: packet.height,
+            screen_width: idx_data.metadata.width,
+            screen_height: idx_data.metadata.height,
+            x: position(packet.x, settings.origin_x, settings.scale_x_percent),
+            y: position(packet.y, settings.origin_y, settings.scale_y_percent),
+            width,
+            height,
             rgba,
         }
     }
@@ -433,16 +1019,194 @@ impl VobSubParser {
     }
 }
 
+/// Scan SUB data for subtitle packets of one sub-stream (or of any stream
+/// when `stream_id` is `None`) and build an IDX-equivalent timeline.
+fn scan_sub_stream(sub_data: &[u8], stream_id: Option<u8>) -> IdxParseResult {
+    let timestamps = scan_sub_timestamps(sub_data, stream_id);
+    let index = stream_id.map_or(0, |id| (id - VOBSUB_STREAM_ID_FIRST) as u32);
+    IdxParseResult {
+        palette: estimate_sub_palette(sub_data, &timestamps),
+        tracks: vec![VobSubTrack {
+            language: None,
+            index,
+            timestamps: timestamps.clone(),
+            delay_ms: 0,
+        }],
+        timestamps,
+        metadata: Default::default(),
+        default_track: 0,
+        selected_track: 0,
+        settings: VobSubIdxSettings::default(),
+    }
+}
+
+/// Estimate a palette for SUB data without an IDX from an even sample of its
+/// packets.
+fn estimate_sub_palette(sub_data: &[u8], timestamps: &[VobSubTimestamp]) -> VobSubPalette {
+    const MAX_SAMPLED_PACKETS: usize = 256;
+
+    let step = timestamps.len().div_ceil(MAX_SAMPLED_PACKETS).max(1);
+    let mut estimator = VobSubPaletteEstimator::new();
+    for timestamp in timestamps.iter().step_by(step) {
+        if let Some((packet, _)) =
+            parse_subtitle_packet_for_stream(sub_data, timestamp.file_position as usize, None)
+        {
+            estimator.add_packet(&packet, sub_data);
+        }
+    }
+    estimator.palette(&VobSubPalette::default())
+}
+
+/// Find the packets of one sub-stream (or of any stream when `stream_id` is
+/// `None`) in SUB data, sorted by timestamp.
+fn scan_sub_timestamps(sub_data: &[u8], stream_id: Option<u8>) -> Vec<VobSubTimestamp> {
+    // Pre-allocate with estimate (roughly 1 subtitle per 10KB)
+    let estimated_count = (sub_data.len() / 10000).max(32);
+    let mut timestamps: Vec<VobSubTimestamp> = Vec::with_capacity(estimated_count);
+    let mut offset = 0;
+    let len = sub_data.len();
+
+    // Look for MPEG-2 PS pack start code
+    while offset < len.saturating_sub(4) {
+        // Find next potential start code (0x00 0x00 0x01 0xBA)
+        if let Some(pos) = memchr(0x00, &sub_data[offset..]) {
+            let candidate = offset + pos;
+
+            // Check for full start code: 00 00 01 BA
+            if candidate + 3 < len
+                && sub_data[candidate + 1] == 0x00
+                && sub_data[candidate + 2] == 0x01
+                && sub_data[candidate + 3] == 0xBA
+                && let Some((packet, _)) =
+                    parse_subtitle_packet_for_stream(sub_data, candidate, stream_id)
+                && packet.width > 0
+                && packet.height > 0
+            {
+                timestamps.push(VobSubTimestamp {
+                    timestamp_ms: packet.timestamp_ms,
+                    file_position: candidate as u64,
+                    duration_ms: None,
+                });
+            }
+            offset = candidate + 1;
+        } else {
+            // No more 0x00 bytes found
+            break;
+        }
+    }
+
+    timestamps.sort_by_key(|t| t.timestamp_ms);
+    timestamps
+}
+
+/// Scale a dimension by a percentage, keeping at least one pixel.
+fn scale_dimension(value: u16, percent: u32) -> u16 {
+    (value as u32 * percent / 100).clamp(1, u16::MAX as u32) as u16
+}
+
+/// Resize an RGBA bitmap with nearest-neighbour sampling.
+fn scale_rgba_nearest(
+    rgba: &[u8],
+    width: u16,
+    height: u16,
+    new_width: u16,
+    new_height: u16,
+) -> Vec<u8> {
+    let (width, height) = (width as usize, height as usize);
+    let (new_width, new_height) = (new_width as usize, new_height as usize);
+    let mut out = vec![0u8; new_width * new_height * 4];
+
+    for y in 0..new_height {
+        let src_y = y * height / new_height;
+        for x in 0..new_width {
+            let src_x = x * width / new_width;
+            let src = (src_y * width + src_x) * 4;
+            let dst = (y * new_width + x) * 4;
+            out[dst..dst + 4].copy_from_slice(&rgba[src..src + 4]);
+        }
+    }
+
+    out
+}
+
 impl Default for VobSubParser {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// A VobSub subtitle frame.
+pub struct VobSubFrame {
+    pub screen_width: u16,
+    pub screen_height: u16,
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+    pub rgba: Vec<u8>,
+}
+
+impl VobSubFrame {
+    pub fn screen_width(&self) -> u16 {
+        self.screen_width
+    }
+    pub fn screen_height(&self) -> u16 {
+        self.screen_height
+    }
+    pub fn x(&self) -> u16 {
+        self.x
+    }
+    pub fn y(&self) -> u16 {
+        self.y
+    }
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Get RGBA pixel data.
+    pub fn get_rgba(&self) -> &[u8] {
+        &self.rgba
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Build a Private Stream 1 PES packet holding a 2x1 subtitle.
+    fn sub_pes(stream_id: u8, pts_ms: u32, forced: bool) -> Vec<u8> {
+        let start_cmd = if forced { 0x00 } else { 0x01 };
+        let mut spu = vec![0x00, 0x00, 0x00, 0x06, 0x90, 0x00];
+        spu.extend_from_slice(&[0x00, 0x00, 0x00, 0x06, start_cmd]);
+        spu.extend_from_slice(&[0x05, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00]);
+        spu.extend_from_slice(&[0x06, 0x00, 0x04, 0x00, 0x04, 0xff]);
+        spu_pes(stream_id, pts_ms, spu)
+    }
+
+    /// Wrap an SPU (with a placeholder size) in a Private Stream 1 PES packet.
+    fn spu_pes(stream_id: u8, pts_ms: u32, mut spu: Vec<u8>) -> Vec<u8> {
+        let size = spu.len() as u16;
+        spu[..2].copy_from_slice(&size.to_be_bytes());
+
+        let pts = pts_ms as u64 * 90;
+        let mut pes = vec![0x00, 0x00, 0x01, 0xBD];
+        pes.extend_from_slice(&((spu.len() + 9) as u16).to_be_bytes());
+        pes.extend_from_slice(&[0x81, 0x80, 0x05]);
+        pes.extend_from_slice(&[
+            0x21 | ((pts >> 29) as u8 & 0x0E),
+            (pts >> 22) as u8,
+            ((pts >> 14) as u8 & 0xFE) | 0x01,
+            (pts >> 7) as u8,
+            ((pts << 1) as u8 & 0xFE) | 0x01,
+        ]);
+        pes.push(stream_id);
+        pes.extend(spu);
+        pes
+    }
+
     #[test]
     fn dispose_restores_default_deband_config() {
         let mut parser = VobSubParser::new();
@@ -483,41 +1247,296 @@ timestamp: 00:00:05:500, filepos: 00001000
         parser.attach_sub_data(vec![0u8; 32]);
         assert!(parser.has_sub_data());
     }
-}
 
-/// A VobSub subtitle frame.
-pub struct VobSubFrame {
-    pub screen_width: u16,
-    pub screen_height: u16,
-    pub x: u16,
-    pub y: u16,
-    pub width: u16,
-    pub height: u16,
-    pub rgba: Vec<u8>,
-}
+    #[test]
+    fn select_track_switches_timeline_and_keeps_sub_data() {
+        let idx = "\
+size: 720x576
+langidx: 1
+id: en, index: 0
+timestamp: 00:00:01:000, filepos: 00000000
+timestamp: 00:00:03:000, filepos: 00000800
+id: ja, index: 1
+timestamp: 00:00:02:000, filepos: 00001000
+";
 
-impl VobSubFrame {
-    pub fn screen_width(&self) -> u16 {
-        self.screen_width
+        let mut parser = VobSubParser::new();
+        parser.load_from_data(idx, vec![0u8; 32]);
+
+        assert_eq!(parser.track_count(), 2);
+        assert_eq!(parser.default_track(), 1);
+        assert_eq!(parser.selected_track(), 1);
+        assert_eq!(parser.language(), "ja");
+        assert_eq!(parser.get_timestamps(), vec![2000.0]);
+
+        assert!(parser.select_track(0));
+        assert_eq!(parser.track_language(0), "en");
+        assert_eq!(parser.track_stream_index(1), 1);
+        assert_eq!(parser.language(), "en");
+        assert_eq!(parser.track_id(), "0");
+        assert_eq!(parser.get_timestamps(), vec![1000.0, 3000.0]);
+        assert_eq!(parser.get_cue_file_position(1), 2048.0);
+        assert!(parser.has_sub_data());
+        assert!(!parser.select_track(2));
     }
-    pub fn screen_height(&self) -> u16 {
-        self.screen_height
+
+    #[test]
+    fn fade_directives_drive_cue_opacity() {
+        let idx = "\
+size: 720x576
+fadein/out: 100, 200
+timestamp: 00:00:01:000, filepos: 00000000
+timestamp: 00:00:02:000, filepos: 00000800
+";
+
+        let mut parser = VobSubParser::new();
+        parser.load_from_data(idx, vec![0u8; 32]);
+
+        assert_eq!(parser.get_cue_opacity(0, 999.0), 0.0);
+        assert_eq!(parser.get_cue_opacity(0, 1050.0), 0.5);
+        assert_eq!(parser.get_cue_opacity(0, 1500.0), 1.0);
+        assert_eq!(parser.get_cue_opacity(0, 1900.0), 0.5);
     }
-    pub fn x(&self) -> u16 {
-        self.x
+
+    #[test]
+    fn render_at_timestamp_follows_control_sequences() {
+        let idx = "\
+size: 720x576
+palette: 000000, ffffff, 000000, 808080, 000000, 000000, 000000, 000000, 000000, 000000, 000000, 000000, 000000, 000000, 000000, 000000
+timestamp: 00:00:01:000, filepos: 00000000
+";
+        // Area only at 0 ms, shown at 1024 ms, recoloured at 2048 ms and
+        // stopped at 3072 ms
+        let mut spu = vec![0x00, 0x00, 0x00, 0x06, 0x90, 0x00];
+        spu.extend_from_slice(&[0x00, 0x00, 0x00, 0x17]);
+        spu.extend_from_slice(&[0x05, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00]);
+        spu.extend_from_slice(&[0x06, 0x00, 0x04, 0x00, 0x05, 0xff]);
+        spu.extend_from_slice(&[0x00, 0x5a, 0x00, 0x1d, 0x01, 0xff]);
+        spu.extend_from_slice(&[0x00, 0xb4, 0x00, 0x25, 0x03, 0x32, 0x20, 0xff]);
+        spu.extend_from_slice(&[0x01, 0x0e, 0x00, 0x25, 0x02, 0xff]);
+
+        let mut parser = VobSubParser::new();
+        parser.load_from_data(idx, spu_pes(0x20, 0, spu));
+        assert_eq!(parser.get_sub_cue_count(0), 2);
+
+        let pixel_at = |parser: &mut VobSubParser, time_seconds: f64| {
+            parser
+                .render_at_timestamp(time_seconds)
+                .map(|frame| frame.rgba[..4].to_vec())
+        };
+        assert_eq!(parser.find_index_at_timestamp(1500.0), 0);
+        assert_eq!(pixel_at(&mut parser, 1.5), None);
+        assert_eq!(pixel_at(&mut parser, 2.5), Some(vec![255, 255, 255, 255]));
+        assert_eq!(pixel_at(&mut parser, 3.5), Some(vec![0, 0, 0, 255]));
+        assert_eq!(pixel_at(&mut parser, 4.5), None);
     }
-    pub fn y(&self) -> u16 {
-        self.y
+
+    #[test]
+    fn fade_directives_scale_rendered_alpha() {
+        let idx = "\
+size: 720x576
+palette: 000000, ffffff, 000000, 808080, 000000, 000000, 000000, 000000, 000000, 000000, 000000, 000000, 000000, 000000, 000000, 000000
+fadein/out: 100, 200
+timestamp: 00:00:01:000, filepos: 00000000
+";
+        let mut spu = vec![0x00, 0x00, 0x00, 0x06, 0x90, 0x00];
+        spu.extend_from_slice(&[0x00, 0x00, 0x00, 0x06, 0x01]);
+        spu.extend_from_slice(&[0x05, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00]);
+        spu.extend_from_slice(&[0x06, 0x00, 0x04, 0x00, 0x05, 0xff]);
+
+        let mut parser = VobSubParser::new();
+        parser.load_from_data(idx, spu_pes(0x20, 0, spu));
+        assert_eq!(parser.get_cue_end_time(0), 6000.0);
+
+        let alpha_at = |parser: &mut VobSubParser, time_seconds: f64| {
+            parser
+                .render_at_timestamp(time_seconds)
+                .map(|frame| frame.rgba[3])
+        };
+        assert_eq!(alpha_at(&mut parser, 1.05), Some(128));
+        assert_eq!(alpha_at(&mut parser, 3.0), Some(255));
+        assert_eq!(alpha_at(&mut parser, 5.95), Some(64));
+        assert_eq!(
+            parser.render_at_index(0).map(|frame| frame.rgba[3]),
+            Some(255)
+        );
     }
-    pub fn width(&self) -> u16 {
-        self.width
+
+    #[test]
+    fn scale_rgba_nearest_duplicates_pixels() {
+        let rgba = [1, 1, 1, 1, 2, 2, 2, 2];
+        let scaled = scale_rgba_nearest(&rgba, 2, 1, 4, 2);
+        assert_eq!(scaled.len(), 4 * 2 * 4);
+        assert_eq!(
+            &scaled[..16],
+            [1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2]
+        );
+        assert_eq!(scale_dimension(3, 50), 1);
     }
-    pub fn height(&self) -> u16 {
-        self.height
+
+    #[test]
+    fn sub_only_loading_estimates_palette() {
+        use crate::vobsub::{VobSubBitmap, VobSubColorRole, VobSubEncoder};
+
+        // Distinct palette colours so the encoder picks indices 5 (fill) and 9 (outline)
+        let mut palette = VobSubPalette::default();
+        palette.rgba[5] = 0xFF00FFFF;
+        palette.rgba[9] = 0xFFFF0000;
+        let (width, height) = (12u16, 6u16);
+        let rgba: Vec<u8> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .flat_map(|(x, y)| match (x, y) {
+                (2..=9, 2..=3) => [0xFF, 0xFF, 0x00, 0xFF],
+                (1..=10, 1..=4) => [0x00, 0x00, 0xFF, 0xFF],
+                _ => [0, 0, 0, 0],
+            })
+            .collect();
+        let bitmap = VobSubBitmap {
+            x: 10,
+            y: 10,
+            width,
+            height,
+            rgba,
+            forced: false,
+        };
+        let mut encoder = VobSubEncoder::new(720, 480, palette);
+        encoder.add_cue(1000, 2000, &bitmap).unwrap();
+        let sub = encoder.finish().sub_data;
+
+        let mut parser = VobSubParser::new();
+        parser.load_from_sub_only(sub);
+        let estimated = parser.palette().unwrap();
+        assert_eq!(estimated.rgba[5], VobSubColorRole::Fill.color());
+        assert_eq!(estimated.rgba[9], VobSubColorRole::Outline.color());
+
+        let mut custom = VobSubPalette::default();
+        custom.rgba[5] = 0xFF0000FF;
+        parser.set_palette(custom);
+        let frame = parser.render_at_index(0).unwrap();
+        assert_eq!(
+            &frame.get_rgba()[(2 * 12 + 2) * 4..][..4],
+            [0xFF, 0, 0, 0xFF]
+        );
+        assert!(parser.select_sub_stream(0x20));
+        assert_eq!(parser.palette().unwrap().rgba[5], 0xFF0000FF);
     }
 
-    /// Get RGBA pixel data.
-    pub fn get_rgba(&self) -> &[u8] {
-        &self.rgba
+    fn encoded_cues(count: u32) -> crate::vobsub::EncodedVobSub {
+        use crate::vobsub::{VobSubBitmap, VobSubEncoder};
+
+        let bitmap = VobSubBitmap {
+            x: 0,
+            y: 0,
+            width: 300,
+            height: 20,
+            rgba: (0..300 * 20)
+                .flat_map(|i| if i % 3 == 0 { [255; 4] } else { [0, 0, 0, 255] })
+                .collect(),
+            forced: false,
+        };
+        let mut encoder = VobSubEncoder::new(720, 480, VobSubPalette::default());
+        for cue in 0..count {
+            encoder
+                .add_cue(cue * 1000, cue * 1000 + 500, &bitmap)
+                .unwrap();
+        }
+        encoder.finish()
+    }
+
+    #[test]
+    fn feed_indexes_sub_only_packets_as_they_complete() {
+        let sub = encoded_cues(3).sub_data;
+        let mut expected = VobSubParser::new();
+        expected.load_from_sub_only(sub.clone());
+
+        let mut parser = VobSubParser::new();
+        let mut resolved = Vec::new();
+        for chunk in sub.chunks(1000) {
+            resolved.push(parser.feed(chunk));
+            assert!(parser.pending_len() < 3 * crate::vobsub::DVD_SECTOR_SIZE);
+        }
+        assert_eq!(resolved.iter().sum::<usize>(), 3);
+        assert_eq!(resolved[0], 0);
+        assert_eq!(parser.finish_feed(), 3);
+        assert_eq!(parser.pending_len(), 0);
+        assert_eq!(parser.get_timestamps(), expected.get_timestamps());
+        assert_eq!(
+            parser.render_at_index(2).unwrap().get_rgba(),
+            expected.render_at_index(2).unwrap().get_rgba()
+        );
+    }
+
+    #[test]
+    fn feed_resolves_idx_cues_once_their_packets_arrive() {
+        let encoded = encoded_cues(2);
+        let second = parser_file_position(&encoded.idx_content, 1);
+
+        let mut parser = VobSubParser::new();
+        parser.load_from_idx(&encoded.idx_content);
+        assert_eq!(parser.feed(&encoded.sub_data[..second - 100]), 1);
+        assert!(parser.render_at_index(0).is_some());
+        assert!(parser.render_at_index(1).is_none());
+        assert_eq!(
+            parser.feed(&encoded.sub_data[second - 100..second + 100]),
+            0
+        );
+        assert!(parser.render_at_index(1).is_none());
+        assert_eq!(parser.feed(&encoded.sub_data[second + 100..]), 1);
+        assert!(parser.render_at_index(1).is_some());
+        assert_eq!(parser.finish_feed(), 2);
+    }
+
+    fn parser_file_position(idx_content: &str, index: usize) -> usize {
+        let mut parser = VobSubParser::new();
+        parser.load_from_idx(idx_content);
+        parser.get_cue_file_position(index) as usize
+    }
+
+    #[test]
+    fn sub_only_loading_enumerates_and_selects_streams() {
+        const PACK_HEADER: [u8; 14] = [
+            0x00, 0x00, 0x01, 0xBA, 0x44, 0x00, 0x04, 0x00, 0x04, 0x01, 0x00, 0x00, 0x03, 0xF8,
+        ];
+        let mut sub = Vec::new();
+        for (stream_id, pts_ms) in [(0x20, 1000), (0x21, 2000), (0x20, 3000)] {
+            sub.extend_from_slice(&PACK_HEADER);
+            sub.extend(sub_pes(stream_id, pts_ms, false));
+        }
+
+        let mut parser = VobSubParser::new();
+        parser.load_from_sub_only(sub);
+
+        assert_eq!(parser.sub_stream_ids(), vec![0x20, 0x21]);
+        assert_eq!(parser.get_timestamps(), vec![1000.0, 3000.0]);
+
+        assert!(parser.select_sub_stream(0x21));
+        assert_eq!(parser.get_timestamps(), vec![2000.0]);
+        assert_eq!(parser.track_stream_index(0), 1);
+        assert!(parser.render_at_index(0).is_some());
+    }
+
+    #[test]
+    fn forced_only_mode_filters_timeline() {
+        let mut sub = sub_pes(0x20, 1000, false);
+        let forced_position = sub.len();
+        sub.extend(sub_pes(0x20, 3000, true));
+        let idx = format!(
+            "forced subs: ON\n\
+             timestamp: 00:00:01:000, filepos: 00000000\n\
+             timestamp: 00:00:03:000, filepos: {forced_position:08x}\n"
+        );
+
+        let mut parser = VobSubParser::new();
+        parser.load_from_data(&idx, sub);
+
+        assert!(parser.forced_only());
+        assert!(!parser.is_cue_forced(0));
+        assert!(parser.is_cue_forced(1));
+        assert_eq!(parser.get_forced_cue_indices(), vec![1]);
+        assert_eq!(parser.find_index_at_timestamp(1500.0), -1);
+        assert_eq!(parser.find_index_at_timestamp(3500.0), 1);
+
+        parser.set_forced_only(false);
+        assert_eq!(parser.find_index_at_timestamp(1500.0), 0);
     }
 }
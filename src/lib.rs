@@ -1,7 +1,10 @@
 //! WASM bindings for libbitsub.
 
-use js_sys::{Float64Array, Uint8Array};
+use std::io::{Read, Seek, SeekFrom};
+
+use js_sys::{Float64Array, Function, Uint8Array, Uint32Array};
 use libbitsub_core as core;
+use wasm_bindgen::JsCast;
 use wasm_bindgen::prelude::*;
 
 // Use dlmalloc as the global allocator.
@@ -23,6 +26,254 @@ fn timestamps_to_array(timestamps: Vec<f64>) -> Float64Array {
     arr
 }
 
+/// List the PIDs of the PGS streams in M2TS/TS data.
+#[wasm_bindgen(js_name = listTransportStreamPgsPids)]
+pub fn list_transport_stream_pgs_pids(data: &[u8]) -> Uint32Array {
+    let pids: Vec<u32> = core::list_pgs_streams(data)
+        .into_iter()
+        .map(|stream| stream.pid as u32)
+        .collect();
+    Uint32Array::from(pids.as_slice())
+}
+
+/// List the languages of the PGS streams in M2TS/TS data, in the same order as
+/// `listTransportStreamPgsPids` (empty when unknown).
+#[wasm_bindgen(js_name = listTransportStreamPgsLanguages)]
+pub fn list_transport_stream_pgs_languages(data: &[u8]) -> Vec<String> {
+    core::list_pgs_streams(data)
+        .into_iter()
+        .map(|stream| stream.language.unwrap_or_default())
+        .collect()
+}
+
+/// A DVB subtitle service carried in M2TS/TS data.
+#[wasm_bindgen]
+pub struct DvbTransportService {
+    pid: u16,
+    inner: core::DvbSubtitleService,
+}
+
+#[wasm_bindgen]
+impl DvbTransportService {
+    #[wasm_bindgen(getter)]
+    pub fn pid(&self) -> u16 {
+        self.pid
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn language(&self) -> Option<String> {
+        self.inner.language.clone()
+    }
+
+    #[wasm_bindgen(getter, js_name = subtitlingType)]
+    pub fn subtitling_type(&self) -> u8 {
+        self.inner.subtitling_type
+    }
+
+    #[wasm_bindgen(getter, js_name = compositionPageId)]
+    pub fn composition_page_id(&self) -> u16 {
+        self.inner.composition_page_id
+    }
+
+    #[wasm_bindgen(getter, js_name = ancillaryPageId)]
+    pub fn ancillary_page_id(&self) -> u16 {
+        self.inner.ancillary_page_id
+    }
+}
+
+/// List the DVB subtitle services in M2TS/TS data, one entry per
+/// subtitling descriptor entry.
+#[wasm_bindgen(js_name = listTransportStreamDvbServices)]
+pub fn list_transport_stream_dvb_services(data: &[u8]) -> Vec<DvbTransportService> {
+    core::list_dvb_streams(data)
+        .into_iter()
+        .flat_map(|stream| {
+            let pid = stream.pid;
+            stream
+                .services
+                .into_iter()
+                .map(move |inner| DvbTransportService { pid, inner })
+        })
+        .collect()
+}
+
+/// A Matroska subtitle track, for building a track picker.
+#[wasm_bindgen]
+pub struct MatroskaTrackInfo {
+    inner: core::mkv::MatroskaTrackInfo,
+}
+
+#[wasm_bindgen]
+impl MatroskaTrackInfo {
+    #[wasm_bindgen(getter, js_name = trackNumber)]
+    pub fn track_number(&self) -> u32 {
+        self.inner.track.track_number as u32
+    }
+
+    #[wasm_bindgen(getter, js_name = codecId)]
+    pub fn codec_id(&self) -> String {
+        self.inner.track.codec_id.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn name(&self) -> Option<String> {
+        self.inner.track.name.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn language(&self) -> Option<String> {
+        self.inner.track.language.clone()
+    }
+
+    #[wasm_bindgen(getter, js_name = languageIetf)]
+    pub fn language_ietf(&self) -> Option<String> {
+        self.inner.track.language_ietf.clone()
+    }
+
+    #[wasm_bindgen(getter, js_name = isDefault)]
+    pub fn is_default(&self) -> bool {
+        self.inner.track.flag_default
+    }
+
+    #[wasm_bindgen(getter, js_name = isForced)]
+    pub fn is_forced(&self) -> bool {
+        self.inner.track.flag_forced
+    }
+
+    #[wasm_bindgen(getter, js_name = isHearingImpaired)]
+    pub fn is_hearing_impaired(&self) -> bool {
+        self.inner.track.flag_hearing_impaired
+    }
+
+    #[wasm_bindgen(getter, js_name = isOriginal)]
+    pub fn is_original(&self) -> bool {
+        self.inner.track.flag_original
+    }
+
+    #[wasm_bindgen(getter, js_name = blockCount)]
+    pub fn block_count(&self) -> usize {
+        self.inner.block_count
+    }
+
+    #[wasm_bindgen(getter, js_name = isSupported)]
+    pub fn is_supported(&self) -> bool {
+        self.inner.is_supported()
+    }
+}
+
+/// List the subtitle tracks of Matroska/MKS data.
+#[wasm_bindgen(js_name = listMatroskaTracks)]
+pub fn list_matroska_tracks(data: &[u8]) -> Result<Vec<MatroskaTrackInfo>, JsValue> {
+    core::mkv::list_matroska_tracks(data)
+        .map(|tracks| {
+            tracks
+                .into_iter()
+                .map(|inner| MatroskaTrackInfo { inner })
+                .collect()
+        })
+        .map_err(|error| JsValue::from_str(&error))
+}
+
+/// A file read through a JavaScript `read(offset, length)` callback that
+/// synchronously returns a `Uint8Array` (e.g. `FileReaderSync` in a worker).
+struct JsRangeSource {
+    read: Function,
+    length: u64,
+    position: u64,
+}
+
+impl JsRangeSource {
+    fn new(read: Function, length: f64) -> Self {
+        Self {
+            read,
+            length: length as u64,
+            position: 0,
+        }
+    }
+}
+
+impl Read for JsRangeSource {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = (buf.len() as u64).min(self.length.saturating_sub(self.position)) as usize;
+        if len == 0 {
+            return Ok(0);
+        }
+
+        let bytes = self
+            .read
+            .call2(
+                &JsValue::NULL,
+                &JsValue::from_f64(self.position as f64),
+                &JsValue::from_f64(len as f64),
+            )
+            .ok()
+            .and_then(|value| value.dyn_into::<Uint8Array>().ok())
+            .ok_or_else(|| std::io::Error::other("read callback did not return a Uint8Array"))?;
+        let count = (bytes.length() as usize).min(len);
+        bytes.subarray(0, count as u32).copy_to(&mut buf[..count]);
+        self.position += count as u64;
+        Ok(count)
+    }
+}
+
+impl Seek for JsRangeSource {
+    fn seek(&mut self, position: SeekFrom) -> std::io::Result<u64> {
+        let position = match position {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => self.length.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta),
+        };
+        self.position = position.ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "seek before start")
+        })?;
+        Ok(self.position)
+    }
+}
+
+/// Reads one subtitle track of a Matroska file through a range callback,
+/// without loading the whole file.
+#[wasm_bindgen]
+pub struct MatroskaReader {
+    inner: core::mkv::MatroskaReader<JsRangeSource>,
+}
+
+#[wasm_bindgen]
+impl MatroskaReader {
+    /// Open a file of `length` bytes. `read(offset, length)` must return the
+    /// requested bytes as a `Uint8Array`, synchronously.
+    #[wasm_bindgen(constructor)]
+    pub fn new(read: Function, length: f64) -> Result<MatroskaReader, JsValue> {
+        core::mkv::MatroskaReader::open(JsRangeSource::new(read, length))
+            .map(|inner| Self { inner })
+            .map_err(|error| JsValue::from_str(&error))
+    }
+
+    /// Select the track to read, by number or the first one of `codecId`.
+    /// Returns the track number.
+    #[wasm_bindgen(js_name = selectTrack)]
+    pub fn select_track(&mut self, codec_id: &str, track: Option<u32>) -> Result<u32, JsValue> {
+        self.inner
+            .select_track(codec_id, track.map(u64::from))
+            .map(|track| track.track_number as u32)
+            .map_err(|error| JsValue::from_str(&error))
+    }
+
+    #[wasm_bindgen(js_name = setFullScan)]
+    pub fn set_full_scan(&mut self, full_scan: bool) {
+        self.inner.set_full_scan(full_scan);
+    }
+
+    #[wasm_bindgen(getter, js_name = isComplete)]
+    pub fn is_complete(&self) -> bool {
+        self.inner.is_complete()
+    }
+
+    #[wasm_bindgen(getter, js_name = bytesRead)]
+    pub fn bytes_read(&self) -> f64 {
+        self.inner.bytes_read() as f64
+    }
+}
+
 /// PGS subtitle parser and renderer exposed to JavaScript.
 #[wasm_bindgen]
 pub struct PgsParser {
@@ -55,6 +306,63 @@ impl PgsParser {
         self.inner.finish_feed()
     }
 
+    /// Parse a PGS stream from M2TS/TS data. Uses the first PGS stream when
+    /// `pid` is omitted.
+    #[wasm_bindgen(js_name = parseTransportStream)]
+    pub fn parse_transport_stream(
+        &mut self,
+        data: &[u8],
+        pid: Option<u16>,
+    ) -> Result<usize, JsValue> {
+        self.inner
+            .parse_transport_stream(data, pid)
+            .map_err(|error| JsValue::from_str(&error))
+    }
+
+    /// Parse an `S_HDMV/PGS` track from Matroska/MKS data. Uses the first PGS
+    /// track when `track` is omitted.
+    #[wasm_bindgen(js_name = parseMatroska)]
+    pub fn parse_matroska(&mut self, data: &[u8], track: Option<u32>) -> Result<usize, JsValue> {
+        self.inner
+            .parse_matroska(data, track.map(u64::from))
+            .map_err(|error| JsValue::from_str(&error))
+    }
+
+    /// Parse an `S_HDMV/PGS` track of a Matroska file read through a
+    /// `MatroskaReader` range callback.
+    #[wasm_bindgen(js_name = parseMatroskaSource)]
+    pub fn parse_matroska_source(
+        &mut self,
+        read: Function,
+        length: f64,
+        track: Option<u32>,
+    ) -> Result<usize, JsValue> {
+        self.inner
+            .parse_matroska_reader(JsRangeSource::new(read, length), track.map(u64::from))
+            .map_err(|error| JsValue::from_str(&error))
+    }
+
+    /// Clear the parser before loading the PGS track selected on `reader`
+    /// with `readMatroskaNext`.
+    #[wasm_bindgen(js_name = startMatroskaReader)]
+    pub fn start_matroska_reader(&mut self, reader: &MatroskaReader) -> Result<(), JsValue> {
+        self.inner
+            .start_matroska_reader(&reader.inner)
+            .map_err(|error| JsValue::from_str(&error))
+    }
+
+    /// Read the next cluster from `reader`. Returns the number of new cues,
+    /// or `undefined` once the track is complete.
+    #[wasm_bindgen(js_name = readMatroskaNext)]
+    pub fn read_matroska_next(
+        &mut self,
+        reader: &mut MatroskaReader,
+    ) -> Result<Option<usize>, JsValue> {
+        self.inner
+            .read_matroska_next(&mut reader.inner)
+            .map_err(|error| JsValue::from_str(&error))
+    }
+
     #[wasm_bindgen(getter, js_name = pendingLen)]
     pub fn pending_len(&self) -> usize {
         self.inner.pending_len()
@@ -80,6 +388,16 @@ impl PgsParser {
         timestamps_to_array(self.inner.get_timestamps())
     }
 
+    #[wasm_bindgen(js_name = getEndTimestamps)]
+    pub fn get_end_timestamps(&self) -> Float64Array {
+        timestamps_to_array(self.inner.get_end_timestamps())
+    }
+
+    #[wasm_bindgen(getter, js_name = displaySetCount)]
+    pub fn display_set_count(&self) -> usize {
+        self.inner.display_set_count()
+    }
+
     #[wasm_bindgen(js_name = findIndexAtTimestamp)]
     pub fn find_index_at_timestamp(&self, time_ms: f64) -> i32 {
         self.inner.find_index_at_timestamp(time_ms)
@@ -95,6 +413,11 @@ impl PgsParser {
         self.inner.get_cue_end_time(index)
     }
 
+    #[wasm_bindgen(js_name = getCueDisplaySetIndex)]
+    pub fn get_cue_display_set_index(&self, index: usize) -> i32 {
+        self.inner.get_cue_display_set_index(index)
+    }
+
     #[wasm_bindgen(js_name = getCueCompositionCount)]
     pub fn get_cue_composition_count(&self, index: usize) -> u32 {
         self.inner.get_cue_composition_count(index)
@@ -110,6 +433,36 @@ impl PgsParser {
         self.inner.get_cue_composition_state(index)
     }
 
+    #[wasm_bindgen(js_name = isCueForced)]
+    pub fn is_cue_forced(&self, index: usize) -> bool {
+        self.inner.is_cue_forced(index)
+    }
+
+    #[wasm_bindgen(js_name = getForcedCueIndices)]
+    pub fn get_forced_cue_indices(&self) -> Uint32Array {
+        Uint32Array::from(self.inner.get_forced_cue_indices().as_slice())
+    }
+
+    #[wasm_bindgen(js_name = setForcedOnly)]
+    pub fn set_forced_only(&mut self, forced_only: bool) {
+        self.inner.set_forced_only(forced_only);
+    }
+
+    #[wasm_bindgen(getter, js_name = forcedOnly)]
+    pub fn forced_only(&self) -> bool {
+        self.inner.forced_only()
+    }
+
+    #[wasm_bindgen(js_name = getCueKeyframeTimestamps)]
+    pub fn get_cue_keyframe_timestamps(&self, index: usize) -> Float64Array {
+        timestamps_to_array(self.inner.get_cue_keyframe_timestamps(index))
+    }
+
+    #[wasm_bindgen(js_name = findKeyframeAtTimestamp)]
+    pub fn find_keyframe_at_timestamp(&self, index: usize, time_ms: f64) -> i32 {
+        self.inner.find_keyframe_at_timestamp(index, time_ms)
+    }
+
     #[wasm_bindgen(js_name = renderAtIndex)]
     pub fn render_at_index(&mut self, index: usize) -> Option<SubtitleFrame> {
         self.inner
@@ -117,6 +470,26 @@ impl PgsParser {
             .map(|inner| SubtitleFrame { inner })
     }
 
+    #[wasm_bindgen(js_name = renderKeyframeAtIndex)]
+    pub fn render_keyframe_at_index(
+        &mut self,
+        index: usize,
+        keyframe_index: usize,
+    ) -> Option<SubtitleFrame> {
+        self.inner
+            .render_keyframe_at_index(index, keyframe_index)
+            .map(|inner| SubtitleFrame { inner })
+    }
+
+    /// Render the cue visible at a timestamp, with its active palette
+    /// keyframe applied.
+    #[wasm_bindgen(js_name = renderAtTimestamp)]
+    pub fn render_at_timestamp(&mut self, time_seconds: f64) -> Option<SubtitleFrame> {
+        self.inner
+            .render_at_timestamp(time_seconds)
+            .map(|inner| SubtitleFrame { inner })
+    }
+
     #[wasm_bindgen(getter, js_name = lastRenderIssue)]
     pub fn last_render_issue(&self) -> String {
         self.inner.last_render_issue()
@@ -126,6 +499,16 @@ impl PgsParser {
     pub fn clear_cache(&mut self) {
         self.inner.clear_cache();
     }
+
+    /// Check the stream against the PGS decoder model. Each entry reads
+    /// `display set <index>: <CODE>: <message>`.
+    pub fn validate(&self) -> Vec<String> {
+        self.inner
+            .validate()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
 }
 
 impl Default for PgsParser {
@@ -134,6 +517,87 @@ impl Default for PgsParser {
     }
 }
 
+/// Blu-ray TextST (text subtitle) parser exposed to JavaScript.
+#[wasm_bindgen]
+pub struct TextStParser {
+    inner: core::TextStParser,
+}
+
+#[wasm_bindgen]
+impl TextStParser {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            inner: core::TextStParser::new(),
+        }
+    }
+
+    pub fn parse(&mut self, data: &[u8]) -> usize {
+        self.inner.parse(data)
+    }
+
+    #[wasm_bindgen(js_name = parseTransportStream)]
+    pub fn parse_transport_stream(
+        &mut self,
+        data: &[u8],
+        pid: Option<u16>,
+    ) -> Result<usize, JsValue> {
+        self.inner
+            .parse_transport_stream(data, pid)
+            .map_err(|error| JsValue::from_str(&error))
+    }
+
+    pub fn reset(&mut self) {
+        self.inner.reset()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> usize {
+        self.inner.count()
+    }
+
+    #[wasm_bindgen(js_name = getTimestamps)]
+    pub fn get_timestamps(&self) -> Float64Array {
+        timestamps_to_array(self.inner.get_timestamps())
+    }
+
+    #[wasm_bindgen(js_name = getEndTimestamps)]
+    pub fn get_end_timestamps(&self) -> Float64Array {
+        timestamps_to_array(self.inner.get_end_timestamps())
+    }
+
+    #[wasm_bindgen(js_name = findIndexAtTimestamp)]
+    pub fn find_index_at_timestamp(&self, time_ms: f64) -> i32 {
+        self.inner.find_index_at_timestamp(time_ms)
+    }
+
+    #[wasm_bindgen(js_name = getCueText)]
+    pub fn get_cue_text(&self, index: usize) -> Option<String> {
+        self.inner.get_cue_text(index)
+    }
+
+    #[wasm_bindgen(js_name = isCueForced)]
+    pub fn is_cue_forced(&self, index: usize) -> bool {
+        self.inner.is_cue_forced(index)
+    }
+
+    pub fn search(&self, query: &str) -> Uint32Array {
+        Uint32Array::from(self.inner.search(query).as_slice())
+    }
+
+    /// Get the cue's palette as 256 packed RGBA values.
+    #[wasm_bindgen(js_name = getCuePalette)]
+    pub fn get_cue_palette(&self, index: usize) -> Uint32Array {
+        Uint32Array::from(self.inner.get_cue_palette(index).unwrap_or_default())
+    }
+}
+
+impl Default for TextStParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// A single PGS subtitle composition element.
 #[wasm_bindgen]
 #[derive(Clone)]
@@ -200,6 +664,51 @@ impl SubtitleFrame {
     }
 }
 
+/// Incremental DVD VOB demuxer: push the title set's VOB files in order, in
+/// chunks of any size, then pass `finish()` to `loadFromDvdSubpictures`.
+#[wasm_bindgen]
+pub struct DvdSubpictureDemuxer {
+    inner: core::DvdSubpictureDemuxer,
+}
+
+#[wasm_bindgen]
+impl DvdSubpictureDemuxer {
+    #[wasm_bindgen(constructor)]
+    pub fn new(stream_id: Option<u8>) -> Self {
+        Self {
+            inner: core::DvdSubpictureDemuxer::new(stream_id),
+        }
+    }
+
+    /// Create a demuxer that keeps the cells of program chain `pgc` of
+    /// `VTS_xx_0.IFO` and rewrites their timestamps to title time.
+    #[wasm_bindgen(js_name = forTitle)]
+    pub fn for_title(
+        ifo_data: &[u8],
+        pgc: usize,
+        stream_id: Option<u8>,
+    ) -> Result<DvdSubpictureDemuxer, JsValue> {
+        let info =
+            core::parse_vts_ifo_pgc(ifo_data, pgc).map_err(|error| JsValue::from_str(&error))?;
+        Ok(Self {
+            inner: core::DvdSubpictureDemuxer::for_title(&info, stream_id),
+        })
+    }
+
+    pub fn push(&mut self, chunk: &[u8]) {
+        self.inner.push(chunk)
+    }
+
+    #[wasm_bindgen(getter, js_name = subDataLen)]
+    pub fn sub_data_len(&self) -> usize {
+        self.inner.sub_data_len()
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.inner.finish()
+    }
+}
+
 /// VobSub subtitle parser and renderer exposed to JavaScript.
 #[wasm_bindgen]
 pub struct VobSubParser {
@@ -236,9 +745,52 @@ impl VobSubParser {
     }
 
     #[wasm_bindgen(js_name = loadFromMks)]
-    pub fn load_from_mks(&mut self, mks_data: &[u8]) -> Result<(), JsValue> {
+    pub fn load_from_mks(&mut self, mks_data: &[u8], track: Option<u32>) -> Result<(), JsValue> {
+        self.inner
+            .load_from_mks(mks_data, track.map(u64::from))
+            .map_err(|error| JsValue::from_str(&error))
+    }
+
+    /// Load an `S_VOBSUB` track of a Matroska file read through a
+    /// `MatroskaReader` range callback.
+    #[wasm_bindgen(js_name = loadFromMatroskaSource)]
+    pub fn load_from_matroska_source(
+        &mut self,
+        read: Function,
+        length: f64,
+        track: Option<u32>,
+    ) -> Result<(), JsValue> {
         self.inner
-            .load_from_mks(mks_data)
+            .load_from_mks_reader(JsRangeSource::new(read, length), track.map(u64::from))
+            .map_err(|error| JsValue::from_str(&error))
+    }
+
+    /// Load the subtitles of program chain `pgc` (default 0) from
+    /// `VTS_xx_0.IFO` and the title set's VOB files (concatenated).
+    #[wasm_bindgen(js_name = loadFromDvd)]
+    pub fn load_from_dvd(
+        &mut self,
+        ifo_data: &[u8],
+        vob_data: &[u8],
+        pgc: Option<usize>,
+    ) -> Result<(), JsValue> {
+        self.inner
+            .load_from_dvd(ifo_data, vob_data, pgc.unwrap_or(0))
+            .map_err(|error| JsValue::from_str(&error))
+    }
+
+    /// Load the subtitles of program chain `pgc` (default 0) from
+    /// `VTS_xx_0.IFO` and subpicture data produced by a
+    /// `DvdSubpictureDemuxer` created with `forTitle` for the same PGC.
+    #[wasm_bindgen(js_name = loadFromDvdSubpictures)]
+    pub fn load_from_dvd_subpictures(
+        &mut self,
+        ifo_data: &[u8],
+        sub_data: Vec<u8>,
+        pgc: Option<usize>,
+    ) -> Result<(), JsValue> {
+        self.inner
+            .load_from_dvd_subpictures(ifo_data, sub_data, pgc.unwrap_or(0))
             .map_err(|error| JsValue::from_str(&error))
     }
 
@@ -247,6 +799,57 @@ impl VobSubParser {
         self.inner.load_from_sub_only(sub_data);
     }
 
+    #[wasm_bindgen(js_name = loadFromSubStream)]
+    pub fn load_from_sub_stream(&mut self, sub_data: Vec<u8>, stream_id: u8) {
+        self.inner.load_from_sub_stream(sub_data, stream_id);
+    }
+
+    /// Append SUB data as it arrives; returns how many cues became renderable.
+    pub fn feed(&mut self, data: &[u8]) -> usize {
+        self.inner.feed(data)
+    }
+
+    #[wasm_bindgen(js_name = finishFeed)]
+    pub fn finish_feed(&mut self) -> usize {
+        self.inner.finish_feed()
+    }
+
+    #[wasm_bindgen(getter, js_name = pendingLen)]
+    pub fn pending_len(&self) -> usize {
+        self.inner.pending_len()
+    }
+
+    #[wasm_bindgen(js_name = getSubStreamIds)]
+    pub fn get_sub_stream_ids(&self) -> Uint8Array {
+        Uint8Array::from(self.inner.sub_stream_ids().as_slice())
+    }
+
+    #[wasm_bindgen(js_name = selectSubStream)]
+    pub fn select_sub_stream(&mut self, stream_id: u8) -> bool {
+        self.inner.select_sub_stream(stream_id)
+    }
+
+    /// Replace the rendering palette with 16 RGBA colours (packed R, G, B, A
+    /// little-endian, as returned by `getPalette`).
+    #[wasm_bindgen(js_name = setPalette)]
+    pub fn set_palette(&mut self, rgba: Vec<u32>) -> Result<(), JsValue> {
+        let rgba: [u32; 16] = rgba
+            .try_into()
+            .map_err(|_| JsValue::from_str("VobSub palette must have 16 colours"))?;
+        self.inner.set_palette(core::VobSubPalette { rgba });
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = getPalette)]
+    pub fn get_palette(&self) -> Uint32Array {
+        let rgba = self
+            .inner
+            .palette()
+            .map(|palette| palette.rgba)
+            .unwrap_or_default();
+        Uint32Array::from(rgba.as_slice())
+    }
+
     pub fn dispose(&mut self) {
         self.inner.dispose();
     }
@@ -281,6 +884,46 @@ impl VobSubParser {
         self.inner.track_id()
     }
 
+    #[wasm_bindgen(getter, js_name = trackCount)]
+    pub fn track_count(&self) -> usize {
+        self.inner.track_count()
+    }
+
+    #[wasm_bindgen(js_name = getTrackLanguage)]
+    pub fn get_track_language(&self, track: usize) -> String {
+        self.inner.track_language(track)
+    }
+
+    #[wasm_bindgen(js_name = getTrackStreamIndex)]
+    pub fn get_track_stream_index(&self, track: usize) -> i32 {
+        self.inner.track_stream_index(track)
+    }
+
+    #[wasm_bindgen(getter, js_name = defaultTrack)]
+    pub fn default_track(&self) -> usize {
+        self.inner.default_track()
+    }
+
+    #[wasm_bindgen(getter, js_name = selectedTrack)]
+    pub fn selected_track(&self) -> usize {
+        self.inner.selected_track()
+    }
+
+    #[wasm_bindgen(js_name = selectTrack)]
+    pub fn select_track(&mut self, track: usize) -> bool {
+        self.inner.select_track(track)
+    }
+
+    #[wasm_bindgen(getter, js_name = forcedSubs)]
+    pub fn forced_subs(&self) -> bool {
+        self.inner.forced_subs()
+    }
+
+    #[wasm_bindgen(js_name = getCueOpacity)]
+    pub fn get_cue_opacity(&mut self, index: usize, time_ms: f64) -> f64 {
+        self.inner.get_cue_opacity(index, time_ms)
+    }
+
     #[wasm_bindgen(getter, js_name = hasIdxMetadata)]
     pub fn has_idx_metadata(&self) -> bool {
         self.inner.has_idx_metadata()
@@ -316,6 +959,46 @@ impl VobSubParser {
         self.inner.get_cue_file_position(index)
     }
 
+    #[wasm_bindgen(js_name = isCueForced)]
+    pub fn is_cue_forced(&mut self, index: usize) -> bool {
+        self.inner.is_cue_forced(index)
+    }
+
+    #[wasm_bindgen(js_name = getForcedCueIndices)]
+    pub fn get_forced_cue_indices(&mut self) -> Uint32Array {
+        Uint32Array::from(self.inner.get_forced_cue_indices().as_slice())
+    }
+
+    #[wasm_bindgen(js_name = setForcedOnly)]
+    pub fn set_forced_only(&mut self, forced_only: bool) {
+        self.inner.set_forced_only(forced_only);
+    }
+
+    #[wasm_bindgen(getter, js_name = forcedOnly)]
+    pub fn forced_only(&self) -> bool {
+        self.inner.forced_only()
+    }
+
+    #[wasm_bindgen(js_name = getSubCueCount)]
+    pub fn get_sub_cue_count(&mut self, index: usize) -> usize {
+        self.inner.get_sub_cue_count(index)
+    }
+
+    #[wasm_bindgen(js_name = getSubCueStartTime)]
+    pub fn get_sub_cue_start_time(&mut self, index: usize, sub_index: usize) -> f64 {
+        self.inner.get_sub_cue_start_time(index, sub_index)
+    }
+
+    #[wasm_bindgen(js_name = getSubCueEndTime)]
+    pub fn get_sub_cue_end_time(&mut self, index: usize, sub_index: usize) -> f64 {
+        self.inner.get_sub_cue_end_time(index, sub_index)
+    }
+
+    #[wasm_bindgen(js_name = findSubCueAtTimestamp)]
+    pub fn find_sub_cue_at_timestamp(&mut self, index: usize, time_ms: f64) -> i32 {
+        self.inner.find_sub_cue_at_timestamp(index, time_ms)
+    }
+
     #[wasm_bindgen(js_name = renderAtIndex)]
     pub fn render_at_index(&mut self, index: usize) -> Option<VobSubFrame> {
         self.inner
@@ -323,6 +1006,25 @@ impl VobSubParser {
             .map(|inner| VobSubFrame { inner })
     }
 
+    #[wasm_bindgen(js_name = renderSubCueAtIndex)]
+    pub fn render_sub_cue_at_index(
+        &mut self,
+        index: usize,
+        sub_index: usize,
+    ) -> Option<VobSubFrame> {
+        self.inner
+            .render_sub_cue_at_index(index, sub_index)
+            .map(|inner| VobSubFrame { inner })
+    }
+
+    /// Render the display interval visible at a timestamp.
+    #[wasm_bindgen(js_name = renderAtTimestamp)]
+    pub fn render_at_timestamp(&mut self, time_seconds: f64) -> Option<VobSubFrame> {
+        self.inner
+            .render_at_timestamp(time_seconds)
+            .map(|inner| VobSubFrame { inner })
+    }
+
     #[wasm_bindgen(js_name = clearCache)]
     pub fn clear_cache(&mut self) {
         self.inner.clear_cache();
@@ -374,6 +1076,84 @@ impl DvbParser {
         self.inner.parse(data)
     }
 
+    #[wasm_bindgen(js_name = parseMatroska)]
+    pub fn parse_matroska(&mut self, data: &[u8], track: Option<u32>) -> Result<usize, JsValue> {
+        self.inner
+            .parse_matroska(data, track.map(u64::from))
+            .map_err(|error| JsValue::from_str(&error))
+    }
+
+    /// Parse an `S_DVBSUB` track of a Matroska file read through a
+    /// `MatroskaReader` range callback.
+    #[wasm_bindgen(js_name = parseMatroskaSource)]
+    pub fn parse_matroska_source(
+        &mut self,
+        read: Function,
+        length: f64,
+        track: Option<u32>,
+    ) -> Result<usize, JsValue> {
+        self.inner
+            .parse_matroska_reader(JsRangeSource::new(read, length), track.map(u64::from))
+            .map_err(|error| JsValue::from_str(&error))
+    }
+
+    /// Clear the parser before loading the DVB track selected on `reader`
+    /// with `readMatroskaNext`.
+    #[wasm_bindgen(js_name = startMatroskaReader)]
+    pub fn start_matroska_reader(&mut self, reader: &MatroskaReader) -> Result<(), JsValue> {
+        self.inner
+            .start_matroska_reader(&reader.inner)
+            .map_err(|error| JsValue::from_str(&error))
+    }
+
+    /// Read the next cluster from `reader`. Returns the number of new cues,
+    /// or `undefined` once the track is complete.
+    #[wasm_bindgen(js_name = readMatroskaNext)]
+    pub fn read_matroska_next(
+        &mut self,
+        reader: &mut MatroskaReader,
+    ) -> Result<Option<usize>, JsValue> {
+        self.inner
+            .read_matroska_next(&mut reader.inner)
+            .map_err(|error| JsValue::from_str(&error))
+    }
+
+    #[wasm_bindgen(js_name = parseTransportStream)]
+    pub fn parse_transport_stream(
+        &mut self,
+        data: &[u8],
+        pid: Option<u16>,
+    ) -> Result<usize, JsValue> {
+        self.inner
+            .parse_transport_stream(data, pid)
+            .map_err(|error| JsValue::from_str(&error))
+    }
+
+    /// Decode only the service on `compositionPageId`, sharing CLUTs and
+    /// objects from `ancillaryPageId`.
+    #[wasm_bindgen(js_name = setPageIds)]
+    pub fn set_page_ids(&mut self, composition_page_id: u16, ancillary_page_id: u16) {
+        self.inner
+            .set_page_ids(composition_page_id, ancillary_page_id)
+    }
+
+    #[wasm_bindgen(js_name = clearPageIds)]
+    pub fn clear_page_ids(&mut self) {
+        self.inner.clear_page_ids()
+    }
+
+    #[wasm_bindgen(getter, js_name = continuityErrors)]
+    pub fn continuity_errors(&self) -> usize {
+        self.inner.continuity_errors()
+    }
+
+    /// Draw character-coded objects into the rendered bitmaps with the
+    /// built-in fixed font.
+    #[wasm_bindgen(js_name = setRenderText)]
+    pub fn set_render_text(&mut self, render_text: bool) {
+        self.inner.set_render_text(render_text)
+    }
+
     pub fn reset(&mut self) {
         self.inner.reset()
     }
@@ -465,6 +1245,26 @@ impl DvbParser {
             })
     }
 
+    #[wasm_bindgen(js_name = getCueText)]
+    pub fn get_cue_text(&self, index: usize) -> Option<String> {
+        self.inner.get_cue_text(index)
+    }
+
+    #[wasm_bindgen(js_name = getCueTextRuns)]
+    pub fn get_cue_text_runs(&self, index: usize) -> Vec<DvbTextRun> {
+        self.inner
+            .get_cue_text_runs(index)
+            .unwrap_or_default()
+            .iter()
+            .cloned()
+            .map(|inner| DvbTextRun { inner })
+            .collect()
+    }
+
+    pub fn search(&self, query: &str) -> Uint32Array {
+        Uint32Array::from(self.inner.search(query).as_slice())
+    }
+
     #[wasm_bindgen(getter, js_name = lastRenderIssue)]
     pub fn last_render_issue(&self) -> String {
         self.inner.last_render_issue()
@@ -482,6 +1282,42 @@ impl Default for DvbParser {
     }
 }
 
+/// Text of a DVB character-coded object, positioned on the screen.
+#[wasm_bindgen]
+pub struct DvbTextRun {
+    inner: core::DvbTextRun,
+}
+
+#[wasm_bindgen]
+impl DvbTextRun {
+    #[wasm_bindgen(getter)]
+    pub fn x(&self) -> u16 {
+        self.inner.x
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn y(&self) -> u16 {
+        self.inner.y
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn text(&self) -> String {
+        self.inner.text.clone()
+    }
+
+    /// Foreground colour as packed RGBA.
+    #[wasm_bindgen(getter)]
+    pub fn foreground(&self) -> u32 {
+        self.inner.foreground
+    }
+
+    /// Background colour as packed RGBA.
+    #[wasm_bindgen(getter)]
+    pub fn background(&self) -> u32 {
+        self.inner.background
+    }
+}
+
 /// A VobSub subtitle frame.
 #[wasm_bindgen]
 pub struct VobSubFrame {
@@ -566,6 +1402,18 @@ impl SubtitleRenderer {
         count
     }
 
+    #[wasm_bindgen(js_name = loadPgsMks)]
+    pub fn load_pgs_mks(&mut self, mks_data: &[u8], track: Option<u32>) -> Result<usize, JsValue> {
+        self.dispose();
+        let mut parser = core::PgsParser::new();
+        let count = parser
+            .parse_matroska(mks_data, track.map(u64::from))
+            .map_err(|error| JsValue::from_str(&error))?;
+        self.pgs_parser = Some(parser);
+        self.format = Some(SubtitleFormat::Pgs);
+        Ok(count)
+    }
+
     #[wasm_bindgen(js_name = loadVobSub)]
     pub fn load_vobsub(&mut self, idx_content: &str, sub_data: Vec<u8>) {
         self.dispose();
@@ -576,17 +1424,45 @@ impl SubtitleRenderer {
     }
 
     #[wasm_bindgen(js_name = loadVobSubMks)]
-    pub fn load_vobsub_mks(&mut self, mks_data: &[u8]) -> Result<(), JsValue> {
+    pub fn load_vobsub_mks(&mut self, mks_data: &[u8], track: Option<u32>) -> Result<(), JsValue> {
         self.dispose();
         let mut parser = core::VobSubParser::new();
         parser
-            .load_from_mks(mks_data)
+            .load_from_mks(mks_data, track.map(u64::from))
             .map_err(|error| JsValue::from_str(&error))?;
         self.vobsub_parser = Some(parser);
         self.format = Some(SubtitleFormat::VobSub);
         Ok(())
     }
 
+    /// Load a subtitle track of any supported codec from Matroska/MKS data.
+    /// Without `track`, loads the default-flagged track (or the first one).
+    #[wasm_bindgen(js_name = loadMks)]
+    pub fn load_mks(&mut self, mks_data: &[u8], track: Option<u32>) -> Result<usize, JsValue> {
+        let tracks =
+            core::mkv::list_matroska_tracks(mks_data).map_err(|error| JsValue::from_str(&error))?;
+        let selected = match track {
+            Some(number) => tracks
+                .iter()
+                .find(|info| info.track.track_number == u64::from(number)),
+            None => core::mkv::default_matroska_track(&tracks),
+        }
+        .ok_or_else(|| JsValue::from_str("No supported subtitle track found in Matroska data"))?;
+        let number = Some(selected.track.track_number as u32);
+
+        match selected.track.codec_id.as_str() {
+            core::mkv::MATROSKA_CODEC_PGS => self.load_pgs_mks(mks_data, number),
+            core::mkv::MATROSKA_CODEC_DVBSUB => self.load_dvb_mks(mks_data, number),
+            core::mkv::MATROSKA_CODEC_VOBSUB => {
+                self.load_vobsub_mks(mks_data, number)?;
+                Ok(self.count())
+            }
+            other => Err(JsValue::from_str(&format!(
+                "Unsupported Matroska subtitle codec: {other}"
+            ))),
+        }
+    }
+
     #[wasm_bindgen(js_name = loadVobSubOnly)]
     pub fn load_vobsub_only(&mut self, sub_data: Vec<u8>) {
         self.dispose();
@@ -606,6 +1482,18 @@ impl SubtitleRenderer {
         count
     }
 
+    #[wasm_bindgen(js_name = loadDvbMks)]
+    pub fn load_dvb_mks(&mut self, mks_data: &[u8], track: Option<u32>) -> Result<usize, JsValue> {
+        self.dispose();
+        let mut parser = core::DvbParser::new();
+        let count = parser
+            .parse_matroska(mks_data, track.map(u64::from))
+            .map_err(|error| JsValue::from_str(&error))?;
+        self.dvb_parser = Some(parser);
+        self.format = Some(SubtitleFormat::Dvb);
+        Ok(count)
+    }
+
     #[wasm_bindgen(getter)]
     pub fn format(&self) -> Option<SubtitleFormat> {
         self.format
@@ -786,71 +1674,45 @@ impl SubtitleRenderer {
     #[wasm_bindgen(js_name = renderAtIndex)]
     pub fn render_at_index(&mut self, index: usize) -> Option<RenderResult> {
         match self.format {
-            Some(SubtitleFormat::Pgs) => {
-                let frame = self.pgs_parser.as_mut()?.render_at_index(index)?;
-                let compositions = frame
-                    .compositions
-                    .into_iter()
-                    .map(|comp| RenderComposition {
-                        x: comp.x,
-                        y: comp.y,
-                        width: comp.width,
-                        height: comp.height,
-                        rgba: comp.rgba,
-                    })
-                    .collect();
-
-                Some(RenderResult {
-                    screen_width: frame.width,
-                    screen_height: frame.height,
-                    compositions,
-                })
-            }
-            Some(SubtitleFormat::VobSub) => {
-                let frame = self.vobsub_parser.as_mut()?.render_at_index(index)?;
-                Some(RenderResult {
-                    screen_width: frame.screen_width,
-                    screen_height: frame.screen_height,
-                    compositions: vec![RenderComposition {
-                        x: frame.x,
-                        y: frame.y,
-                        width: frame.width,
-                        height: frame.height,
-                        rgba: frame.rgba,
-                    }],
-                })
-            }
-            Some(SubtitleFormat::Dvb) => {
-                let frame = self.dvb_parser.as_mut()?.render_at_index(index)?;
-                let compositions = frame
-                    .compositions
-                    .into_iter()
-                    .map(|comp| RenderComposition {
-                        x: comp.x,
-                        y: comp.y,
-                        width: comp.width,
-                        height: comp.height,
-                        rgba: comp.rgba,
-                    })
-                    .collect();
-
-                Some(RenderResult {
-                    screen_width: frame.width,
-                    screen_height: frame.height,
-                    compositions,
-                })
-            }
+            Some(SubtitleFormat::Pgs) => self
+                .pgs_parser
+                .as_mut()?
+                .render_at_index(index)
+                .map(RenderResult::from_pgs),
+            Some(SubtitleFormat::VobSub) => self
+                .vobsub_parser
+                .as_mut()?
+                .render_at_index(index)
+                .map(RenderResult::from_vobsub),
+            Some(SubtitleFormat::Dvb) => self
+                .dvb_parser
+                .as_mut()?
+                .render_at_index(index)
+                .map(RenderResult::from_dvb),
             None => None,
         }
     }
 
     #[wasm_bindgen(js_name = renderAtTimestamp)]
     pub fn render_at_timestamp(&mut self, time_seconds: f64) -> Option<RenderResult> {
-        let index = self.find_index_at_timestamp(time_seconds * 1000.0);
-        if index < 0 {
-            return None;
+        match self.format {
+            Some(SubtitleFormat::Pgs) => self
+                .pgs_parser
+                .as_mut()?
+                .render_at_timestamp(time_seconds)
+                .map(RenderResult::from_pgs),
+            Some(SubtitleFormat::VobSub) => self
+                .vobsub_parser
+                .as_mut()?
+                .render_at_timestamp(time_seconds)
+                .map(RenderResult::from_vobsub),
+            Some(SubtitleFormat::Dvb) => self
+                .dvb_parser
+                .as_mut()?
+                .render_at_timestamp(time_seconds)
+                .map(RenderResult::from_dvb),
+            None => None,
         }
-        self.render_at_index(index as usize)
     }
 
     #[wasm_bindgen(js_name = clearCache)]
@@ -896,6 +1758,62 @@ pub struct RenderResult {
     compositions: Vec<RenderComposition>,
 }
 
+impl RenderResult {
+    fn from_pgs(frame: core::SubtitleFrame) -> Self {
+        let compositions = frame
+            .compositions
+            .into_iter()
+            .map(|comp| RenderComposition {
+                x: comp.x,
+                y: comp.y,
+                width: comp.width,
+                height: comp.height,
+                rgba: comp.rgba,
+            })
+            .collect();
+
+        Self {
+            screen_width: frame.width,
+            screen_height: frame.height,
+            compositions,
+        }
+    }
+
+    fn from_vobsub(frame: core::VobSubFrame) -> Self {
+        Self {
+            screen_width: frame.screen_width,
+            screen_height: frame.screen_height,
+            compositions: vec![RenderComposition {
+                x: frame.x,
+                y: frame.y,
+                width: frame.width,
+                height: frame.height,
+                rgba: frame.rgba,
+            }],
+        }
+    }
+
+    fn from_dvb(frame: core::DvbFrame) -> Self {
+        let compositions = frame
+            .compositions
+            .into_iter()
+            .map(|comp| RenderComposition {
+                x: comp.x,
+                y: comp.y,
+                width: comp.width,
+                height: comp.height,
+                rgba: comp.rgba,
+            })
+            .collect();
+
+        Self {
+            screen_width: frame.width,
+            screen_height: frame.height,
+            compositions,
+        }
+    }
+}
+
 #[wasm_bindgen]
 impl RenderResult {
     #[wasm_bindgen(getter, js_name = screenWidth)]
@@ -213,9 +213,20 @@ export class PgsParser {
    * Render subtitle at the given timestamp in seconds.
    */
   renderAtTimestamp(timeSeconds: number): SubtitleData | undefined {
+    if (!this.parser) return undefined
+
     const index = this.findIndexAtTimestamp(timeSeconds)
     if (index < 0) return undefined
-    return this.renderAtIndex(index)
+
+    // Applies the palette keyframe active at this time, so fades play
+    const frame = this.parser.renderAtTimestamp(timeSeconds)
+    if (!frame) {
+      const warning = warningFromRenderIssue(this.getLastRenderIssue(), { format: 'pgs', cueIndex: index })
+      if (warning) this.emitWarning(warning)
+      return undefined
+    }
+
+    return this.convertFrame(frame)
   }
 
   /**
@@ -733,9 +744,20 @@ export class VobSubParserLowLevel {
    * Render subtitle at the given timestamp in seconds.
    */
   renderAtTimestamp(timeSeconds: number): SubtitleData | undefined {
+    if (!this.parser) return undefined
+
     const index = this.findIndexAtTimestamp(timeSeconds)
     if (index < 0) return undefined
-    return this.renderAtIndex(index)
+
+    // Renders the display interval active at this time
+    const frame = this.parser.renderAtTimestamp(timeSeconds)
+    if (!frame) {
+      const warning = warningFromRenderIssue(this.getLastRenderIssue(), { format: 'vobsub', cueIndex: index })
+      if (warning) this.emitWarning(warning)
+      return undefined
+    }
+
+    return this.convertFrame(frame)
   }
 
   /**